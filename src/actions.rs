@@ -0,0 +1,116 @@
+//! Moves as numbers, for reinforcement learning: every placement there could ever be gets
+//! its own slot in a fixed action space, and `legal_action_mask` says which ones the current
+//! player can make right now.
+//!
+//! An action is a piece, one of its eight orientations (in `Orientation::ALL` order), and the
+//! play area cell where the top-left of the turned piece's bounding box goes. They're
+//! numbered
+//!
+//! ```text
+//! ((piece_id * 8 + orientation) * board_dim + row) * board_dim + col
+//! ```
+//!
+//! so there are `pieces * 8 * board_dim * board_dim` of them: 67,200 for the classic set on
+//! the classic board. That only changes with the piece set or the board size, never over
+//! the course of a game.
+//!
+//! Symmetric pieces look the same in several orientations, but each placement only gets one
+//! action: the first orientation with that look, the same one `enumerate_legal_moves` picks.
+//! The other orientations' actions never show up as legal.
+
+// Nothing in the game needs these. They're for plugging it into outside learning setups.
+#![allow(dead_code)]
+
+use macroquad::prelude::*;
+
+use crate::{
+    logic::{GameState, Move},
+    piece::{self, Orientation},
+};
+
+const ORIENTATIONS: usize = Orientation::ALL.len();
+
+impl GameState {
+    /// How many actions there are in all. See the module docs for how they're numbered.
+    pub fn action_space_size(&self) -> usize {
+        let dim = self.config.board_dim;
+        self.config.pieces.len() * ORIENTATIONS * dim * dim
+    }
+
+    /// Which actions the current player could take right now, indexed by action.
+    pub fn legal_action_mask(&self) -> Vec<bool> {
+        let mut mask = vec![false; self.action_space_size()];
+        for mv in self.enumerate_legal_moves() {
+            mask[self.action_index(&mv)] = true;
+        }
+        mask
+    }
+
+    /// The action that makes `mv`, which has to land on the board.
+    pub fn action_index(&self, mv: &Move) -> usize {
+        let dim = self.config.board_dim;
+        let orientation = Orientation::ALL
+            .iter()
+            .position(|&o| o == mv.orientation)
+            .expect("Every orientation is in Orientation::ALL.");
+        let (row, col) = mv.anchor(&self.config.pieces);
+        debug_assert!((0..dim as i32).contains(&row) && (0..dim as i32).contains(&col));
+        ((mv.piece_id * ORIENTATIONS + orientation) * dim + row as usize) * dim + col as usize
+    }
+
+    /// The move `index` stands for, whether or not it's legal. `None` if it's past the end of
+    /// the action space.
+    pub fn action_index_to_move(&self, index: usize) -> Option<Move> {
+        if index >= self.action_space_size() {
+            return None;
+        }
+        let dim = self.config.board_dim;
+        let (col, rest) = (index % dim, index / dim);
+        let (row, rest) = (rest % dim, rest / dim);
+        let (orientation, piece_id) = (rest % ORIENTATIONS, rest / ORIENTATIONS);
+        let orientation = Orientation::ALL[orientation];
+
+        // The action says where the bounding box goes, but a move says where its 5x5 grid
+        // does, so back up however far into the grid the turned piece starts. That can put
+        // the grid partly off the board, which is fine.
+        let shape = orientation.apply(self.config.pieces.shape(piece_id));
+        let top = piece::cells(&shape).map(|(r, _)| r).min().unwrap_or(0);
+        let left = piece::cells(&shape).map(|(_, c)| c).min().unwrap_or(0);
+        Some(Move {
+            piece_id,
+            orientation,
+            corner: ivec2(col as i32 - left as i32, row as i32 - top as i32),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ai, logic::GameState};
+
+    #[test]
+    fn mask_matches_legal_moves() {
+        let mut game_state = GameState::new(4);
+        assert_eq!(game_state.action_space_size(), 21 * 8 * 20 * 20);
+        assert_eq!(
+            game_state.action_index_to_move(game_state.action_space_size()),
+            None
+        );
+
+        for _ in 0..12 {
+            let mask = game_state.legal_action_mask();
+            assert_eq!(mask.len(), game_state.action_space_size());
+            let legal = game_state.enumerate_legal_moves();
+            assert_eq!(mask.iter().filter(|&&legal| legal).count(), legal.len());
+            // Every legal move comes back out of its action unchanged.
+            for mv in &legal {
+                let index = game_state.action_index(mv);
+                assert!(mask[index]);
+                assert_eq!(game_state.action_index_to_move(index), Some(*mv));
+            }
+
+            let mv = ai::choose_move(&game_state).expect("Early on, there's room.");
+            game_state.apply_move(&mv);
+        }
+    }
+}
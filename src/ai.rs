@@ -0,0 +1,217 @@
+use macroquad::rand::{ChooseRandom, RandGenerator};
+use std::cmp::Reverse;
+
+use crate::logic::{GameState, Move};
+
+/// How hard the bot tries.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum Difficulty {
+    /// Plays any legal move at all.
+    Easy,
+    /// Plays the biggest piece it can (see `choose_move`).
+    #[default]
+    Medium,
+    /// Like `Medium`, but also tries to steal corners from everyone else.
+    Hard,
+}
+
+/// Picks a move for the current player, or `None` if they're stuck.
+///
+/// This bot is greedy: it puts down as many tiles as it can, and if several moves tie,
+/// it takes the one that opens up the most new corners for itself. Doesn't touch the
+/// game state, so it works just as well on a copy of a networked game.
+pub fn choose_move(game_state: &GameState) -> Option<Move> {
+    game_state
+        .enumerate_legal_moves()
+        .into_iter()
+        .max_by_key(|mv| {
+            (
+                mv.tile_count(&game_state.config.pieces),
+                game_state.corners_opened_by(mv),
+            )
+        })
+}
+
+/// Picks a move for the current player at the given `difficulty`. Any randomness comes
+/// from `rng`, so seed it yourself if you want the same game twice.
+pub fn choose_move_with(
+    game_state: &GameState,
+    difficulty: Difficulty,
+    rng: &RandGenerator,
+) -> Option<Move> {
+    match difficulty {
+        Difficulty::Easy => game_state
+            .enumerate_legal_moves()
+            .choose_with_state(rng)
+            .copied(),
+        Difficulty::Medium => choose_move(game_state),
+        Difficulty::Hard => game_state
+            .enumerate_legal_moves()
+            .into_iter()
+            .max_by_key(|mv| rank(game_state, Difficulty::Hard, mv)),
+    }
+}
+
+/// How much the bot at `difficulty` likes `mv`. Bigger is better.
+fn rank(game_state: &GameState, difficulty: Difficulty, mv: &Move) -> (usize, usize, usize) {
+    let tiles = mv.tile_count(&game_state.config.pieces);
+    match difficulty {
+        Difficulty::Easy => (0, 0, 0),
+        Difficulty::Medium => (tiles, 0, game_state.corners_opened_by(mv)),
+        // Only looks one move ahead: whatever corners we cover now are corners nobody
+        // else gets to use on their next turn.
+        Difficulty::Hard => (
+            tiles,
+            game_state.corners_blocked_by(mv),
+            game_state.corners_opened_by(mv),
+        ),
+    }
+}
+
+/// Like `choose_move_with`, but gives up weighing moves once `budget` seconds run out and
+/// goes with the best one it's seen, so a slow bot can't hold up the frame. The clock starts
+/// before the moves are listed, so that counts against the budget too. Moves get weighed
+/// biggest pieces first, so running out of time costs tie-breaks, not tiles. There's always
+/// a move if the player has one, however short the budget. `Easy` has no `rng` to pick with,
+/// so it takes the last one it gets to. `now` tells the time in seconds; pass macroquad's
+/// `get_time`, which unlike `std::time` works in the browser too.
+pub fn choose_move_timed(
+    game_state: &GameState,
+    difficulty: Difficulty,
+    budget: f64,
+    mut now: impl FnMut() -> f64,
+) -> Option<Move> {
+    let deadline = now() + budget;
+    let mut moves = game_state.enumerate_legal_moves();
+    // Stable, so equal ranks stay in the order `max_by_key` would see them.
+    moves.sort_by_key(|mv| Reverse(mv.tile_count(&game_state.config.pieces)));
+    let mut moves = moves.into_iter();
+    let mut best = moves.next()?;
+    let mut best_rank = rank(game_state, difficulty, &best);
+    for mv in moves {
+        if now() >= deadline {
+            break;
+        }
+        // Ties go to the later move, same as `max_by_key`, so a big enough budget picks
+        // what `choose_move_with` would.
+        let mv_rank = rank(game_state, difficulty, &mv);
+        if mv_rank >= best_rank {
+            (best, best_rank) = (mv, mv_rank);
+        }
+    }
+    Some(best)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use macroquad::prelude::*;
+
+    fn play(game_state: &mut GameState, mv: Move) {
+        game_state.select_piece(Some(mv.piece_id));
+        game_state.piece_buffer = mv.shape(&game_state.config.pieces);
+        assert!(game_state.valid_move(mv.corner + IVec2::ONE));
+        game_state.place_piece(mv.corner).unwrap();
+        game_state.end_turn();
+    }
+
+    #[test]
+    fn bot_only_plays_legal_moves() {
+        let mut game_state = GameState::new(4);
+        // Let the bot play against itself for a while so it sees a crowded board.
+        for _ in 0..24 {
+            let Some(mv) = choose_move(&game_state) else {
+                game_state.end_turn();
+                continue;
+            };
+            assert!(game_state.enumerate_legal_moves().contains(&mv));
+            play(&mut game_state, mv);
+        }
+    }
+
+    #[test]
+    fn bot_plays_big_pieces_first() {
+        let game_state = GameState::new(2);
+        let mv = choose_move(&game_state).expect("The empty board has room.");
+        assert_eq!(mv.tile_count(&game_state.config.pieces), 5);
+    }
+
+    #[test]
+    fn easy_bot_plays_legal_moves() {
+        let rng = RandGenerator::new();
+        rng.srand(42);
+        let mut game_state = GameState::new(4);
+        for _ in 0..24 {
+            let Some(mv) = choose_move_with(&game_state, Difficulty::Easy, &rng) else {
+                game_state.end_turn();
+                continue;
+            };
+            assert!(game_state.enumerate_legal_moves().contains(&mv));
+            play(&mut game_state, mv);
+        }
+    }
+
+    #[test]
+    fn timed_bot_always_has_an_answer() {
+        let rng = RandGenerator::new();
+        let mut game_state = GameState::new(4);
+        for _ in 0..12 {
+            // A clock that loses a millisecond every time it's looked at.
+            let mut ticks = 0.;
+            let mut clock = || {
+                ticks += 0.001;
+                ticks
+            };
+            let rushed = choose_move_timed(&game_state, Difficulty::Hard, 0.001, &mut clock)
+                .expect("It's early, there's room.");
+            let patient =
+                choose_move_timed(&game_state, Difficulty::Hard, 3600., &mut clock).unwrap();
+
+            let legal = game_state.enumerate_legal_moves();
+            assert!(legal.contains(&rushed));
+            // Even in a hurry, it doesn't settle for a smaller piece.
+            let tiles = |mv: &Move| mv.tile_count(&game_state.config.pieces);
+            assert_eq!(tiles(&rushed), legal.iter().map(tiles).max().unwrap());
+            assert!(
+                rank(&game_state, Difficulty::Hard, &patient)
+                    >= rank(&game_state, Difficulty::Hard, &rushed)
+            );
+            assert_eq!(
+                Some(patient),
+                choose_move_with(&game_state, Difficulty::Hard, &rng)
+            );
+            play(&mut game_state, patient);
+        }
+    }
+
+    #[test]
+    fn hard_bot_blocks_when_sizes_tie() {
+        let rng = RandGenerator::new();
+        let mut game_state = GameState::new(4);
+        let mut blocked_something = false;
+
+        for _ in 0..20 {
+            let Some(mv) = choose_move_with(&game_state, Difficulty::Hard, &rng) else {
+                game_state.end_turn();
+                continue;
+            };
+            let blocked = game_state.corners_blocked_by(&mv);
+            // Nothing just as big could have blocked more.
+            let pieces = &game_state.config.pieces;
+            assert!(game_state
+                .enumerate_legal_moves()
+                .iter()
+                .filter(|other| other.tile_count(pieces) == mv.tile_count(pieces))
+                .all(|other| game_state.corners_blocked_by(other) <= blocked));
+            blocked_something |= blocked > 0;
+            play(&mut game_state, mv);
+        }
+
+        assert!(blocked_something);
+    }
+}
@@ -0,0 +1,127 @@
+//! The pictures and music the game ships with. A broken one gets logged and left out,
+//! instead of taking the whole game down with it.
+
+use macroquad::{
+    audio::{load_sound, play_sound, PlaySoundParams},
+    prelude::*,
+};
+
+/// A picture that came with the game, or what to show in its place if it wouldn't load.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Art {
+    Loaded(Texture2D),
+    /// A flat `fill` with `caption` written near the top.
+    Placeholder {
+        fill: Color,
+        caption: &'static str,
+    },
+}
+
+impl Art {
+    /// Decodes `bytes` as a PNG. If that doesn't work, says so and hands back `placeholder`.
+    pub fn from_png(bytes: &[u8], name: &str, placeholder: Art) -> Self {
+        match image::load_from_memory_with_format(bytes, image::ImageFormat::Png) {
+            Ok(decoded) => {
+                let decoded = decoded.to_rgba8();
+                let (width, height) = (decoded.width(), decoded.height());
+                match (u16::try_from(width), u16::try_from(height)) {
+                    (Ok(width), Ok(height)) => {
+                        Self::Loaded(Texture2D::from_rgba8(width, height, &decoded))
+                    }
+                    _ => {
+                        eprintln!("{name} is too big to draw ({width}x{height})");
+                        placeholder
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to load {name} -- {e}");
+                placeholder
+            }
+        }
+    }
+
+    /// Stretches it over the whole window.
+    pub fn draw_fullscreen(&self, text_color: Color) {
+        match *self {
+            Self::Loaded(texture) => {
+                let draw_params = DrawTextureParams {
+                    dest_size: Some(vec2(screen_width(), screen_height())),
+                    ..Default::default()
+                };
+                draw_texture_ex(texture, 0., 0., WHITE, draw_params);
+            }
+            Self::Placeholder { fill, caption } => {
+                clear_background(fill);
+                let font_size = 0.1 * screen_height();
+                let dims = measure_text(caption, None, font_size as u16, 1.);
+                draw_text(
+                    caption,
+                    0.5 * (screen_width() - dims.width),
+                    0.25 * screen_height(),
+                    font_size,
+                    text_color,
+                );
+            }
+        }
+    }
+}
+
+/// Everything the game draws that it didn't draw itself.
+pub struct Assets {
+    /// Behind the standings once the game's over.
+    pub win: Art,
+}
+
+impl Assets {
+    /// Needs the window up, since the pictures go straight to the GPU.
+    pub fn load() -> Self {
+        Self {
+            win: Art::from_png(
+                include_bytes!("../assets/WIN.png"),
+                "the win screen",
+                Art::Placeholder {
+                    fill: GOLD,
+                    caption: "Game over!",
+                },
+            ),
+        }
+    }
+}
+
+/// Starts the music looping, if it'll load. The game's just quieter if it won't.
+pub async fn play_music() {
+    match load_sound("assets/SneakySnitch.ogg").await {
+        Ok(music) => play_sound(
+            music,
+            PlaySoundParams {
+                looped: true,
+                volume: 1.,
+            },
+        ),
+        Err(e) => eprintln!("Failed to load epic music :( -- {e}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bad_art_falls_back() {
+        let placeholder = Art::Placeholder {
+            fill: GOLD,
+            caption: "Game over!",
+        };
+        // Bad bytes never get as far as the GPU, so this works without a window.
+        assert_eq!(
+            Art::from_png(b"not a png", "junk", placeholder),
+            placeholder
+        );
+        let truncated = &include_bytes!("../assets/WIN.png")[..64];
+        assert_eq!(
+            Art::from_png(truncated, "half a png", placeholder),
+            placeholder
+        );
+    }
+}
@@ -0,0 +1,84 @@
+//! The board as bit masks, one `u64` per row, so checking where a piece can go takes a few
+//! ANDs and shifts per row instead of a look at every cell. `GameState::board` is still the
+//! real board; this just follows along.
+
+use crate::logic::TileColor;
+
+/// One mask per row for each kind of tile. Bit `col` of a row's mask is set if the cell at
+/// that column has that kind of tile.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitBoard {
+    width: usize,
+    walls: Vec<u64>,
+    /// Cells with any player's tile in them.
+    occupied: Vec<u64>,
+    /// The same, one color at a time, in `color_index` order.
+    colors: [Vec<u64>; 4],
+}
+
+impl BitBoard {
+    /// The widest board a row mask can hold, leaving room to shift a piece's five columns
+    /// past either end.
+    pub const MAX_WIDTH: usize = 59;
+
+    /// Masks for `board`, which has to be square and no wider than `MAX_WIDTH`.
+    pub fn from_board(board: &[Vec<TileColor>]) -> Self {
+        let width = board.len();
+        assert!(
+            width <= Self::MAX_WIDTH,
+            "A {width} cell wide board doesn't fit in a bitboard."
+        );
+        let mut bitboard = Self {
+            width,
+            walls: vec![0; width],
+            occupied: vec![0; width],
+            colors: std::array::from_fn(|_| vec![0; width]),
+        };
+        for (row, tiles) in board.iter().enumerate() {
+            for (col, &tile) in tiles.iter().enumerate() {
+                bitboard.set(row, col, tile);
+            }
+        }
+        bitboard
+    }
+
+    /// Puts `tile` at (`row`, `col`). Only ever fills cells in, the way tiles go on the board.
+    pub fn set(&mut self, row: usize, col: usize, tile: TileColor) {
+        let bit = 1 << col;
+        match color_index(tile) {
+            Some(idx) => {
+                self.occupied[row] |= bit;
+                self.colors[idx][row] |= bit;
+            }
+            None if tile == TileColor::Wall => self.walls[row] |= bit,
+            None => {}
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn walls(&self, row: usize) -> u64 {
+        self.walls[row]
+    }
+
+    pub fn occupied(&self, row: usize) -> u64 {
+        self.occupied[row]
+    }
+
+    /// `color`'s tiles in `row`. Walls and empty cells don't have any.
+    pub fn color(&self, row: usize, color: TileColor) -> u64 {
+        color_index(color).map_or(0, |idx| self.colors[idx][row])
+    }
+}
+
+fn color_index(tile: TileColor) -> Option<usize> {
+    match tile {
+        TileColor::Red => Some(0),
+        TileColor::Yellow => Some(1),
+        TileColor::Green => Some(2),
+        TileColor::Blue => Some(3),
+        TileColor::Empty | TileColor::Wall => None,
+    }
+}
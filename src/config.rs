@@ -0,0 +1,198 @@
+use crate::piece::PieceSet;
+
+/// Describes the board a game is played on, along with any house rules.
+///
+/// Start from one of the presets and tweak it builder-style:
+/// `GameConfig::classic().allow_monominoes_first(false)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameConfig {
+    /// Width (and height) of the play area, in tiles.
+    pub board_dim: usize,
+    /// (row, col) of each cell in the play area some player's first piece has to cover,
+    /// listed clockwise. Players are spread out evenly over these, so two players
+    /// on a board with four corners sit across from each other.
+    pub start_corners: Vec<(usize, usize)>,
+    /// Which way play goes around the board. Seats follow it, so whoever's up next always
+    /// sits at the next corner over.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub seating: Seating,
+    /// Whether a player may open with the single-tile piece.
+    pub allow_monominoes_first: bool,
+    /// Whether the first piece has to cover the player's start corner. If not, it can go anywhere.
+    pub first_move_must_cover_corner: bool,
+    /// Whether a player may pass even when they have a move. If not, they only pass when stuck.
+    pub allow_voluntary_pass: bool,
+    /// How long each turn may take before the player is passed automatically, if there's
+    /// a limit at all.
+    pub turn_seconds: Option<u32>,
+    /// Whether the second player may take over the first player's opening instead of
+    /// answering it, to make up for going second. Only two-player games offer this.
+    pub pie_rule: bool,
+    /// Whether players draw their pieces one at a time in a shuffled order instead of
+    /// picking from all of them. They have to play the first one that fits (see
+    /// `GameState::drawn_piece`), and only pass once none do.
+    pub draft_mode: bool,
+    /// Seeds the shuffle for `draft_mode`, so everyone who has this config draws the same
+    /// pieces in the same order -- networked players included.
+    pub draft_seed: u64,
+    /// With three players, whether the fourth color gets played too, by each of them in
+    /// turn, so nobody gets a corner of the board to themselves.
+    pub three_player_neutral: bool,
+    /// A game for one, to practice against yourself: any start corner will do, and it's
+    /// over once nothing else fits. Scored by how many tiles made it onto the board.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub solitaire: bool,
+    /// How many pieces a player puts down before their turn ends. Once they've put one down
+    /// they can end it early, and it ends on its own if nothing else fits.
+    #[cfg_attr(feature = "serde", serde(default = "one_piece_per_turn"))]
+    pub pieces_per_turn: usize,
+    /// Whether each piece a player puts down has to be no bigger than their last one, the
+    /// way some people teach the game. Their first piece can be anything.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub descending_size: bool,
+    /// The pieces everyone gets. Saves and JSON only know the classic set for now, so
+    /// custom ones don't survive them.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub pieces: PieceSet,
+}
+
+impl GameConfig {
+    /// The usual 20x20 board, starting from the four corners in clockwise order.
+    pub fn classic() -> Self {
+        Self {
+            board_dim: 20,
+            start_corners: vec![(19, 19), (19, 0), (0, 0), (0, 19)],
+            seating: Seating::Clockwise,
+            allow_monominoes_first: true,
+            first_move_must_cover_corner: true,
+            allow_voluntary_pass: false,
+            turn_seconds: None,
+            pie_rule: false,
+            draft_mode: false,
+            draft_seed: 0,
+            three_player_neutral: false,
+            solitaire: false,
+            pieces_per_turn: 1,
+            descending_size: false,
+            pieces: PieceSet::classic(),
+        }
+    }
+
+    /// Blokus Duo: a two-player game on a 14x14 board, starting near the middle.
+    pub fn duo() -> Self {
+        Self::classic()
+            .board_dim(14)
+            .start_corners(vec![(4, 4), (9, 9)])
+    }
+
+    pub fn board_dim(mut self, board_dim: usize) -> Self {
+        self.board_dim = board_dim;
+        self
+    }
+
+    pub fn start_corners(mut self, start_corners: Vec<(usize, usize)>) -> Self {
+        self.start_corners = start_corners;
+        self
+    }
+
+    pub fn seating(mut self, seating: Seating) -> Self {
+        self.seating = seating;
+        self
+    }
+
+    pub fn allow_monominoes_first(mut self, allow: bool) -> Self {
+        self.allow_monominoes_first = allow;
+        self
+    }
+
+    pub fn first_move_must_cover_corner(mut self, must_cover: bool) -> Self {
+        self.first_move_must_cover_corner = must_cover;
+        self
+    }
+
+    pub fn allow_voluntary_pass(mut self, allow: bool) -> Self {
+        self.allow_voluntary_pass = allow;
+        self
+    }
+
+    pub fn turn_seconds(mut self, seconds: Option<u32>) -> Self {
+        self.turn_seconds = seconds;
+        self
+    }
+
+    pub fn pie_rule(mut self, enabled: bool) -> Self {
+        self.pie_rule = enabled;
+        self
+    }
+
+    pub fn draft_mode(mut self, enabled: bool) -> Self {
+        self.draft_mode = enabled;
+        self
+    }
+
+    pub fn draft_seed(mut self, seed: u64) -> Self {
+        self.draft_seed = seed;
+        self
+    }
+
+    pub fn three_player_neutral(mut self, enabled: bool) -> Self {
+        self.three_player_neutral = enabled;
+        self
+    }
+
+    pub fn solitaire(mut self, enabled: bool) -> Self {
+        self.solitaire = enabled;
+        self
+    }
+
+    pub fn pieces_per_turn(mut self, count: usize) -> Self {
+        self.pieces_per_turn = count.max(1);
+        self
+    }
+
+    pub fn descending_size(mut self, enabled: bool) -> Self {
+        self.descending_size = enabled;
+        self
+    }
+
+    // Only custom piece sets need this, and nothing in the game offers one yet.
+    #[allow(dead_code)]
+    pub fn pieces(mut self, pieces: PieceSet) -> Self {
+        self.pieces = pieces;
+        self
+    }
+}
+
+impl Default for GameConfig {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
+/// Which way turns go around the board.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Seating {
+    /// Down the `start_corners` list, the way the game has always gone.
+    #[default]
+    Clockwise,
+    /// Up the list instead, wrapping around from the first corner to the last.
+    CounterClockwise,
+}
+
+impl Seating {
+    /// The other way, for the toggle on the setup screen.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Clockwise => Self::CounterClockwise,
+            Self::CounterClockwise => Self::Clockwise,
+        }
+    }
+}
+
+/// What configs from before `pieces_per_turn` existed get.
+#[cfg(feature = "serde")]
+fn one_piece_per_turn() -> usize {
+    1
+}
@@ -0,0 +1,180 @@
+//! JSON for tools that live outside the game, like analysis scripts and bots written in
+//! other languages. Only built with the `serde` feature.
+//!
+//! The board comes out as rows of the same characters `TileColor` displays as, walls and all,
+//! and each player's pieces as a sorted list of piece IDs.
+
+use serde::{Deserialize, Serialize};
+use smallvec::SmallVec;
+use std::fmt;
+
+use crate::{
+    config::GameConfig,
+    logic::{GameState, Player, SetupError, TileColor},
+};
+
+/// Everything `to_json` writes out. Like a save, it leaves out whatever the current player
+/// happens to be holding.
+#[derive(Serialize, Deserialize)]
+struct GameStateView {
+    config: GameConfig,
+    current_player: usize,
+    pass_counter: usize,
+    neutral_turns: usize,
+    #[serde(default)]
+    placements_this_turn: usize,
+    players: Vec<Player>,
+    board: Vec<Vec<TileColor>>,
+}
+
+/// Why some JSON couldn't be turned back into a game.
+#[derive(Debug)]
+pub enum JsonError {
+    /// It isn't JSON, or it's missing something.
+    Json(serde_json::Error),
+    /// It's well-formed, but doesn't add up to a game (the board is the wrong size, say).
+    Malformed(&'static str),
+    /// The players and board don't make for a game we can set up.
+    Setup(SetupError),
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Json(e) => write!(f, "{e}"),
+            Self::Malformed(what) => write!(f, "the {what} doesn't fit the game"),
+            Self::Setup(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl GameState {
+    pub fn to_json(&self) -> String {
+        let view = GameStateView {
+            config: self.config.clone(),
+            current_player: self.current_player,
+            pass_counter: self.pass_counter,
+            neutral_turns: self.neutral_turns,
+            placements_this_turn: self.placements_this_turn,
+            players: self.players.to_vec(),
+            board: self.board.clone(),
+        };
+        serde_json::to_string(&view).expect("Nothing in a game can fail to serialize.")
+    }
+
+    /// The inverse of `to_json`.
+    // The game only ever writes JSON. Reading it back is for the tools on the other end.
+    #[allow(dead_code)]
+    pub fn from_json(json: &str) -> Result<GameState, JsonError> {
+        let view: GameStateView = serde_json::from_str(json).map_err(JsonError::Json)?;
+        if view.current_player >= view.players.len() {
+            return Err(JsonError::Malformed("current player"));
+        }
+        let width = view.config.board_dim + 2;
+        if view.board.len() != width || view.board.iter().any(|row| row.len() != width) {
+            return Err(JsonError::Malformed("board"));
+        }
+
+        let players = SmallVec::from_vec(view.players);
+        let mut game_state =
+            GameState::try_with_players(players, view.config).map_err(JsonError::Setup)?;
+        game_state.board = view.board;
+        game_state.current_player = view.current_player;
+        if view.pass_counter > game_state.players.len() {
+            return Err(JsonError::Malformed("pass_counter"));
+        }
+        game_state.pass_counter = view.pass_counter;
+        game_state.neutral_turns = view.neutral_turns;
+        if view.placements_this_turn >= game_state.config.pieces_per_turn {
+            return Err(JsonError::Malformed("placements_this_turn"));
+        }
+        game_state.placements_this_turn = view.placements_this_turn;
+        game_state.refresh_frontiers();
+        Ok(game_state)
+    }
+}
+
+impl From<TileColor> for char {
+    fn from(tile: TileColor) -> char {
+        tile.to_string()
+            .chars()
+            .next()
+            .expect("Every tile has a character.")
+    }
+}
+
+impl TryFrom<char> for TileColor {
+    type Error = String;
+
+    fn try_from(c: char) -> Result<Self, Self::Error> {
+        TileColor::from_char(c).ok_or_else(|| format!("'{c}' isn't a tile"))
+    }
+}
+
+/// (De)serializes a set of pieces as a sorted list of their IDs.
+pub mod piece_set {
+    use bit_set::BitSet;
+    use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+    use crate::{logic::PieceID, piece};
+
+    pub fn serialize<S: Serializer>(
+        pieces: &BitSet<PieceID>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        // `BitSet` already iterates in order.
+        serializer.collect_seq(pieces.iter().map(|id| id as u8))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<BitSet<PieceID>, D::Error> {
+        let ids = Vec::<u8>::deserialize(deserializer)?;
+        match ids.iter().find(|&&id| id as usize >= piece::SHAPES.len()) {
+            Some(id) => Err(D::Error::custom(format!("there's no piece {id}"))),
+            None => Ok(ids.into_iter().map(|id| id as usize).collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai;
+
+    #[test]
+    fn json_round_trip() {
+        let mut game_state = GameState::new(3);
+        for _ in 0..5 {
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            game_state.apply_move(&mv);
+        }
+        game_state.players[1].is_bot = true;
+        game_state.pass_counter = 1;
+
+        let json = game_state.to_json();
+        let loaded = GameState::from_json(&json).expect("We just wrote this.");
+        assert_eq!(loaded.board, game_state.board);
+        assert_eq!(loaded.current_player, game_state.current_player);
+        assert_eq!(loaded.pass_counter, 1);
+        assert_eq!(loaded.config, game_state.config);
+        for (a, b) in loaded.players.iter().zip(&game_state.players) {
+            assert_eq!(a.color, b.color);
+            assert_eq!(a.remaining_pieces, b.remaining_pieces);
+            assert_eq!(a.is_bot, b.is_bot);
+            assert_eq!(a.last_piece, b.last_piece);
+            assert_eq!(a.pieces_placed, b.pieces_placed);
+        }
+        assert_eq!(loaded.to_json(), json);
+        assert_eq!(
+            loaded.enumerate_legal_moves(),
+            game_state.enumerate_legal_moves()
+        );
+    }
+
+    #[test]
+    fn fresh_game_snapshot() {
+        let json = GameState::new(4).to_json();
+        assert_eq!(json, include_str!("snapshots/fresh_game.json").trim_end());
+    }
+}
@@ -0,0 +1,323 @@
+//! Which keys do what while placing a piece or looking around the board, so anyone can move
+//! them somewhere that suits their hands or their keyboard.
+//!
+//! Bindings are written down one action a line, the action's name and then its keys:
+//!
+//! ```text
+//! rotate-left Q PageUp
+//! commit Enter
+//! ```
+//!
+//! Actions a file leaves out keep their usual keys.
+
+use macroquad::prelude::KeyCode;
+use smallvec::SmallVec;
+use std::fmt::{self, Write};
+
+/// Something a key can do.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    FlipHorizontal,
+    FlipVertical,
+    RotateLeft,
+    RotateRight,
+    /// Put the piece down where it's aimed, like clicking there.
+    Commit,
+    /// Let go of the piece in hand.
+    Cancel,
+    /// Pick up the next piece in the tray. Holding Shift goes the other way.
+    NextPiece,
+    PrevPiece,
+    /// Put the board back the way it started, unzoomed and centered.
+    ResetView,
+    /// Slide the board around while zoomed in. These share keys with the flips, so they
+    /// only work with nothing in hand.
+    PanUp,
+    PanLeft,
+    PanDown,
+    PanRight,
+    /// Write the game out to the dump slot, for attaching to a bug report.
+    DumpState,
+}
+
+impl Action {
+    pub const ALL: [Action; 14] = [
+        Self::FlipHorizontal,
+        Self::FlipVertical,
+        Self::RotateLeft,
+        Self::RotateRight,
+        Self::Commit,
+        Self::Cancel,
+        Self::NextPiece,
+        Self::PrevPiece,
+        Self::ResetView,
+        Self::PanUp,
+        Self::PanLeft,
+        Self::PanDown,
+        Self::PanRight,
+        Self::DumpState,
+    ];
+
+    /// What it's called in a bindings file.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::FlipHorizontal => "flip-horizontal",
+            Self::FlipVertical => "flip-vertical",
+            Self::RotateLeft => "rotate-left",
+            Self::RotateRight => "rotate-right",
+            Self::Commit => "commit",
+            Self::Cancel => "cancel",
+            Self::NextPiece => "next-piece",
+            Self::PrevPiece => "prev-piece",
+            Self::ResetView => "reset-view",
+            Self::PanUp => "pan-up",
+            Self::PanLeft => "pan-left",
+            Self::PanDown => "pan-down",
+            Self::PanRight => "pan-right",
+            Self::DumpState => "dump-state",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+}
+
+/// The keys a binding can use. Macroquad's `KeyCode` can't be read back from a string, so
+/// this is what bindings files get checked against. Digits and arrows are left out, since
+/// they already pick pieces and move the cursor.
+const BINDABLE_KEYS: &[KeyCode] = &[
+    KeyCode::A,
+    KeyCode::B,
+    KeyCode::C,
+    KeyCode::D,
+    KeyCode::E,
+    KeyCode::F,
+    KeyCode::G,
+    KeyCode::H,
+    KeyCode::I,
+    KeyCode::J,
+    KeyCode::K,
+    KeyCode::L,
+    KeyCode::M,
+    KeyCode::N,
+    KeyCode::O,
+    KeyCode::P,
+    KeyCode::Q,
+    KeyCode::R,
+    KeyCode::S,
+    KeyCode::T,
+    KeyCode::U,
+    KeyCode::V,
+    KeyCode::W,
+    KeyCode::X,
+    KeyCode::Y,
+    KeyCode::Z,
+    KeyCode::Space,
+    KeyCode::Enter,
+    KeyCode::Escape,
+    KeyCode::Tab,
+    KeyCode::Backspace,
+    KeyCode::Insert,
+    KeyCode::Delete,
+    KeyCode::Home,
+    KeyCode::End,
+    KeyCode::PageUp,
+    KeyCode::PageDown,
+    KeyCode::LeftBracket,
+    KeyCode::RightBracket,
+    KeyCode::Backslash,
+    KeyCode::Comma,
+    KeyCode::Period,
+    KeyCode::Slash,
+    KeyCode::Semicolon,
+    KeyCode::Apostrophe,
+    KeyCode::Minus,
+    KeyCode::Equal,
+    KeyCode::GraveAccent,
+    KeyCode::Kp0,
+    KeyCode::Kp1,
+    KeyCode::Kp2,
+    KeyCode::Kp3,
+    KeyCode::Kp4,
+    KeyCode::Kp5,
+    KeyCode::Kp6,
+    KeyCode::Kp7,
+    KeyCode::Kp8,
+    KeyCode::Kp9,
+    KeyCode::KpDecimal,
+    KeyCode::KpEnter,
+    KeyCode::KpAdd,
+    KeyCode::KpSubtract,
+    KeyCode::F1,
+    KeyCode::F2,
+    KeyCode::F3,
+    KeyCode::F4,
+    KeyCode::F5,
+    KeyCode::F6,
+    KeyCode::F7,
+    KeyCode::F8,
+    KeyCode::F9,
+    KeyCode::F10,
+    KeyCode::F11,
+    KeyCode::F12,
+];
+
+/// What a key is called in a bindings file, which is just what macroquad calls it.
+fn key_name(key: KeyCode) -> String {
+    format!("{key:?}")
+}
+
+fn key_from_name(name: &str) -> Option<KeyCode> {
+    BINDABLE_KEYS
+        .iter()
+        .copied()
+        .find(|&key| key_name(key) == name)
+}
+
+/// What went wrong reading a bindings file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BindingsError {
+    UnknownAction(String),
+    UnknownKey(String),
+}
+
+impl fmt::Display for BindingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownAction(name) => write!(f, "there's no action called \"{name}\""),
+            Self::UnknownKey(name) => write!(f, "\"{name}\" isn't a key that can be bound"),
+        }
+    }
+}
+
+/// The keys for each `Action`. More than one key can do the same thing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyBindings {
+    /// In `Action::ALL` order.
+    keys: [SmallVec<[KeyCode; 2]>; Action::ALL.len()],
+}
+
+impl KeyBindings {
+    /// The keys the game has always used.
+    pub fn new() -> Self {
+        let mut bindings = Self {
+            keys: Default::default(),
+        };
+        bindings.bind(Action::FlipHorizontal, &[KeyCode::A, KeyCode::D]);
+        bindings.bind(Action::FlipVertical, &[KeyCode::W, KeyCode::S]);
+        bindings.bind(Action::RotateLeft, &[KeyCode::Q, KeyCode::PageUp]);
+        bindings.bind(Action::RotateRight, &[KeyCode::E, KeyCode::PageDown]);
+        bindings.bind(Action::Commit, &[KeyCode::Enter]);
+        bindings.bind(Action::Cancel, &[KeyCode::Escape]);
+        // `[` and `]` are there for anyone whose Tab key belongs to the window manager.
+        bindings.bind(Action::NextPiece, &[KeyCode::Tab, KeyCode::RightBracket]);
+        bindings.bind(Action::PrevPiece, &[KeyCode::LeftBracket]);
+        bindings.bind(Action::ResetView, &[KeyCode::R]);
+        bindings.bind(Action::PanUp, &[KeyCode::W]);
+        bindings.bind(Action::PanLeft, &[KeyCode::A]);
+        bindings.bind(Action::PanDown, &[KeyCode::S]);
+        bindings.bind(Action::PanRight, &[KeyCode::D]);
+        bindings.bind(Action::DumpState, &[KeyCode::F12]);
+        bindings
+    }
+
+    fn slot(action: Action) -> usize {
+        Action::ALL
+            .iter()
+            .position(|&a| a == action)
+            .expect("Every action is in Action::ALL.")
+    }
+
+    /// Swaps out whatever keys `action` had for `keys`.
+    pub fn bind(&mut self, action: Action, keys: &[KeyCode]) {
+        self.keys[Self::slot(action)] = keys.iter().copied().collect();
+    }
+
+    pub fn keys(&self, action: Action) -> &[KeyCode] {
+        &self.keys[Self::slot(action)]
+    }
+
+    /// Whether any of `action`'s keys are `pressed`. Pass macroquad's `is_key_pressed`.
+    pub fn fired(&self, action: Action, pressed: impl Fn(KeyCode) -> bool) -> bool {
+        self.keys(action).iter().any(|&key| pressed(key))
+    }
+
+    /// Writes these out the way `from_bindings_string` reads them.
+    pub fn to_bindings_string(&self) -> String {
+        let mut text = String::new();
+        for action in Action::ALL {
+            text.push_str(action.name());
+            for &key in self.keys(action) {
+                write!(text, " {}", key_name(key)).unwrap();
+            }
+            text.push('\n');
+        }
+        text
+    }
+
+    /// Reads bindings written by `to_bindings_string`, or by hand.
+    pub fn from_bindings_string(text: &str) -> Result<Self, BindingsError> {
+        let mut bindings = Self::new();
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            let Some(name) = fields.next() else {
+                continue;
+            };
+            let action = Action::from_name(name)
+                .ok_or_else(|| BindingsError::UnknownAction(name.to_string()))?;
+            let keys = fields
+                .map(|name| {
+                    key_from_name(name).ok_or_else(|| BindingsError::UnknownKey(name.into()))
+                })
+                .collect::<Result<SmallVec<[KeyCode; 2]>, _>>()?;
+            bindings.bind(action, &keys);
+        }
+        Ok(bindings)
+    }
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebound_keys_fire_their_action() {
+        let mut bindings = KeyBindings::new();
+        let pressing = |pressed: KeyCode| move |key: KeyCode| key == pressed;
+        assert!(bindings.fired(Action::RotateLeft, pressing(KeyCode::Q)));
+
+        bindings.bind(Action::RotateLeft, &[KeyCode::Z]);
+        assert!(bindings.fired(Action::RotateLeft, pressing(KeyCode::Z)));
+        assert!(!bindings.fired(Action::RotateLeft, pressing(KeyCode::Q)));
+        // Nothing else picked up the new key.
+        for action in Action::ALL {
+            if action != Action::RotateLeft {
+                assert!(!bindings.fired(action, pressing(KeyCode::Z)));
+            }
+        }
+
+        // It sticks through a bindings file, and hand-written ones only change what they say.
+        let text = bindings.to_bindings_string();
+        assert!(text.contains("rotate-left Z\n"));
+        assert_eq!(KeyBindings::from_bindings_string(&text), Ok(bindings));
+        let lefty = KeyBindings::from_bindings_string("commit Space\npan-up I\n\n").unwrap();
+        assert_eq!(lefty.keys(Action::Commit), [KeyCode::Space]);
+        assert_eq!(lefty.keys(Action::Cancel), [KeyCode::Escape]);
+        assert_eq!(lefty.keys(Action::PanUp), [KeyCode::I]);
+        assert_eq!(lefty.keys(Action::ResetView), [KeyCode::R]);
+        assert_eq!(lefty.keys(Action::DumpState), [KeyCode::F12]);
+        let moved = KeyBindings::from_bindings_string("dump-state F9").unwrap();
+        assert_eq!(moved.keys(Action::DumpState), [KeyCode::F9]);
+        assert_eq!(
+            KeyBindings::from_bindings_string("rotate-left Key1"),
+            Err(BindingsError::UnknownKey("Key1".into()))
+        );
+    }
+}
@@ -0,0 +1,91 @@
+//! Color bookkeeping for a room full of players who haven't started yet.
+//!
+//! Everything here keeps every player's color distinct, so the local setup screen and
+//! networked lobbies can let people fiddle with colors without stepping on each other.
+
+use crate::logic::{Player, TileColor};
+
+/// The first color in `TileColor::all_playable` nobody has taken, if any.
+pub fn first_unused_color(players: &[Player]) -> Option<TileColor> {
+    TileColor::all_playable()
+        .into_iter()
+        .find(|c| players.iter().all(|p| p.color != *c))
+}
+
+/// Moves `players[idx]` on to the next color in `TileColor::all_playable` nobody else has,
+/// wrapping around. Does nothing if every color is taken.
+pub fn change_color(players: &mut [Player], idx: usize) {
+    let order = TileColor::all_playable();
+    let current = order
+        .iter()
+        .position(|c| *c == players[idx].color)
+        .unwrap_or(0);
+    let next = (1..order.len())
+        .map(|offset| order[(current + offset) % order.len()])
+        .find(|c| players.iter().all(|p| p.color != *c));
+    if let Some(color) = next {
+        players[idx].color = color;
+    }
+}
+
+/// Trades colors between `players[idx]` and whoever comes after them, wrapping around to the
+/// first player.
+pub fn swap_colors(players: &mut [Player], idx: usize) {
+    let other = (idx + 1) % players.len();
+    let (a, b) = (players[idx].color, players[other].color);
+    players[idx].color = b;
+    players[other].color = a;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all_distinct(players: &[Player]) -> bool {
+        players
+            .iter()
+            .enumerate()
+            .all(|(i, p)| players[..i].iter().all(|q| q.color != p.color))
+    }
+
+    #[test]
+    fn colors_stay_distinct() {
+        let mut players = Player::default_order(3);
+        assert_eq!(
+            first_unused_color(&players),
+            Some(TileColor::all_playable()[3])
+        );
+
+        // Only one color is free, so changing takes it.
+        change_color(&mut players, 0);
+        assert_eq!(players[0].color, TileColor::all_playable()[3]);
+        assert_eq!(
+            first_unused_color(&players),
+            Some(TileColor::all_playable()[0])
+        );
+
+        swap_colors(&mut players, 2);
+        assert_eq!(players[2].color, TileColor::all_playable()[3]);
+        assert_eq!(players[0].color, TileColor::all_playable()[2]);
+
+        for step in 0..12 {
+            let idx = step % players.len();
+            if step % 3 == 0 {
+                swap_colors(&mut players, idx);
+            } else {
+                change_color(&mut players, idx);
+            }
+            assert!(all_distinct(&players));
+        }
+
+        // Nowhere to go with a full table.
+        players.push(Player::new(
+            first_unused_color(&players).unwrap(),
+            crate::piece::SHAPES.len(),
+        ));
+        let before: Vec<_> = players.iter().map(|p| p.color).collect();
+        change_color(&mut players, 1);
+        assert_eq!(players[1].color, before[1]);
+        assert_eq!(first_unused_color(&players), None);
+    }
+}
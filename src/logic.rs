@@ -1,13 +1,39 @@
 use bit_set::BitSet;
-use macroquad::prelude::*;
+use macroquad::{
+    prelude::*,
+    rand::{ChooseRandom, RandGenerator},
+};
 use smallvec::SmallVec;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{hash_map::DefaultHasher, HashSet},
+    fmt,
+    hash::{Hash, Hasher},
+};
 
-use crate::{debug, piece};
+use crate::{
+    ai,
+    bitboard::BitBoard,
+    config::{GameConfig, Seating},
+    palette::Palette,
+    piece::{self, PieceSet},
+};
+
+// How many times anything has looked at the board through `GameState::tile`.
+#[cfg(test)]
+thread_local! {
+    static BOARD_READS: Cell<usize> = const { Cell::new(0) };
+}
 
 pub type PieceID = usize;
 
 /// Denotes possible tile colors. Also used to denote player colors.
-#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "char", try_from = "char")
+)]
 pub enum TileColor {
     #[default]
     Empty,
@@ -32,71 +58,492 @@ impl std::fmt::Display for TileColor {
     }
 }
 
-impl Into<Color> for TileColor {
-    fn into(self) -> Color {
-        match self {
-            TileColor::Red => RED,
-            TileColor::Yellow => YELLOW,
-            TileColor::Green => GREEN,
-            TileColor::Blue => BLUE,
-            TileColor::Empty | TileColor::Wall => BLANK,
-        }
+/// The classic palette. Anything drawn in front of players should ask their `Palette` instead.
+impl From<TileColor> for Color {
+    fn from(tile: TileColor) -> Color {
+        Palette::Classic.color(tile)
     }
 }
 
 impl TileColor {
-    pub const DEFAULT_ORDER: [Self; 4] = [
-        TileColor::Blue,
-        TileColor::Yellow,
-        TileColor::Red,
-        TileColor::Green,
-    ];
-
-    pub fn highlight_color(self) -> Color {
-        match self {
-            TileColor::Red => color_u8!(0xff, 0x70, 0x70, 0xff),
-            TileColor::Yellow => color_u8!(0xff, 0xee, 0x75, 0xff),
-            TileColor::Green => color_u8!(0x8d, 0xff, 0x6b, 0xff),
-            TileColor::Blue => color_u8!(0x28, 0xa0, 0xff, 0xff),
-            TileColor::Empty | TileColor::Wall => BLANK,
+    /// The inverse of `Display`: turns a board character back into a tile.
+    pub fn from_char(c: char) -> Option<Self> {
+        match c {
+            'R' => Some(Self::Red),
+            'Y' => Some(Self::Yellow),
+            'G' => Some(Self::Green),
+            'B' => Some(Self::Blue),
+            '.' => Some(Self::Empty),
+            '#' => Some(Self::Wall),
+            _ => None,
         }
     }
+
+    /// Every color a player can have, in the one true order: who gets which color, and who
+    /// goes first. Anything that needs a list of colors should go through this (or
+    /// `Player::default_order`), so there's only one list to keep straight.
+    pub const fn all_playable() -> [Self; 4] {
+        [
+            TileColor::Blue,
+            TileColor::Yellow,
+            TileColor::Red,
+            TileColor::Green,
+        ]
+    }
 }
 
 /// Player data
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Player {
     /// Player's color
     pub color: TileColor,
     /// Denotes which pieces this player still has available
+    #[cfg_attr(feature = "serde", serde(with = "crate::json::piece_set"))]
     pub remaining_pieces: BitSet<PieceID>,
+    /// Whether the computer plays this seat.
+    pub is_bot: bool,
+    /// How hard the computer tries, if it's playing this seat.
+    pub difficulty: ai::Difficulty,
+    /// The piece this player put down most recently, for the end-of-game bonus.
+    pub last_piece: Option<PieceID>,
+    /// How many pieces this player has put on the board so far.
+    pub pieces_placed: usize,
+    /// The order this player draws their pieces in, when the game's in `draft_mode`.
+    /// Empty otherwise.
+    pub draw_order: Vec<PieceID>,
+    /// Whether this is nobody's color: the spare one in a three-player game, which the real
+    /// players take turns playing (see `GameState::controller`).
+    pub is_neutral: bool,
+    /// What the player wants to be called, if they said. See `display_name`.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub name: Option<String>,
+    /// Whether this player gave up partway through. Their pieces stay where they are and
+    /// still count toward their score, but they don't get any more turns and can't win.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub forfeited: bool,
 }
 
 impl Player {
-    /// Construct a new player with this color, all pieces in hand.
-    pub fn new(color: TileColor) -> Self {
+    /// Construct a new player with this color, holding all `piece_count` pieces of the set
+    /// they're playing with.
+    pub fn new(color: TileColor, piece_count: usize) -> Self {
         Self {
             color,
-            remaining_pieces: BitSet::from_iter(0..21),
+            remaining_pieces: BitSet::from_iter(0..piece_count),
+            is_bot: false,
+            difficulty: ai::Difficulty::default(),
+            last_piece: None,
+            pieces_placed: 0,
+            draw_order: Vec::new(),
+            is_neutral: false,
+            name: None,
+            forfeited: false,
+        }
+    }
+
+    /// The player's name, or their color's if they never picked one.
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .unwrap_or_else(|| format!("{:?}", self.color))
+    }
+
+    /// The spare color in a three-player game.
+    pub fn neutral(color: TileColor, piece_count: usize) -> Self {
+        Self {
+            is_neutral: true,
+            ..Self::new(color, piece_count)
         }
     }
 
+    /// Whether this player has anything left to play and hasn't given up. Players who
+    /// don't get skipped.
+    pub fn still_playing(&self) -> bool {
+        !self.forfeited && !self.remaining_pieces.is_empty()
+    }
+
+    /// The first `player_count` players in `TileColor::all_playable`, all human, with the
+    /// classic pieces.
     pub fn default_order(player_count: usize) -> SmallVec<[Player; 4]> {
-        TileColor::DEFAULT_ORDER
-            .map(Player::new)
+        TileColor::all_playable()
+            .map(|color| Player::new(color, piece::SHAPES.len()))
             .into_iter()
             .take(player_count)
             .collect()
     }
 }
 
+/// A single placement: which piece, which way it's facing, and where the top-left of its
+/// 5x5 grid goes. `corner` is in play area coordinates, just like `place_piece` expects.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Move {
+    pub piece_id: PieceID,
+    pub orientation: piece::Orientation,
+    pub corner: IVec2,
+}
+
+impl Move {
+    /// The tiles this move puts down, before being moved to `corner`.
+    pub fn shape(&self, pieces: &PieceSet) -> piece::Shape {
+        self.orientation.apply(pieces.shape(self.piece_id))
+    }
+
+    /// The (row, col) of every board cell this move covers, in board coordinates
+    /// (so counting the wall).
+    pub fn board_cells(&self, pieces: &PieceSet) -> impl Iterator<Item = (usize, usize)> {
+        let corner = self.corner;
+        let shape = self.shape(pieces);
+        let cells: SmallVec<[(usize, usize); 5]> = piece::cells(&shape)
+            .map(|(dr, dc)| {
+                (
+                    (corner.y + dr as i32) as usize + 1,
+                    (corner.x + dc as i32) as usize + 1,
+                )
+            })
+            .collect();
+        cells.into_iter()
+    }
+
+    /// How many tiles this move puts down.
+    pub fn tile_count(&self, pieces: &PieceSet) -> usize {
+        pieces.size(self.piece_id)
+    }
+
+    /// The (row, col) in the play area of the top-left of the piece's bounding box, which
+    /// unlike `corner` depends only on where the tiles actually go.
+    pub fn anchor(&self, pieces: &PieceSet) -> (i32, i32) {
+        let shape = self.shape(pieces);
+        let top = piece::cells(&shape).map(|(r, _)| r).min().unwrap_or(0);
+        let left = piece::cells(&shape).map(|(_, c)| c).min().unwrap_or(0);
+        (self.corner.y + top as i32, self.corner.x + left as i32)
+    }
+
+    /// Writes this move down for `color`, like `R:L5@r3c4/rot1`: the color, the piece's name
+    /// from `pieces`, the top-left of its bounding box, then how it's turned. Flipped pieces
+    /// get `/flip` before the rotation, since that's the order they're applied.
+    pub fn to_notation(self, color: TileColor, pieces: &PieceSet) -> String {
+        let (row, col) = self.anchor(pieces);
+        let flip = if self.orientation.flipped {
+            "/flip"
+        } else {
+            ""
+        };
+        format!(
+            "{color}:{}@r{row}c{col}{flip}/rot{}",
+            pieces.name(self.piece_id),
+            self.orientation.rotation
+        )
+    }
+
+    /// Reads a move written by `to_notation`, checking that its color is in `game_state` and
+    /// that the piece lands on the board. Whether the move is legal is up to `check_move`.
+    pub fn from_notation(
+        notation: &str,
+        game_state: &GameState,
+    ) -> Result<(TileColor, Self), NotationError> {
+        let malformed = || NotationError::Malformed(notation.to_string());
+        let (color, rest) = notation.split_once(':').ok_or_else(malformed)?;
+        let (name, rest) = rest.split_once('@').ok_or_else(malformed)?;
+        let mut parts = rest.split('/');
+
+        let mut color_chars = color.chars();
+        let color = match (
+            color_chars.next().and_then(TileColor::from_char),
+            color_chars.next(),
+        ) {
+            (Some(color), None) => color,
+            _ => return Err(malformed()),
+        };
+        if !game_state.players.iter().any(|p| p.color == color) {
+            return Err(NotationError::UnknownColor(color));
+        }
+
+        let pieces = &game_state.config.pieces;
+        let piece_id = pieces
+            .find(name)
+            .ok_or_else(|| NotationError::UnknownPiece(name.to_string()))?;
+
+        let anchor = parts.next().ok_or_else(malformed)?;
+        let (row, col) = anchor
+            .strip_prefix('r')
+            .and_then(|rc| rc.split_once('c'))
+            .and_then(|(r, c)| Some((r.parse::<i32>().ok()?, c.parse::<i32>().ok()?)))
+            .ok_or_else(malformed)?;
+
+        let mut orientation = piece::Orientation::default();
+        let mut turn = parts.next().ok_or_else(malformed)?;
+        if turn == "flip" {
+            orientation.flipped = true;
+            turn = parts.next().ok_or_else(malformed)?;
+        }
+        orientation.rotation = turn
+            .strip_prefix("rot")
+            .and_then(|n| n.parse().ok())
+            .filter(|&n| n < 4)
+            .ok_or_else(malformed)?;
+        if parts.next().is_some() {
+            return Err(malformed());
+        }
+
+        // Work back from the bounding box to where the 5x5 grid goes.
+        let mut mv = Self {
+            piece_id,
+            orientation,
+            corner: IVec2::ZERO,
+        };
+        let (top, left) = mv.anchor(pieces);
+        mv.corner = ivec2(col - left, row - top);
+
+        let dim = game_state.play_area_dim() as i32;
+        let shape = mv.shape(pieces);
+        let on_board = piece::cells(&shape).all(|(r, c)| {
+            let (r, c) = (mv.corner.y + r as i32, mv.corner.x + c as i32);
+            (0..dim).contains(&r) && (0..dim).contains(&c)
+        });
+        if !on_board {
+            return Err(NotationError::OffBoard);
+        }
+        Ok((color, mv))
+    }
+}
+
+/// Why a move written down with `Move::to_notation` couldn't be read back.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NotationError {
+    /// Doesn't look like `R:L5@r3c4/rot1` at all.
+    Malformed(String),
+    /// Nobody in the game plays this color.
+    UnknownColor(TileColor),
+    /// No piece goes by this name.
+    UnknownPiece(String),
+    /// Some tile would land off the board.
+    OffBoard,
+}
+
+impl fmt::Display for NotationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed(notation) => write!(f, "Couldn't make sense of \"{notation}\"!"),
+            Self::UnknownColor(color) => write!(f, "Nobody is playing {color:?}!"),
+            Self::UnknownPiece(name) => write!(f, "There's no piece called \"{name}\"!"),
+            Self::OffBoard => write!(f, "That piece doesn't fit on the board!"),
+        }
+    }
+}
+
+/// Why a piece can't go where the player wants to put it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoveError {
+    /// Some tile would land on top of another piece.
+    Overlap,
+    /// Some tile would share an edge with the player's own color.
+    AdjacentSameColor,
+    /// No tile touches the player's own color corner-to-corner (or covers their start
+    /// corner, on their first move).
+    NoDiagonalContact,
+    /// Some tile would hang off the edge of the board.
+    OutOfBounds,
+    /// The house rules don't allow this piece yet.
+    NotAllowedYet,
+    /// The player already put this piece down.
+    AlreadyPlayed,
+    /// The player is drawing pieces, and this isn't the one they drew.
+    NotDrawn,
+    /// Pieces have to get smaller as the game goes, and this one's bigger than the last.
+    BiggerThanLast,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            Self::Overlap => "can't cover other pieces",
+            Self::AdjacentSameColor => "can't share an edge with your own color",
+            Self::NoDiagonalContact => "must touch your own color diagonally",
+            Self::OutOfBounds => "must fit on the board",
+            Self::NotAllowedYet => "can't open with this piece",
+            Self::AlreadyPlayed => "is already on the board",
+            Self::NotDrawn => "isn't the one you drew",
+            Self::BiggerThanLast => "can't be bigger than your last piece",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// Why `place_piece` couldn't put anything down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaceError {
+    /// The current player isn't holding a piece.
+    NothingSelected,
+    /// What's in `piece_buffer` isn't any way of turning the selected piece.
+    ShapeMismatch(PieceID),
+    /// The current player already put down as many pieces as a turn allows.
+    TurnOver,
+}
+
+impl fmt::Display for PlaceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NothingSelected => f.write_str("There's no piece in hand to put down!"),
+            Self::ShapeMismatch(id) => {
+                write!(f, "The piece in hand doesn't look like piece {id}!")
+            }
+            Self::TurnOver => f.write_str("That's all the pieces this turn!"),
+        }
+    }
+}
+
+/// Something that happened to the game, for whoever wants to react to it (sounds, the move log,
+/// the network) without checking for it themselves. Collect them with `GameState::drain_events`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameEvent {
+    /// `player` made `mv`.
+    PiecePlaced { player: usize, mv: Move },
+    /// It's `next`'s turn now.
+    TurnEnded { next: usize },
+    /// `player` skipped their turn.
+    Passed { player: usize },
+    /// `player` gave up, and won't be taking any more turns.
+    Forfeited { player: usize },
+    /// Nobody gets to move anymore. `winner` is whoever has the best score, unless that's a tie.
+    GameOver { winner: Option<usize> },
+}
+
+/// A way of turning the whole board around that leaves where the tiles are unchanged.
+/// Colors don't count, only which cells are covered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symmetry {
+    /// A quarter turn. Boards like this look the same after a half turn too.
+    QuarterTurn,
+    HalfTurn,
+    /// Left to right, across the middle column.
+    MirrorLeftRight,
+    /// Top to bottom, across the middle row.
+    MirrorTopBottom,
+    /// Across the diagonal from the top-left corner to the bottom-right one.
+    Diagonal,
+    /// Across the other diagonal.
+    AntiDiagonal,
+}
+
+impl Symmetry {
+    /// Strongest first, so a board that has several gets reported as the best one.
+    pub const ALL: [Self; 6] = [
+        Self::QuarterTurn,
+        Self::HalfTurn,
+        Self::MirrorLeftRight,
+        Self::MirrorTopBottom,
+        Self::Diagonal,
+        Self::AntiDiagonal,
+    ];
+
+    /// Where (`row`, `col`) ends up on a square board whose last row and column are `last`.
+    /// The same transposes and flips `piece::rotate` and `piece::flip` do, just board sized.
+    pub fn apply(self, (row, col): (usize, usize), last: usize) -> (usize, usize) {
+        match self {
+            Self::QuarterTurn => (col, last - row),
+            Self::HalfTurn => (last - row, last - col),
+            Self::MirrorLeftRight => (row, last - col),
+            Self::MirrorTopBottom => (last - row, col),
+            Self::Diagonal => (col, row),
+            Self::AntiDiagonal => (last - col, last - row),
+        }
+    }
+}
+
+/// Why a game couldn't be set up with the players and board it was given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupError {
+    /// Nobody's playing.
+    NoPlayers,
+    /// More players than there are colors. Holds how many there were.
+    TooManyPlayers(usize),
+    /// Two players share this color.
+    DuplicateColor(TileColor),
+    /// The board doesn't have a start corner for everybody.
+    NotEnoughStartCorners,
+    /// Playing the neutral color takes exactly three players.
+    NeutralNeedsThree,
+    /// Solitaire takes exactly one player.
+    SolitaireNeedsOne,
+    /// Somebody's holding a piece that isn't in the set. Holds the piece.
+    UnknownPiece(PieceID),
+    /// The board's wider than `BitBoard` can keep track of. Holds how wide it was asked to
+    /// be, not counting the wall.
+    BoardTooBig(usize),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPlayers => write!(f, "Need at least one player!"),
+            Self::TooManyPlayers(n) => write!(f, "Only up to four players are supported, not {n}!"),
+            Self::DuplicateColor(color) => write!(f, "More than one player is {color:?}!"),
+            Self::NotEnoughStartCorners => write!(f, "Not enough start corners for everyone!"),
+            Self::NeutralNeedsThree => write!(f, "The neutral color is only for three players!"),
+            Self::SolitaireNeedsOne => write!(f, "Solitaire is just for one player!"),
+            Self::UnknownPiece(id) => write!(f, "There's no piece {id} in this set!"),
+            Self::BoardTooBig(dim) => write!(f, "A {dim} by {dim} board is too big!"),
+        }
+    }
+}
+
+/// Why a scenario couldn't be set up. See `GameState::from_scenario`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScenarioError {
+    /// The board isn't square, or isn't the size the config says. Holds how many rows and
+    /// columns it should have, wall included.
+    WrongSize(usize),
+    /// This character isn't a tile.
+    UnknownTile(char),
+    /// The ring around the edge has something other than wall at this row and column.
+    BrokenWall(usize, usize),
+    /// Someone's holding a piece the config's set doesn't have.
+    UnknownPiece(PieceID),
+    /// The players can't play together on this board.
+    Setup(SetupError),
+}
+
+impl fmt::Display for ScenarioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongSize(width) => write!(f, "The board should be {width} by {width}!"),
+            Self::UnknownTile(c) => write!(f, "'{c}' isn't a tile!"),
+            Self::BrokenWall(row, col) => {
+                write!(
+                    f,
+                    "The wall around the board has a gap at row {row}, column {col}!"
+                )
+            }
+            Self::UnknownPiece(id) => write!(f, "There's no piece {id} in this set!"),
+            Self::Setup(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+/// How one placement went, for looking back over once the game's done. See `GameState::stats`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MoveStats {
+    /// Who made it.
+    pub player: usize,
+    pub piece_id: PieceID,
+    /// How many tiles went down.
+    pub tiles: usize,
+    /// New corners the player got out of it.
+    pub corners_opened: usize,
+    /// Corners it took away from everybody else.
+    pub corners_closed: usize,
+    /// How long the turn took, in seconds, if anybody was checking the clock.
+    pub seconds: Option<f64>,
+}
+
 /// The current game state.
 ///
 /// Constructed on game start.
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct GameState {
-    /// The current state of the board.
-    pub board: [[TileColor; 22]; 22],
+    /// The current state of the board, surrounded by a one tile thick wall.
+    pub board: Vec<Vec<TileColor>>,
     /// Player data.
     pub players: SmallVec<[Player; 4]>,
     /// Points to player whose turn it is.
@@ -108,271 +555,3069 @@ pub struct GameState {
     pub piece_buffer: piece::Shape,
     /// Number of turns passed in a row. If equal to `players.len()` then stops the game.
     pub pass_counter: usize,
+    /// How many turns the neutral color has had, so `controller` knows whose go it is.
+    pub neutral_turns: usize,
+    /// How many pieces the current player has put down so far this turn. See
+    /// `GameConfig::pieces_per_turn`.
+    pub placements_this_turn: usize,
+    /// The board and rules this game is played with.
+    pub config: GameConfig,
+    /// The play area cell (x = column, y = row) the player is pointing at, whether with the
+    /// mouse or the arrow keys. Sticks around between frames so the keyboard can nudge it.
+    pub cursor: IVec2,
+    /// Which cell (x = column, y = row) of the held piece's 5x5 grid sits under `cursor`.
+    /// Starts out in the middle whenever a piece gets picked up, then follows the piece
+    /// around as it turns, so the same tile stays under the pointer.
+    pub grab: IVec2,
+    /// Each player's "frontier": empty cells diagonal to their color but not touching it
+    /// along an edge, or just their start corner if they haven't played yet. Every legal move
+    /// puts at least one tile on one of these, so we only need to search around them.
+    /// Indexed by `row * board.len() + col`.
+    frontiers: SmallVec<[BitSet; 4]>,
+    /// `board` as bit masks, for checking moves quickly. Kept up to date by `place_piece`.
+    bitboard: BitBoard,
+    /// When the current turn's clock started, in seconds on whatever clock gets passed to
+    /// `turn_expired`. `None` until someone checks the clock this turn.
+    turn_started: Option<f64>,
+    /// The latest reading `turn_expired` was given, so a placement knows how long it took.
+    last_clock: Option<f64>,
+    /// Every placement so far, oldest first.
+    stats: Vec<MoveStats>,
+    /// Every placement so far, oldest first, along with who made it. Saves keep these, so
+    /// outlines survive loading and resyncing, but not `stats`.
+    moves: Vec<(usize, Move)>,
+    /// Remembers what `can_make_move` said last, since nothing changes between frames
+    /// until someone does something. `None` means we need to ask again.
+    legal_move_cache: Cell<Option<bool>>,
+    /// Remembers what `legal_anchors` said last, along with the player, piece, orientation and
+    /// board (hashed) it was for. Players hold the same piece still for many frames at a time.
+    legal_anchor_cache: RefCell<Option<(AnchorKey, HashSet<IVec2>)>>,
+    /// Remembers what `reachable_pieces_for_region` said last, along with the player, the
+    /// cells (sorted) and the board (hashed) it was for. Players hover over the same pocket for
+    /// many frames at a time.
+    pocket_cache: RefCell<Option<(PocketKey, Vec<PieceID>)>>,
+    /// Everything that's happened since the last `drain_events`, oldest first.
+    events: Vec<GameEvent>,
 }
 
+/// Everything `legal_anchors` depends on: the current player, the piece they're holding, how
+/// it's turned and where they're holding it, and a hash of the board.
+type AnchorKey = (usize, PieceID, piece::Shape, IVec2, u64);
+
+/// Everything `reachable_pieces_for_region` depends on: the player, the region's cells in
+/// order, and a hash of the board.
+type PocketKey = (usize, Vec<(usize, usize)>, u64);
+
 impl GameState {
     /// For internal testing only.
+    #[cfg(test)]
     pub fn new(player_count: usize) -> Self {
-        Self::with_players(Player::default_order(player_count))
+        Self::with_players(Player::default_order(player_count), GameConfig::classic())
+    }
+
+    /// Construct a fresh gamestate with a given set of `players` on the board described by `config`.
+    /// Panics if they can't play together; see `try_with_players`.
+    pub fn with_players(players: SmallVec<[Player; 4]>, config: GameConfig) -> Self {
+        match Self::try_with_players(players, config) {
+            Ok(game_state) => game_state,
+            Err(e) => panic!("{e}"),
+        }
     }
 
-    /// Construct a fresh gamestate with a given set of `players`
-    pub fn with_players(players: SmallVec<[Player; 4]>) -> Self {
-        assert!(players.len() <= 4, "Only up to four players are supported!");
-        let mut board = [[TileColor::default(); 22]; 22];
-        board[0] = [TileColor::Wall; 22];
-        board[21] = [TileColor::Wall; 22];
+    /// Like `with_players`, but says what's wrong instead of panicking. Takes one to four
+    /// players, all different colors, and at least as many start corners as players.
+    /// With `three_player_neutral`, the neutral color gets added after the three players
+    /// unless it's already there.
+    pub fn try_with_players(
+        mut players: SmallVec<[Player; 4]>,
+        config: GameConfig,
+    ) -> Result<Self, SetupError> {
+        if config.three_player_neutral {
+            if players.iter().filter(|p| !p.is_neutral).count() != 3 {
+                return Err(SetupError::NeutralNeedsThree);
+            }
+            let unused = TileColor::all_playable()
+                .into_iter()
+                .find(|&c| players.iter().all(|p| p.color != c));
+            if let (Some(color), 3) = (unused, players.len()) {
+                players.push(Player::neutral(color, config.pieces.len()));
+            }
+        }
+        if players.is_empty() {
+            return Err(SetupError::NoPlayers);
+        }
+        if config.solitaire && players.len() != 1 {
+            return Err(SetupError::SolitaireNeedsOne);
+        }
+        if players.len() > 4 {
+            return Err(SetupError::TooManyPlayers(players.len()));
+        }
+        for (idx, player) in players.iter().enumerate() {
+            if players[..idx].iter().any(|p| p.color == player.color) {
+                return Err(SetupError::DuplicateColor(player.color));
+            }
+        }
+        if config.start_corners.len() < players.len() {
+            return Err(SetupError::NotEnoughStartCorners);
+        }
+        let pieces_held = players
+            .iter()
+            .flat_map(|p| p.remaining_pieces.iter().chain(p.last_piece));
+        if let Some(id) = pieces_held.max().filter(|&id| id >= config.pieces.len()) {
+            return Err(SetupError::UnknownPiece(id));
+        }
+        // The play area, and a ring of walls around it.
+        let width = config.board_dim + 2;
+        if width > BitBoard::MAX_WIDTH {
+            return Err(SetupError::BoardTooBig(config.board_dim));
+        }
+
+        // Surround the play area with a ring of walls. This makes bounds-checking less
+        // annoying, since every tile in the play area has neighbors we can look at.
+        let mut board = vec![vec![TileColor::default(); width]; width];
+        board[0] = vec![TileColor::Wall; width];
+        board[width - 1] = vec![TileColor::Wall; width];
 
-        for i in 0..22 {
-            board[i][0] = TileColor::Wall;
-            board[i][21] = TileColor::Wall;
+        for row in board.iter_mut() {
+            row[0] = TileColor::Wall;
+            row[width - 1] = TileColor::Wall;
         }
 
-        // Place invisible colored square in each corner of the board so players
-        // can make the first move. This simplifies move validation and makes
-        // bounds-checking less annoying. For two players, we place them across
-        // from each other, otherwise each player takes their turn in a
-        // clockwise order.
-        let corners = if players.len() <= 2 {
-            [(21, 21), (0, 0), (0, 0), (0, 0)]
-        } else {
-            [(21, 21), (21, 0), (0, 0), (0, 21)]
-        };
-        for (p, (row, col)) in players.iter().zip(corners) {
-            board[row][col] = p.color;
+        // Everyone draws the same pieces in the same order, so nobody gets luckier draws.
+        if config.draft_mode {
+            let rng = RandGenerator::new();
+            rng.srand(config.draft_seed);
+            let mut draw_order: Vec<PieceID> = (0..config.pieces.len()).collect();
+            draw_order.shuffle_with_state(&rng);
+            for player in players.iter_mut() {
+                player.draw_order = draw_order.clone();
+            }
         }
 
-        Self {
+        let bitboard = BitBoard::from_board(&board);
+        let mut game_state = Self {
             board,
             players,
             current_player: 0,
             selected_piece: None,
             piece_buffer: piece::EMPTY_SHAPE,
             pass_counter: 0,
+            neutral_turns: 0,
+            placements_this_turn: 0,
+            cursor: IVec2::splat(config.board_dim as i32 / 2),
+            grab: IVec2::splat(2),
+            config,
+            frontiers: SmallVec::new(),
+            bitboard,
+            turn_started: None,
+            last_clock: None,
+            stats: Vec::new(),
+            moves: Vec::new(),
+            legal_move_cache: Cell::new(None),
+            legal_anchor_cache: RefCell::new(None),
+            pocket_cache: RefCell::new(None),
+            events: Vec::new(),
+        };
+        game_state.refresh_frontiers();
+        Ok(game_state)
+    }
+
+    /// A fresh game on the same board with the same people, all holding full hands again.
+    /// With `rotate_seats`, whoever went second goes first this time, and so on down the
+    /// line, so the same person doesn't get the first move every game.
+    pub fn rematch(&self, rotate_seats: bool) -> Self {
+        let piece_count = self.config.pieces.len();
+        // The neutral color gets dealt back in at the end, wherever it sat before.
+        let mut players: SmallVec<[Player; 4]> = self
+            .players
+            .iter()
+            .filter(|p| !p.is_neutral)
+            .map(|p| Player {
+                is_bot: p.is_bot,
+                difficulty: p.difficulty,
+                name: p.name.clone(),
+                ..Player::new(p.color, piece_count)
+            })
+            .collect();
+        if rotate_seats && !players.is_empty() {
+            players.rotate_left(1);
         }
+        Self::with_players(players, self.config.clone())
     }
 
-    /// Returns adjusted coordinates if `shape` can be placed at them. Returns `None` otherwise.
-    pub fn check_bounds_and_recenter(&self, center: IVec2) -> Option<IVec2> {
-        let IVec2 { x: col, y: row } = center;
-        // top row, bottom row, left col, right col
-        let mut shape_bounds = [0; 4];
-
-        for (dr, r) in self.piece_buffer.iter().enumerate() {
-            for dc in r.iter_ones() {
-                let dr = dr as i32 - 2;
-                let dc = dc as i32 - 2;
-                // Only update if we have any 1s in this row. If we don't, do nothing.
-                if dr < shape_bounds[0] {
-                    shape_bounds[0] = dr;
-                } else if dr > shape_bounds[1] {
-                    shape_bounds[1] = dr;
+    /// Sets up a game partway through, for puzzles or for reproducing a bug from a pasted
+    /// board. `board` is drawn the way `TileColor`'s `Display` draws it, one string a row,
+    /// wall and all. There's a player for each of `hands`, in the usual color order, holding
+    /// those pieces; the rest count as already played. It's the first player's turn.
+    #[allow(dead_code)] // For tests, and anyone with a bug report to reproduce.
+    pub fn from_scenario(
+        config: GameConfig,
+        board: &[&str],
+        hands: Vec<BitSet<PieceID>>,
+    ) -> Result<Self, ScenarioError> {
+        let width = config.board_dim + 2;
+        if board.len() != width || board.iter().any(|row| row.chars().count() != width) {
+            return Err(ScenarioError::WrongSize(width));
+        }
+        let board = board
+            .iter()
+            .map(|row| {
+                row.chars()
+                    .map(|c| TileColor::from_char(c).ok_or(ScenarioError::UnknownTile(c)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let on_edge = |i: usize| i == 0 || i == width - 1;
+        for (row, tiles) in board.iter().enumerate() {
+            for (col, &tile) in tiles.iter().enumerate() {
+                if (on_edge(row) || on_edge(col)) && tile != TileColor::Wall {
+                    return Err(ScenarioError::BrokenWall(row, col));
                 }
+            }
+        }
+        if let Some(id) = hands
+            .iter()
+            .flat_map(|hand| hand.iter())
+            .find(|&id| id >= config.pieces.len())
+        {
+            return Err(ScenarioError::UnknownPiece(id));
+        }
+        // There are only four colors to go around, and the rest of the hands would vanish.
+        if hands.len() > 4 {
+            return Err(ScenarioError::Setup(SetupError::TooManyPlayers(
+                hands.len(),
+            )));
+        }
 
-                if dc < shape_bounds[2] {
-                    shape_bounds[2] = dc;
-                } else if dc > shape_bounds[3] {
-                    shape_bounds[3] = dc;
-                }
+        let mut players = Player::default_order(hands.len());
+        for (player, hand) in players.iter_mut().zip(hands) {
+            player.pieces_placed = config.pieces.len().saturating_sub(hand.len());
+            player.remaining_pieces = hand;
+        }
+        let mut game_state =
+            Self::try_with_players(players, config).map_err(ScenarioError::Setup)?;
+        game_state.board = board;
+        game_state.refresh_frontiers();
+        Ok(game_state)
+    }
+
+    /// How many cells across the play area is, not counting the wall.
+    pub fn play_area_dim(&self) -> usize {
+        self.config.board_dim
+    }
+
+    /// How thick the wall around the play area is. Add this to a play area row or column
+    /// to index into `board`.
+    pub fn wall_thickness(&self) -> usize {
+        (self.board.len() - self.play_area_dim()) / 2
+    }
+
+    /// Every cell in the play area someone has put a tile on, as (row, col, color) in play
+    /// area coordinates.
+    pub fn occupied_cells(&self) -> impl Iterator<Item = (usize, usize, TileColor)> + '_ {
+        let wall = self.wall_thickness();
+        let dim = self.play_area_dim();
+        self.board[wall..wall + dim]
+            .iter()
+            .enumerate()
+            .flat_map(move |(row, tiles)| {
+                tiles[wall..wall + dim]
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, tile)| !matches!(tile, TileColor::Empty | TileColor::Wall))
+                    .map(move |(col, &tile)| (row, col, tile))
+            })
+    }
+
+    /// Whether the tiles on the board look the same after turning or mirroring it, and if so,
+    /// the strongest `Symmetry` they have. An empty board has all of them.
+    pub fn is_symmetric(&self) -> Option<Symmetry> {
+        let covered: HashSet<(usize, usize)> =
+            self.occupied_cells().map(|(r, c, _)| (r, c)).collect();
+        let last = self.play_area_dim() - 1;
+        Symmetry::ALL.into_iter().find(|&symmetry| {
+            covered
+                .iter()
+                .all(|&cell| covered.contains(&symmetry.apply(cell, last)))
+        })
+    }
+
+    /// Rebuilds everyone's frontier (and the bitboard) from scratch. Call this after editing
+    /// `board` by hand.
+    pub fn refresh_frontiers(&mut self) {
+        self.bitboard = BitBoard::from_board(&self.board);
+        self.frontiers = (0..self.players.len())
+            .map(|idx| self.compute_frontier(idx))
+            .collect();
+        self.legal_move_cache.set(None);
+    }
+
+    /// The (row, col) in the play area that `player_idx`'s first piece has to cover. Seats
+    /// go around the corners the same way turns do, so the next player up always sits at
+    /// the next corner over.
+    pub fn start_corner(&self, player_idx: usize) -> (usize, usize) {
+        let corners = &self.config.start_corners;
+        let seats = self.players.len();
+        match self.config.seating {
+            Seating::Clockwise => corners[player_idx * corners.len() / seats],
+            Seating::CounterClockwise => {
+                // How many turns after the first player this seat moves.
+                let turns_in = (seats - player_idx) % seats;
+                let along = turns_in * corners.len() / seats;
+                corners[(corners.len() - along) % corners.len()]
             }
         }
+    }
 
-        if row + shape_bounds[0] >= 0
-            && row + shape_bounds[1] < (self.board.len() - 2) as i32
-            && col + shape_bounds[2] >= 0
-            && col + shape_bounds[3] < (self.board.len() - 2) as i32
-        {
-            Some(ivec2(col - 2, row - 2))
+    /// The seat `steps` turns on from `player_idx`, going whichever way `seating` says.
+    fn seat_after(&self, player_idx: usize, steps: usize) -> usize {
+        let seats = self.players.len();
+        match self.config.seating {
+            Seating::Clockwise => (player_idx + steps) % seats,
+            Seating::CounterClockwise => (player_idx + seats - steps % seats) % seats,
+        }
+    }
+
+    /// Every (row, col) in the play area `player_idx`'s first piece may cover. That's just
+    /// their start corner, except in solitaire, where any of them will do.
+    pub fn start_cells(&self, player_idx: usize) -> SmallVec<[(usize, usize); 4]> {
+        if self.config.solitaire {
+            self.config.start_corners.iter().copied().collect()
+        } else {
+            smallvec::smallvec![self.start_corner(player_idx)]
+        }
+    }
+
+    /// The (row, col)s in the play area the current player's piece can cover, if this is
+    /// their first move and the rules say they have to start somewhere in particular. Empty
+    /// otherwise.
+    pub fn first_move_corners(&self) -> SmallVec<[(usize, usize); 4]> {
+        if self.config.first_move_must_cover_corner && !self.has_played(self.current_player) {
+            self.start_cells(self.current_player)
         } else {
-            None
+            SmallVec::new()
         }
     }
 
+    /// Returns adjusted coordinates if `shape` can be placed at them. Returns `None` otherwise.
+    pub fn check_bounds_and_recenter(&self, center: IVec2) -> Option<IVec2> {
+        // The cursor sits on the middle of the piece's 5x5 grid, wherever its tiles are.
+        let corner = center - IVec2::splat(2);
+        // top row, bottom row, left col, right col of the tiles actually in the grid.
+        // Turning a piece can leave it off to one side, so don't assume anything about
+        // where they are.
+        let cells = || piece::cells(&self.piece_buffer);
+        let shape_bounds = match (
+            cells().map(|(r, _)| r).min(),
+            cells().map(|(r, _)| r).max(),
+            cells().map(|(_, c)| c).min(),
+            cells().map(|(_, c)| c).max(),
+        ) {
+            (Some(top), Some(bottom), Some(left), Some(right)) => {
+                [top, bottom, left, right].map(|bound| bound as i32)
+            }
+            // Nothing in the buffer, so just make sure `center` itself is on the board.
+            _ => [2; 4],
+        };
+
+        let dim = self.play_area_dim() as i32;
+        let on_board = |start: i32, first: i32, last: i32| start + first >= 0 && start + last < dim;
+        (on_board(corner.y, shape_bounds[0], shape_bounds[1])
+            && on_board(corner.x, shape_bounds[2], shape_bounds[3]))
+        .then_some(corner)
+    }
+
+    /// Where the middle of the held piece's grid is when `cursor` is over its `grab` cell,
+    /// ready for `check_bounds_and_recenter`.
+    pub fn held_center(&self, cursor: IVec2) -> IVec2 {
+        cursor + IVec2::splat(2) - self.grab
+    }
+
     /// Writes the current player's piece buffer using `corner` as a basis.
     /// If you currently have the center of the piece, be sure to adjust it using
-    /// `check_bounds_and_recenter` first!
-    pub fn place_piece(&mut self, corner: IVec2) {
+    /// `check_bounds_and_recenter` first! Nothing changes if there's no piece in hand.
+    pub fn place_piece(&mut self, corner: IVec2) -> Result<(), PlaceError> {
         let IVec2 {
             x: adj_col,
             y: adj_row,
         } = corner;
-        debug::print_board(&self.board);
         debug_assert!(!self.players.is_empty());
+        if self.placements_this_turn >= self.config.pieces_per_turn {
+            return Err(PlaceError::TurnOver);
+        }
+        let piece_id = self.selected_piece.ok_or(PlaceError::NothingSelected)?;
+        let mv = self
+            .held_move(corner)
+            .ok_or(PlaceError::ShapeMismatch(piece_id))?;
+        let first_move = !self.has_played(self.current_player);
+        let wall = self.wall_thickness();
+        // Corners have to be counted before the board changes under them.
+        let stats = MoveStats {
+            player: self.current_player,
+            piece_id: mv.piece_id,
+            tiles: mv.tile_count(&self.config.pieces),
+            corners_opened: self.corners_opened_by(&mv),
+            corners_closed: self.corners_blocked_by(&mv),
+            seconds: self
+                .turn_started
+                .zip(self.last_clock)
+                .map(|(start, now)| now - start),
+        };
+        self.stats.push(stats);
+        self.moves.push((self.current_player, mv));
         let player = &mut self.players[self.current_player];
-        for (dr, r) in self.piece_buffer.iter().enumerate() {
-            for dc in r.iter_ones() {
-                // Sometimes I wish Rust allowed signed indices.
-                let r_ind = (adj_row + dr as i32) as usize;
-                let c_ind = (adj_col + dc as i32) as usize;
-                self.board[r_ind + 1][c_ind + 1] = player.color;
+        let color = player.color;
+        for (dr, dc) in piece::cells(&self.piece_buffer) {
+            // Sometimes I wish Rust allowed signed indices.
+            let r_ind = (adj_row + dr as i32) as usize;
+            let c_ind = (adj_col + dc as i32) as usize;
+            self.board[r_ind + wall][c_ind + wall] = color;
+            self.bitboard.set(r_ind + wall, c_ind + wall, color);
+        }
+
+        player.remaining_pieces.remove(piece_id);
+        player.last_piece = Some(piece_id);
+        player.pieces_placed += 1;
+
+        // Now fix up everyone's frontiers. Nobody can use the cells we just covered,
+        // and the current player may have gained new corners and lost old ones.
+        let width = self.board.len();
+        let (footprint, (top, left)) = piece::footprint(&self.piece_buffer);
+        let (top, left) = (adj_row + top + wall as i32, adj_col + left + wall as i32);
+        let index = |(dr, dc): (i32, i32)| (top + dr) as usize * width + (left + dc) as usize;
+        for &cell in &footprint.cells {
+            for frontier in self.frontiers.iter_mut() {
+                frontier.remove(index(cell));
+            }
+        }
+        for &cell in &footprint.edges {
+            self.frontiers[self.current_player].remove(index(cell));
+        }
+        for &(dr, dc) in &footprint.corners {
+            let (r, c) = ((top + dr) as usize, (left + dc) as usize);
+            if self.is_frontier_cell(r, c, color) {
+                self.frontiers[self.current_player].insert(r * width + c);
             }
         }
 
-        player.remaining_pieces.remove(self.selected_piece.unwrap());
+        // Our first move might have been allowed to go anywhere, in which case the
+        // frontier still has the whole board in it. Start over from what we just placed.
+        if first_move {
+            self.frontiers[self.current_player] = self.compute_frontier(self.current_player);
+        }
 
+        self.events.push(GameEvent::PiecePlaced {
+            player: self.current_player,
+            mv,
+        });
         self.selected_piece = None;
         self.piece_buffer = piece::EMPTY_SHAPE;
         // We were able to place a piece, so clearly this player did not pass.
         self.pass_counter = 0;
+        self.placements_this_turn += 1;
+        self.legal_move_cache.set(None);
+        Ok(())
     }
 
     /// Determines if the current move is valid. Requires a pointer to the full game board
     /// and the player who wishes to make the move (provided by this struct).
-    /// Assumes the piece will be in bounds.
     pub fn valid_move(&self, corner: IVec2) -> bool {
-        self._valid_move(&self.piece_buffer, corner)
+        self.validate_move(corner).is_ok()
     }
 
-    // For internal use -- needed only because `can_make_move` needs its own piece buffer.
-    fn _valid_move(&self, piece_buffer: &piece::Shape, corner: IVec2) -> bool {
+    /// Like `valid_move`, but says what's wrong with the move when it isn't valid.
+    /// `corner` is in board coordinates, so it counts the wall.
+    pub fn validate_move(&self, corner: IVec2) -> Result<(), MoveError> {
+        if let Some(piece_id) = self.selected_piece {
+            self.check_piece(self.current_player, piece_id)?;
+        }
+        self._validate_move(self.current_player, &self.piece_buffer, corner)
+    }
+
+    /// Checks whether the current player may make `mv`, whatever they happen to be holding.
+    pub fn check_move(&self, mv: &Move) -> Result<(), MoveError> {
+        if !self.current_player().remaining_pieces.contains(mv.piece_id) {
+            return Err(MoveError::AlreadyPlayed);
+        }
+        self.check_piece(self.current_player, mv.piece_id)?;
+        let shape = mv.shape(&self.config.pieces);
+        let wall = IVec2::splat(self.wall_thickness() as i32);
+        self._validate_move(self.current_player, &shape, mv.corner + wall)
+    }
+
+    /// The move the current player makes by putting down what they're holding at `corner`
+    /// (in play area coordinates), or `None` if their hands are empty.
+    pub fn held_move(&self, corner: IVec2) -> Option<Move> {
+        let piece_id = self.selected_piece?;
+        let held = piece::CanonicalShape::new(self.piece_buffer);
+        let orientation = piece::Orientation::ALL.into_iter().find(|o| {
+            piece::CanonicalShape::new(o.apply(self.config.pieces.shape(piece_id))) == held
+        })?;
+        // The same orientation can sit anywhere in its 5x5 grid depending on how the player
+        // got there, so line the grids up by their top-left tiles.
+        let top_left = |shape: &piece::Shape| {
+            let (rows, cols): (Vec<_>, Vec<_>) = piece::cells(shape).unzip();
+            ivec2(
+                cols.into_iter().min().unwrap_or(0) as i32,
+                rows.into_iter().min().unwrap_or(0) as i32,
+            )
+        };
+        let shift = top_left(&self.piece_buffer)
+            - top_left(&orientation.apply(self.config.pieces.shape(piece_id)));
+        Some(Move {
+            piece_id,
+            orientation,
+            corner: corner + shift,
+        })
+    }
+
+    // For internal use -- needed only because `can_make_move` needs its own piece buffer
+    // and `any_legal_move_for` may ask about someone other than the current player.
+    fn _validate_move(
+        &self,
+        player_idx: usize,
+        piece_buffer: &piece::Shape,
+        corner: IVec2,
+    ) -> Result<(), MoveError> {
         let IVec2 {
             x: adj_col,
             y: adj_row,
         } = corner;
-        let player = &self.players[self.current_player];
-        let mut any_diagonal_matches = false;
-
-        for (r_ind, row) in piece_buffer.iter().enumerate() {
-            for tile in row.iter_ones() {
-                let r_coord = adj_row + r_ind as i32;
-                let c_coord = adj_col + tile as i32;
-
-                // The board must have space for all tiles that comprise the piece.
-                if self.board[r_coord as usize][c_coord as usize] != TileColor::Empty {
-                    return false;
-                }
-
-                let adjacents = [
-                    (r_coord - 1, c_coord),
-                    (r_coord, c_coord - 1),
-                    (r_coord + 1, c_coord),
-                    (r_coord, c_coord + 1),
-                ];
+        let color = self.players[player_idx].color;
+        let width = self.bitboard.width() as i32;
+        let (footprint, (top, left)) = piece::footprint(piece_buffer);
+        let (top, left) = (adj_row + top, adj_col + left);
 
-                // No tiles adjacent
-                if adjacents
-                    .into_iter()
-                    .any(|(rc, cc)| self.board[rc as usize][cc as usize] == player.color)
-                {
-                    return false;
-                }
+        // Every tile has to land inside the wall, on an empty cell. Going a row at a time, left
+        // to right, the first bad tile we find is the same one a tile-by-tile check would find.
+        for (dr, &row_bits) in footprint.rows.iter().enumerate() {
+            let row = top + dr as i32;
+            // Every row of a piece has a tile in it, so a row that's entirely off the board
+            // has a tile off the board.
+            if !(0..width).contains(&row) || !(-4..width).contains(&left) {
+                return Err(MoveError::OutOfBounds);
+            }
+            let row_bits = row_bits as u64;
+            let placed = if left >= 0 {
+                row_bits << left
+            } else if row_bits & ((1 << -left) - 1) != 0 {
+                // Hanging off the left end of the row.
+                return Err(MoveError::OutOfBounds);
+            } else {
+                row_bits >> -left
+            };
 
-                let diagonals = [
-                    (r_coord - 1, c_coord - 1),
-                    (r_coord + 1, c_coord - 1),
-                    (r_coord - 1, c_coord + 1),
-                    (r_coord + 1, c_coord + 1),
-                ];
+            let walls = placed & self.bitboard.walls(row as usize);
+            let tiles = placed & self.bitboard.occupied(row as usize);
+            if walls | tiles != 0 {
+                return Err(if walls.trailing_zeros() < tiles.trailing_zeros() {
+                    MoveError::OutOfBounds
+                } else {
+                    MoveError::Overlap
+                });
+            }
+            // Hanging off the right end of the row.
+            if placed >> width != 0 {
+                return Err(MoveError::OutOfBounds);
+            }
+        }
 
-                any_diagonal_matches = any_diagonal_matches
-                    || diagonals
-                        .into_iter()
-                        .any(|(rc, cc)| self.board[rc as usize][cc as usize] == player.color);
+        // No tiles adjacent. The piece is inside the wall, so everything around it is on the board.
+        for (dr, &edge_bits) in footprint.edge_rows.iter().enumerate() {
+            let row = (top - 1 + dr as i32) as usize;
+            if ((edge_bits as u64) << (left - 1)) & self.bitboard.color(row, color) != 0 {
+                return Err(MoveError::AdjacentSameColor);
             }
         }
 
-        any_diagonal_matches
+        let cells = || {
+            footprint
+                .cells
+                .iter()
+                .map(move |&(dr, dc)| (top + dr, left + dc))
+        };
+        // There's nothing of ours to touch on our first move, so the piece has to cover our
+        // start corner instead. Otherwise, some tile has to touch our color diagonally. The
+        // frontier already tracks which cells do that, so just ask it.
+        let connected = if self.config.first_move_must_cover_corner && !self.has_played(player_idx)
+        {
+            let wall = self.wall_thickness() as i32;
+            let start_cells = self.start_cells(player_idx);
+            cells().any(|(r, c)| {
+                start_cells
+                    .iter()
+                    .any(|&(row, col)| (row as i32 + wall, col as i32 + wall) == (r, c))
+            })
+        } else {
+            let frontier = &self.frontiers[player_idx];
+            cells().any(|(r, c)| frontier.contains(r as usize * width as usize + c as usize))
+        };
+        if connected {
+            Ok(())
+        } else {
+            Err(MoveError::NoDiagonalContact)
+        }
     }
 
-    /// Go to the next player.
-    pub fn end_turn(&mut self) {
-        self.current_player = (self.current_player + 1) % self.players.len();
+    /// How many tiles' worth of pieces `player_idx` still has in hand.
+    pub fn remaining_tile_count(&self, player_idx: usize) -> usize {
+        self.players[player_idx]
+            .remaining_pieces
+            .iter()
+            .map(|piece_id| self.config.pieces.size(piece_id))
+            .sum()
     }
 
-    /// With the current implementation of things, a naive solution
-    /// is the best one.
-    ///
-    /// This tries to place all remaining pieces on every tile on the board for eight possible orientations.
-    /// I figured we might be able to make this faster by storing valid coordinates for each player.
-    /// That would require I record this set of points in GameState and send rotated copies of those to each player.
-    /// I don't know if there's any value to precomputing these points.
-    /// This algorithm, on average, *shouldn't* have to iterate through every piece most of the time.
-    /// Players will often save their smaller pieces for later, which are more likely to pass any of these checks
-    /// and cause the function to return early. In addition, even if they don't, this function will only struggle
-    /// to find a match towards the end of the game, where there are fewer pieces to iterate over to begin with.
-    /// This otherwise O(rcp) solution *should* almost never reach its worst-case runtime. But it may cause slowdown in
-    /// some pathological cases.
-    pub fn can_make_move(&self) -> bool {
-        let player = &self.players[self.current_player];
-        player.remaining_pieces.iter().any(|pc| {
-            let mut piece_buf = piece::SHAPES[pc];
-            use piece::{FlipDir, RotateDir};
-            // Do people find this hard to understand?
-            // I don't, but that's because I'm lambda-brained.
-            (0..2).any(|_| {
-                piece_buf = piece::flip(piece_buf, FlipDir::Vertical);
-                (0..4).any(|_| {
-                    piece_buf = piece::rotate(piece_buf, RotateDir::Right);
-                    (0..20)
-                        .any(|row| (0..20).any(|col| self._valid_move(&piece_buf, ivec2(col, row))))
-                })
-            })
-        })
+    /// `player_idx`'s score, using the standard rules: lose a point for every tile still in
+    /// hand, or if every piece made it onto the board, gain 15 -- and 5 more if the dot went
+    /// last. Works mid-game too, as if the game ended right now. Solitaire just counts the
+    /// tiles on the board, to measure up against `solitaire_target`.
+    pub fn score(&self, player_idx: usize) -> i32 {
+        if self.config.solitaire {
+            return (self.solitaire_target() - self.remaining_tile_count(player_idx)) as i32;
+        }
+        if !self.played_every_piece(player_idx) {
+            return -(self.remaining_tile_count(player_idx) as i32);
+        }
+
+        if self.dot_went_last(player_idx) {
+            20
+        } else {
+            15
+        }
     }
 
-    pub fn is_game_over(&self) -> bool {
-        self.players[self.current_player]
-            .remaining_pieces
-            .is_empty()
-            || self.pass_counter == self.players.len()
+    /// The best a solitaire game can go: every tile in the set on the board.
+    pub fn solitaire_target(&self) -> usize {
+        (0..self.config.pieces.len())
+            .map(|piece_id| self.config.pieces.size(piece_id))
+            .sum()
     }
 
-    pub fn select_piece(&mut self, piece_id: Option<PieceID>) {
-        self.selected_piece = piece_id;
-        let shape = match piece_id {
-            Some(id) => piece::SHAPES[id],
-            None => piece::EMPTY_SHAPE,
-        };
-        self.piece_buffer = shape;
+    /// Whether `player_idx` got every one of their pieces onto the board, for the 15 point bonus.
+    pub fn played_every_piece(&self, player_idx: usize) -> bool {
+        self.players[player_idx].remaining_pieces.is_empty()
     }
 
-    pub fn current_player(&self) -> &Player {
-        &self.players[self.current_player]
+    /// Whether `player_idx` played every piece and saved the dot for last, for 5 more.
+    pub fn dot_went_last(&self, player_idx: usize) -> bool {
+        // Piece 0 is the monomino.
+        self.played_every_piece(player_idx) && self.players[player_idx].last_piece == Some(0)
     }
 
-    #[cfg(test)]
-    pub fn try_advance_turn(&mut self, row: usize, col: usize) -> bool {
-        let corner = match self.check_bounds_and_recenter(ivec2(col as i32, row as i32)) {
-            Some(coords) => coords,
-            None => return false,
-        };
+    /// Puts down `mv` for the current player and moves on to the next one, if it's legal.
+    /// If it isn't, nothing changes -- not even what they're holding. Anything placing pieces
+    /// from outside the game (bots, the network, replays) should come through here.
+    pub fn try_apply_move(&mut self, mv: &Move) -> Result<(), MoveError> {
+        self.check_move(mv)?;
+        self.apply_move(mv);
+        Ok(())
+    }
 
-        let place_ok = self.valid_move(corner + IVec2::ONE);
-        if place_ok {
-            self.place_piece(corner);
+    /// Puts down `mv` for the current player, and moves on to the next one if that was all
+    /// they get this turn (see `turn_done`).
+    /// Doesn't check that it's legal, so get it from `enumerate_legal_moves` or similar.
+    pub fn apply_move(&mut self, mv: &Move) {
+        self.select_piece(Some(mv.piece_id));
+        self.piece_buffer = mv.shape(&self.config.pieces);
+        self.place_piece(mv.corner)
+            .expect("Legal moves are always for a piece the player can pick up.");
+        if self.turn_done() {
             self.end_turn();
         }
-        place_ok
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Whether the current player has put down everything they're going to this turn: as
+    /// many pieces as `pieces_per_turn` allows, or at least one and nothing else fits.
+    pub fn turn_done(&self) -> bool {
+        self.placements_this_turn >= self.config.pieces_per_turn
+            || (self.placements_this_turn > 0 && !self.can_make_move())
+    }
 
-    #[test]
-    fn accept_correct_move() {
-        let mut game_state = GameState::new(4);
-        // L5 shape, bottom right:
+    /// Whether the current player may pass right now: always when they're stuck,
+    /// and whenever they like if the house rules allow it. Once they've put a piece down,
+    /// passing just ends the turn early.
+    pub fn may_pass(&self) -> bool {
+        self.config.allow_voluntary_pass || self.placements_this_turn > 0 || !self.can_make_move()
+    }
+
+    /// Whether the second player can still take the first player's opening for themselves,
+    /// which they only get to do right after it's been played.
+    pub fn pie_swap_available(&self) -> bool {
+        self.config.pie_rule
+            && self.players.len() == 2
+            && self.current_player == 1
+            && self.pass_counter == 0
+            && self.players[0].pieces_placed == 1
+            && self.players[1].pieces_placed == 0
+    }
+
+    /// Under the pie rule, the second player takes over the first player's color along with
+    /// the piece already on the board, and hands over their own untouched color and corner.
+    /// The first player then moves next, as the color that hasn't played yet. Whoever's
+    /// controlling each seat stays put.
+    pub fn swap_seats(&mut self) {
+        let (first, second) = self.players.split_at_mut(1);
+        let (first, second) = (&mut first[0], &mut second[0]);
+        std::mem::swap(&mut first.color, &mut second.color);
+        std::mem::swap(&mut first.remaining_pieces, &mut second.remaining_pieces);
+        std::mem::swap(&mut first.last_piece, &mut second.last_piece);
+        std::mem::swap(&mut first.pieces_placed, &mut second.pieces_placed);
+
+        // Each color keeps its start corner, so the corners trade seats too.
+        let corners = &mut self.config.start_corners;
+        let second_corner = corners.len() / self.players.len();
+        corners.swap(0, second_corner);
+
+        self.current_player = 0;
+        self.turn_started = None;
+        self.legal_move_cache.set(None);
+        self.refresh_frontiers();
+    }
+
+    /// Skip the current player's turn. Once everyone passes in a row, the game is over.
+    /// If they've already put a piece down this turn, it isn't a pass, just the end of it.
+    pub fn pass_turn(&mut self) {
+        if self.placements_this_turn == 0 {
+            self.register_pass();
+        }
+        self.end_turn();
+    }
+
+    /// Counts a pass toward everyone being stuck. `place_piece` starts the count over, and
+    /// nothing outside `GameState` should touch it, or the game and the screen can end up
+    /// disagreeing about whether it's over.
+    fn register_pass(&mut self) {
+        self.events.push(GameEvent::Passed {
+            player: self.current_player,
+        });
+        self.pass_counter += 1;
+        // Once everyone's passed in a row the game's over, and nobody passes after that.
+        debug_assert!(
+            self.pass_counter <= self.players.len(),
+            "{} passes with only {} players",
+            self.pass_counter,
+            self.players.len()
+        );
+    }
+
+    /// Takes `player_idx` out of the game for good. Their tiles stay on the board and still
+    /// score, but their turns get skipped from now on, and they can't win. If it was their
+    /// turn, it ends, without counting as a pass.
+    pub fn forfeit(&mut self, player_idx: usize) {
+        if self.players[player_idx].forfeited || self.is_game_over() {
+            return;
+        }
+        self.players[player_idx].forfeited = true;
+        self.events
+            .push(GameEvent::Forfeited { player: player_idx });
+        if player_idx == self.current_player {
+            self.select_piece(None);
+            self.end_turn();
+        } else if self.is_game_over() {
+            // Everyone else might have been waiting on them to pass too.
+            let winner = self.leader();
+            self.events.push(GameEvent::GameOver { winner });
+        }
+    }
+
+    /// Go to the next player who still has pieces. If nobody does, the game's over anyway.
+    pub fn end_turn(&mut self) {
+        if self.current_player().is_neutral {
+            self.neutral_turns += 1;
+        }
+        let seats = self.players.len();
+        self.current_player = (1..=seats)
+            .map(|step| self.seat_after(self.current_player, step))
+            .find(|&idx| self.players[idx].still_playing())
+            .unwrap_or(self.seat_after(self.current_player, 1));
+        self.legal_move_cache.set(None);
+        self.turn_started = None;
+        self.placements_this_turn = 0;
+        self.events.push(GameEvent::TurnEnded {
+            next: self.current_player,
+        });
+        if self.is_game_over() {
+            let winner = self.leader();
+            self.events.push(GameEvent::GameOver { winner });
+        }
+    }
+
+    /// Hands over everything that's happened since the last call, oldest first.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// What `drain_events` would hand over right now, without taking it away from whoever
+    /// else is listening.
+    pub fn pending_events(&self) -> &[GameEvent] {
+        &self.events
+    }
+
+    /// The seat with the best score, if nobody's tied with them. The neutral color can't win,
+    /// and neither can anyone who forfeited.
+    fn leader(&self) -> Option<usize> {
+        let mut seats = (0..self.players.len())
+            .filter(|&idx| !self.players[idx].is_neutral && !self.players[idx].forfeited);
+        let first = seats.next()?;
+        let (mut best, mut tied) = (first, false);
+        for idx in seats {
+            match self.score(idx).cmp(&self.score(best)) {
+                std::cmp::Ordering::Greater => (best, tied) = (idx, false),
+                std::cmp::Ordering::Equal => tied = true,
+                std::cmp::Ordering::Less => {}
+            }
+        }
+        (!tied).then_some(best)
+    }
+
+    /// Whether the current player has run out of time, going by the clock reading `now`
+    /// (in seconds). The first check in a turn starts that turn's clock, timed or not, so
+    /// `stats` can say how long each move took. It's up to the caller to pass them, so that
+    /// a networked game can tell everyone.
+    pub fn turn_expired(&mut self, now: f64) -> bool {
+        let started = *self.turn_started.get_or_insert(now);
+        self.last_clock = Some(now);
+        self.config
+            .turn_seconds
+            .is_some_and(|limit| now - started >= limit as f64)
+    }
+
+    /// Starts the current turn's clock over, the next time `turn_expired` looks at it. For
+    /// turns that get taken back, so the clock doesn't pick up where it left off.
+    pub fn restart_turn_clock(&mut self) {
+        self.turn_started = None;
+    }
+
+    /// How every placement so far went, oldest first.
+    pub fn stats(&self) -> &[MoveStats] {
+        &self.stats
+    }
+
+    /// Every piece put down so far, and who put it there, oldest first. Tells apart which
+    /// tiles came from which piece when a color's pieces touch.
+    pub fn moves(&self) -> &[(usize, Move)] {
+        &self.moves
+    }
+
+    /// Puts back what `moves` says, for a game read back from a save. Loading only checks
+    /// that each one lands inside the play area, not that it matches the tiles there, since
+    /// they're only there to tell pieces apart.
+    pub fn restore_moves(&mut self, moves: Vec<(usize, Move)>) {
+        self.moves = moves;
+    }
+
+    /// How much of the current turn is left, as a fraction from 1 down to 0, or `None` if
+    /// turns aren't timed (or the clock hasn't started yet).
+    pub fn turn_time_left(&self, now: f64) -> Option<f64> {
+        let limit = self.config.turn_seconds? as f64;
+        let started = self.turn_started?;
+        Some((1. - (now - started) / limit).clamp(0., 1.))
+    }
+
+    /// Determines if the current player can place any of their remaining pieces.
+    ///
+    /// Every legal move has to land a tile on one of the player's frontier cells, so instead
+    /// of trying every piece on every tile of the board, we only try placements that put
+    /// some tile of the piece onto a frontier cell. The frontier rarely grows past a few
+    /// dozen cells, so this stays cheap even late in the game when most attempts fail.
+    ///
+    /// The game loop asks this every frame, so the answer is cached until the board or the
+    /// turn changes.
+    pub fn can_make_move(&self) -> bool {
+        match self.legal_move_cache.get() {
+            Some(answer) => answer,
+            None => {
+                let answer = self.any_legal_move_for(self.current_player);
+                self.legal_move_cache.set(Some(answer));
+                answer
+            }
+        }
+    }
+
+    /// Every play area cell the current player could point at to put down the piece they're
+    /// holding, turned the way it is now. Empty if they aren't holding anything.
+    pub fn legal_anchors(&self) -> HashSet<IVec2> {
+        let Some(held) = self.held_move(IVec2::ZERO) else {
+            return HashSet::new();
+        };
+        let mut hasher = DefaultHasher::new();
+        self.board.hash(&mut hasher);
+        let key = (
+            self.current_player,
+            held.piece_id,
+            self.piece_buffer,
+            self.grab,
+            hasher.finish(),
+        );
+        if let Some((cached_key, anchors)) = &*self.legal_anchor_cache.borrow() {
+            if *cached_key == key {
+                return anchors.clone();
+            }
+        }
+
+        // `held_move` says how far the held piece's grid sits from the one legal moves use,
+        // and the cursor points at the `grab` cell of that grid.
+        let last_cell = self.play_area_dim() as i32 - 1;
+        let anchors: HashSet<_> = self
+            .enumerate_legal_moves()
+            .into_iter()
+            .filter(|mv| mv.piece_id == held.piece_id && mv.orientation == held.orientation)
+            .map(|mv| mv.corner - held.corner + self.grab)
+            .filter(|cell| {
+                cell.cmpge(IVec2::ZERO).all() && cell.cmple(IVec2::splat(last_cell)).all()
+            })
+            .collect();
+        *self.legal_anchor_cache.borrow_mut() = Some((key, anchors.clone()));
+        anchors
+    }
+
+    /// Determines if the player at `player_idx` could place any of their remaining pieces,
+    /// whether or not it's their turn.
+    pub fn any_legal_move_for(&self, player_idx: usize) -> bool {
+        self.legal_moves_for(player_idx).next().is_some()
+    }
+
+    /// Counts the corners `mv` would open up for the current player that they don't already have.
+    pub fn corners_opened_by(&self, mv: &Move) -> usize {
+        let color = self.current_player().color;
+        let width = self.board.len();
+        let covered: HashSet<(usize, usize)> = mv.board_cells(&self.config.pieces).collect();
+        let is_own = |r: usize, c: usize| self.board[r][c] == color || covered.contains(&(r, c));
+
+        let mut opened = HashSet::new();
+        for &(row, col) in &covered {
+            for (r, c) in [
+                (row - 1, col - 1),
+                (row + 1, col - 1),
+                (row - 1, col + 1),
+                (row + 1, col + 1),
+            ] {
+                if self.board[r][c] == TileColor::Empty
+                    && !covered.contains(&(r, c))
+                    && !self.frontiers[self.current_player].contains(r * width + c)
+                    && !is_own(r - 1, c)
+                    && !is_own(r + 1, c)
+                    && !is_own(r, c - 1)
+                    && !is_own(r, c + 1)
+                {
+                    opened.insert((r, c));
+                }
+            }
+        }
+        opened.len()
+    }
+
+    /// Counts the corners `mv` would take away from everyone else by covering them.
+    pub fn corners_blocked_by(&self, mv: &Move) -> usize {
+        let width = self.board.len();
+        self.frontiers
+            .iter()
+            .enumerate()
+            .filter(|&(idx, _)| idx != self.current_player)
+            .map(|(_, frontier)| {
+                mv.board_cells(&self.config.pieces)
+                    .filter(|&(r, c)| frontier.contains(r * width + c))
+                    .count()
+            })
+            .sum()
+    }
+
+    // Like the two above, these are for bots and tools sizing up a position. Nothing in the
+    // game itself asks yet.
+
+    /// How many of the play area's four corner cells `player_idx` has a tile in.
+    #[allow(dead_code)]
+    pub fn controlled_corners(&self, player_idx: usize) -> usize {
+        let color = self.players[player_idx].color;
+        let wall = self.wall_thickness();
+        let last = self.play_area_dim() - 1;
+        [(0, 0), (0, last), (last, 0), (last, last)]
+            .into_iter()
+            .filter(|&(row, col)| self.board[row + wall][col + wall] == color)
+            .count()
+    }
+
+    /// How many cells `player_idx` could start a piece from right now: empty, diagonal to
+    /// their color and not touching it along an edge. Before their first move, that's just
+    /// wherever they're allowed to open.
+    #[allow(dead_code)]
+    pub fn frontier_size(&self, player_idx: usize) -> usize {
+        self.frontiers[player_idx].len()
+    }
+
+    /// How many empty cells `player_idx` could still get to: everything reachable from their
+    /// frontier by edges without passing through a cell that touches their color along an
+    /// edge, since those can never be theirs.
+    #[allow(dead_code)]
+    pub fn territory(&self, player_idx: usize) -> usize {
+        let color = self.players[player_idx].color;
+        let width = self.board.len();
+
+        let mut seen = self.frontiers[player_idx].clone();
+        let mut queue: Vec<usize> = seen.iter().collect();
+        while let Some(cell) = queue.pop() {
+            let (row, col) = (cell / width, cell % width);
+            for (r, c) in [
+                (row - 1, col),
+                (row + 1, col),
+                (row, col - 1),
+                (row, col + 1),
+            ] {
+                if self.claimable_by(r, c, color) && seen.insert(r * width + c) {
+                    queue.push(r * width + c);
+                }
+            }
+        }
+        seen.len()
+    }
+
+    /// Whether `color` could ever put a tile on board cell (`row`, `col`): it's empty and
+    /// doesn't touch `color` along an edge. Both only get less true as the board fills up.
+    /// The walls are never empty, so this never looks off the board.
+    fn claimable_by(&self, row: usize, col: usize, color: TileColor) -> bool {
+        self.board[row][col] == TileColor::Empty
+            && [
+                (row - 1, col),
+                (row + 1, col),
+                (row, col - 1),
+                (row, col + 1),
+            ]
+            .iter()
+            .all(|&(r, c)| self.board[r][c] != color)
+    }
+
+    /// Empty play area cells nobody will ever put a tile on, as (row, col)s from the top left.
+    /// For everyone still playing, none of their pieces fits over the cell, turned any which
+    /// way, without one of its tiles touching their color along an edge. Cells only stop
+    /// being claimable, so a dead cell stays dead. House rules that hold pieces back are
+    /// ignored, so some cells might be deader than this says, but never the other way round.
+    pub fn dead_cells(&self) -> Vec<(usize, usize)> {
+        let width = self.board.len();
+        let mut live = BitSet::with_capacity(width * width);
+        for player in self.players.iter().filter(|p| p.still_playing()) {
+            let color = player.color;
+            let shapes: Vec<piece::Shape> = player
+                .remaining_pieces
+                .iter()
+                .flat_map(|piece_id| piece::orientations(self.config.pieces.shape(piece_id)))
+                .map(|(_, shape)| shape)
+                .collect();
+            for row in 1..width - 1 {
+                'cell: for col in 1..width - 1 {
+                    if live.contains(row * width + col) || !self.claimable_by(row, col, color) {
+                        continue;
+                    }
+                    // Try every piece with each of its tiles over this cell. Open cells find
+                    // one straight away, and whatever fits marks its other cells live too, so
+                    // only the cells that really are dead cost much.
+                    for shape in &shapes {
+                        let tiles: SmallVec<[(usize, usize); 5]> = piece::cells(shape).collect();
+                        for &(dr, dc) in &tiles {
+                            let covered: Option<SmallVec<[(usize, usize); 5]>> = tiles
+                                .iter()
+                                .map(|&(r, c)| {
+                                    Some(((row + r).checked_sub(dr)?, (col + c).checked_sub(dc)?))
+                                })
+                                .collect();
+                            let Some(covered) = covered else {
+                                continue;
+                            };
+                            let fits = covered.iter().all(|&(r, c)| {
+                                r < width && c < width && self.claimable_by(r, c, color)
+                            });
+                            if fits {
+                                live.extend(covered.iter().map(|&(r, c)| r * width + c));
+                                continue 'cell;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        let wall = self.wall_thickness();
+        let dim = self.play_area_dim();
+        (0..dim)
+            .flat_map(|row| (0..dim).map(move |col| (row, col)))
+            .filter(|&(row, col)| {
+                let (r, c) = (row + wall, col + wall);
+                self.board[r][c] == TileColor::Empty && !live.contains(r * width + c)
+            })
+            .collect()
+    }
+
+    /// Lists every legal placement the current player could make right now. Each distinct
+    /// placement shows up exactly once, even for symmetric pieces.
+    pub fn enumerate_legal_moves(&self) -> Vec<Move> {
+        let mut seen = HashSet::new();
+        self.legal_moves_for(self.current_player)
+            .filter(|mv| seen.insert(*mv))
+            .collect()
+    }
+
+    /// Which of `player_idx`'s remaining pieces could legally go down entirely inside `cells`
+    /// (play area (row, col)s), turned any which way. Late in the game, this says whether a
+    /// pocket of empty cells is still any use to them.
+    pub fn reachable_pieces_for_region(
+        &self,
+        cells: &[(usize, usize)],
+        player_idx: usize,
+    ) -> Vec<PieceID> {
+        let mut sorted = cells.to_vec();
+        sorted.sort_unstable();
+        let mut hasher = DefaultHasher::new();
+        self.board.hash(&mut hasher);
+        let key = (player_idx, sorted, hasher.finish());
+        if let Some((cached_key, reachable)) = &*self.pocket_cache.borrow() {
+            if *cached_key == key {
+                return reachable.clone();
+            }
+        }
+
+        // One pass over everything they could play, keeping the pieces that stay inside.
+        let wall = self.wall_thickness();
+        let region: HashSet<_> = cells.iter().map(|&(r, c)| (r + wall, c + wall)).collect();
+        let mut reachable = BitSet::new();
+        for mv in self.legal_moves_for(player_idx) {
+            if !reachable.contains(mv.piece_id)
+                && mv
+                    .board_cells(&self.config.pieces)
+                    .all(|cell| region.contains(&cell))
+            {
+                reachable.insert(mv.piece_id);
+            }
+        }
+        let reachable: Vec<_> = reachable.iter().collect();
+        *self.pocket_cache.borrow_mut() = Some((key, reachable.clone()));
+        reachable
+    }
+
+    /// A legal move for the current player to learn from, or `None` if they have to pass.
+    /// Goes for the biggest piece that fits, since getting rid of those early is good practice.
+    pub fn suggest_move(&self) -> Option<Move> {
+        self.enumerate_legal_moves()
+            .into_iter()
+            .max_by_key(|mv| mv.tile_count(&self.config.pieces))
+    }
+
+    /// Walks through legal placements for `player_idx` by anchoring each orientation of each
+    /// remaining piece so one of its tiles sits on a frontier cell. The same placement can
+    /// come up more than once if it covers several frontier cells.
+    fn legal_moves_for(&self, player_idx: usize) -> impl Iterator<Item = Move> + '_ {
+        // Working out the drawn piece means looking for somewhere it fits, so only do it once.
+        let drawn = self.config.draft_mode.then(|| self.drawn_piece(player_idx));
+        self.players[player_idx]
+            .remaining_pieces
+            .iter()
+            .filter(move |&piece_id| match drawn {
+                Some(drawn) => {
+                    drawn == Some(piece_id) && self.check_house_rules(player_idx, piece_id).is_ok()
+                }
+                None => self.may_play(player_idx, piece_id),
+            })
+            .flat_map(move |piece_id| self.placements(player_idx, piece_id))
+    }
+
+    /// Everywhere `piece_id` would fit for `player_idx`, going by the board alone. Whether the
+    /// house rules let them play it at all is up to `check_piece`.
+    fn placements(&self, player_idx: usize, piece_id: PieceID) -> impl Iterator<Item = Move> + '_ {
+        let width = self.board.len();
+        let wall = IVec2::splat(self.wall_thickness() as i32);
+        let frontier = &self.frontiers[player_idx];
+        piece::orientations(self.config.pieces.shape(piece_id))
+            .into_iter()
+            .flat_map(move |(orientation, shape)| {
+                let tiles: SmallVec<[(usize, usize); 5]> = piece::cells(&shape).collect();
+                frontier
+                    .iter()
+                    .flat_map(move |cell| {
+                        let (row, col) = ((cell / width) as i32, (cell % width) as i32);
+                        tiles
+                            .clone()
+                            .into_iter()
+                            .map(move |(dr, dc)| ivec2(col - dc as i32, row - dr as i32))
+                    })
+                    .filter(move |&corner| {
+                        self.fits_on_board(&shape, corner)
+                            && self._validate_move(player_idx, &shape, corner).is_ok()
+                    })
+                    .map(move |corner| Move {
+                        piece_id,
+                        orientation,
+                        corner: corner - wall,
+                    })
+            })
+    }
+
+    /// The old way of doing `can_make_move`: try every remaining piece on every tile of the
+    /// board for eight possible orientations. Slow, but obviously correct, so we keep it
+    /// around to check the fast version against.
+    #[cfg(test)]
+    fn can_make_move_brute_force(&self) -> bool {
+        let player = &self.players[self.current_player];
+        player.remaining_pieces.iter().any(|pc| {
+            let mut piece_buf = self.config.pieces.shape(pc);
+            use piece::{FlipDir, RotateDir};
+            // Do people find this hard to understand?
+            // I don't, but that's because I'm lambda-brained.
+            (0..2).any(|_| {
+                piece_buf = piece::flip(piece_buf, FlipDir::Vertical);
+                (0..4).any(|_| {
+                    piece_buf = piece::rotate(piece_buf, RotateDir::Right);
+                    (-4..self.board.len() as i32).any(|row| {
+                        (-4..self.board.len() as i32).any(|col| {
+                            let corner = ivec2(col, row);
+                            self.fits_on_board(&piece_buf, corner)
+                                && self
+                                    ._validate_move(self.current_player, &piece_buf, corner)
+                                    .is_ok()
+                        })
+                    })
+                })
+            })
+        })
+    }
+
+    /// Whether every tile of `shape` lands inside the walls when its top-left is at `corner`
+    /// (in board coordinates, like `valid_move`).
+    fn fits_on_board(&self, shape: &piece::Shape, corner: IVec2) -> bool {
+        let last = self.board.len() as i32 - 2;
+        piece::cells(shape).all(|(dr, dc)| {
+            let row = corner.y + dr as i32;
+            let col = corner.x + dc as i32;
+            (1..=last).contains(&row) && (1..=last).contains(&col)
+        })
+    }
+
+    /// What's at (row, col) on the board, walls and all. Validation goes through the bitboard
+    /// now, but the tests still count how often the old ways of doing it call this.
+    #[cfg(test)]
+    fn tile(&self, row: i32, col: i32) -> TileColor {
+        BOARD_READS.with(|reads| reads.set(reads.get() + 1));
+        self.board[row as usize][col as usize]
+    }
+
+    /// Whether a tile of `color` could go at (`row`, `col`) and touch its own color by a corner.
+    fn is_frontier_cell(&self, row: usize, col: usize, color: TileColor) -> bool {
+        // Also keeps us from looking past the walls, since walls are never empty.
+        if self.board[row][col] != TileColor::Empty {
+            return false;
+        }
+
+        let orthogonals = [
+            (row - 1, col),
+            (row, col - 1),
+            (row + 1, col),
+            (row, col + 1),
+        ];
+        let diagonals = [
+            (row - 1, col - 1),
+            (row + 1, col - 1),
+            (row - 1, col + 1),
+            (row + 1, col + 1),
+        ];
+
+        orthogonals.iter().all(|&(r, c)| self.board[r][c] != color)
+            && diagonals.iter().any(|&(r, c)| self.board[r][c] == color)
+    }
+
+    /// Whether `player_idx` has put anything on the board yet.
+    fn has_played(&self, player_idx: usize) -> bool {
+        self.players[player_idx].pieces_placed > 0
+    }
+
+    /// How many tiles were in the last piece `player_idx` put down, if they've put one down.
+    pub fn last_piece_size(&self, player_idx: usize) -> Option<usize> {
+        self.players[player_idx]
+            .last_piece
+            .map(|piece_id| self.config.pieces.size(piece_id))
+    }
+
+    /// Whether the house rules let `player_idx` put down `piece_id` right now.
+    pub fn may_play(&self, player_idx: usize, piece_id: PieceID) -> bool {
+        self.check_piece(player_idx, piece_id).is_ok()
+    }
+
+    /// The piece `player_idx` has to play when drawing pieces: the first one in their
+    /// `draw_order` they still have that would go down somewhere. Pieces that wouldn't are
+    /// skipped rather than leaving them stuck, so they only pass once nothing in their hand
+    /// fits. `None` if they're not drawing pieces at all.
+    pub fn drawn_piece(&self, player_idx: usize) -> Option<PieceID> {
+        let player = &self.players[player_idx];
+        let mut in_hand = player
+            .draw_order
+            .iter()
+            .copied()
+            .filter(|&id| player.remaining_pieces.contains(id));
+        let top = in_hand.clone().next();
+        in_hand
+            .find(|&id| {
+                self.check_house_rules(player_idx, id).is_ok()
+                    && self.placements(player_idx, id).next().is_some()
+            })
+            .or(top)
+    }
+
+    /// Like `may_play`, but says which house rule is in the way.
+    fn check_piece(&self, player_idx: usize, piece_id: PieceID) -> Result<(), MoveError> {
+        if self.config.draft_mode && self.drawn_piece(player_idx) != Some(piece_id) {
+            return Err(MoveError::NotDrawn);
+        }
+        self.check_house_rules(player_idx, piece_id)
+    }
+
+    /// The house rules other than drawing pieces, which `drawn_piece` goes by to skip ahead.
+    fn check_house_rules(&self, player_idx: usize, piece_id: PieceID) -> Result<(), MoveError> {
+        if self.config.descending_size
+            && self
+                .last_piece_size(player_idx)
+                .is_some_and(|last| self.config.pieces.size(piece_id) > last)
+        {
+            return Err(MoveError::BiggerThanLast);
+        }
+        // Piece 0 is the monomino. Nobody picks their pieces when drawing them, so drawing it
+        // first can't be held against them.
+        if self.config.allow_monominoes_first
+            || self.config.draft_mode
+            || piece_id != 0
+            || self.has_played(player_idx)
+        {
+            Ok(())
+        } else {
+            Err(MoveError::NotAllowedYet)
+        }
+    }
+
+    /// Finds the frontier for `player_idx` by scanning the whole board.
+    fn compute_frontier(&self, player_idx: usize) -> BitSet {
+        let color = self.players[player_idx].color;
+        let width = self.board.len();
+        let last = width - 2;
+        let mut frontier = BitSet::new();
+
+        // Nobody has anything to touch diagonally on their first move, so they get their
+        // start corner instead -- or the whole board, if the house rules say so.
+        if !self.has_played(player_idx) {
+            if self.config.first_move_must_cover_corner {
+                let wall = self.wall_thickness();
+                for (row, col) in self.start_cells(player_idx) {
+                    let (row, col) = (row + wall, col + wall);
+                    if self.board[row][col] == TileColor::Empty {
+                        frontier.insert(row * width + col);
+                    }
+                }
+            } else {
+                for row in 1..=last {
+                    for col in 1..=last {
+                        if self.board[row][col] == TileColor::Empty {
+                            frontier.insert(row * width + col);
+                        }
+                    }
+                }
+            }
+            return frontier;
+        }
+
+        for row in 1..=last {
+            for col in 1..=last {
+                if self.is_frontier_cell(row, col, color) {
+                    frontier.insert(row * width + col);
+                }
+            }
+        }
+        frontier
+    }
+
+    /// Whether nobody can move anymore: everyone's either out of pieces, forfeited, or passed
+    /// since the last piece went down. Running out of pieces or forfeiting only takes that
+    /// player out of the game. Solitaire doesn't wait for a pass -- it's over as soon as
+    /// nothing fits.
+    pub fn is_game_over(&self) -> bool {
+        let in_play = self.players.iter().filter(|p| p.still_playing()).count();
+        // The neutral color can't carry on by itself, since nobody's left to play it.
+        let anyone_in_play = self
+            .players
+            .iter()
+            .any(|p| !p.is_neutral && p.still_playing());
+        !anyone_in_play
+            || self.pass_counter >= in_play
+            || (self.config.solitaire && !self.can_make_move())
+    }
+
+    /// Picks up `piece_id`, or puts down whatever the current player is holding. When drawing
+    /// pieces, the only one they can pick up is the one they drew.
+    pub fn select_piece(&mut self, piece_id: Option<PieceID>) {
+        if self.config.draft_mode
+            && piece_id.is_some()
+            && piece_id != self.drawn_piece(self.current_player)
+        {
+            return;
+        }
+        self.selected_piece = piece_id;
+        self.legal_move_cache.set(None);
+        self.grab = IVec2::splat(2);
+        let shape = match piece_id {
+            Some(id) => self.config.pieces.shape(id),
+            None => piece::EMPTY_SHAPE,
+        };
+        self.piece_buffer = shape;
+    }
+
+    pub fn current_player(&self) -> &Player {
+        &self.players[self.current_player]
+    }
+
+    /// Whoever's deciding what happens this turn. That's the current player, unless it's
+    /// the neutral color's turn, which goes to each of the real players in seat order.
+    pub fn controller(&self) -> usize {
+        if !self.current_player().is_neutral {
+            return self.current_player;
+        }
+        // Anyone who forfeited is done deciding things, for the neutral color too.
+        let real_players: SmallVec<[usize; 4]> = (0..self.players.len())
+            .filter(|&idx| !self.players[idx].is_neutral && !self.players[idx].forfeited)
+            .collect();
+        if real_players.is_empty() {
+            return self.current_player;
+        }
+        real_players[self.neutral_turns % real_players.len()]
+    }
+
+    #[cfg(test)]
+    pub fn try_advance_turn(&mut self, row: usize, col: usize) -> bool {
+        let corner = match self.check_bounds_and_recenter(ivec2(col as i32, row as i32)) {
+            Some(coords) => coords,
+            None => return false,
+        };
+
+        let place_ok = self.valid_move(corner + IVec2::splat(self.wall_thickness() as i32));
+        if place_ok {
+            self.place_piece(corner).unwrap();
+            if self.turn_done() {
+                self.end_turn();
+            }
+        }
+        place_ok
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accept_correct_move() {
+        let mut game_state = GameState::new(4);
+        // L5 shape, bottom right:
+        game_state.select_piece(Some(10));
+        assert!(game_state.try_advance_turn(18, 18));
+
+        // Dot piece, bottom left:
+        game_state.select_piece(Some(0));
+        assert!(game_state.try_advance_turn(19, 0));
+
+        // Notch square, top left:
+        game_state.select_piece(Some(14));
+        assert!(game_state.try_advance_turn(0, 1));
+    }
+
+    #[test]
+    fn unnamed_players_go_by_their_color() {
+        let mut player = Player::new(TileColor::Yellow, piece::SHAPES.len());
+        assert_eq!(player.display_name(), format!("{:?}", TileColor::Yellow));
+        assert_eq!(player.display_name(), "Yellow");
+        player.name = Some("Ada".to_string());
+        assert_eq!(player.display_name(), "Ada");
+    }
+
+    /// Covers (`row`, `col`) on `game_state`'s play area, skipping the rules.
+    fn cover(game_state: &mut GameState, row: usize, col: usize, color: TileColor) {
+        let wall = game_state.wall_thickness();
+        game_state.board[row + wall][col + wall] = color;
+    }
+
+    #[test]
+    fn symmetric_boards_are_spotted() {
+        let mut game_state = GameState::new(4);
+        assert_eq!(game_state.is_symmetric(), Some(Symmetry::QuarterTurn));
+
+        // The same L in every corner, turned a quarter each time.
+        let last = game_state.play_area_dim() - 1;
+        let l_shape = [(0, 0), (1, 0), (2, 0), (2, 1)];
+        let colors = [
+            TileColor::Blue,
+            TileColor::Yellow,
+            TileColor::Red,
+            TileColor::Green,
+        ];
+        let mut pinwheel = game_state.clone();
+        for (turns, color) in colors.into_iter().enumerate() {
+            for &cell in &l_shape {
+                let (r, c) =
+                    (0..turns).fold(cell, |cell, _| Symmetry::QuarterTurn.apply(cell, last));
+                cover(&mut pinwheel, r, c, color);
+            }
+        }
+        assert_eq!(pinwheel.is_symmetric(), Some(Symmetry::QuarterTurn));
+
+        // Mirrored left to right only: the L and its reflection along the top edge.
+        for &(r, c) in &l_shape {
+            cover(&mut game_state, r, c, TileColor::Blue);
+            cover(&mut game_state, r, last - c, TileColor::Yellow);
+        }
+        assert_eq!(game_state.is_symmetric(), Some(Symmetry::MirrorLeftRight));
+
+        // One more tile off to the side breaks it.
+        cover(&mut game_state, 10, 3, TileColor::Red);
+        assert_eq!(game_state.is_symmetric(), None);
+    }
+
+    #[test]
+    fn reject_incorrect_move() {
+        let mut game_state = GameState::new(4);
+        // Where the dot would go with the cursor on (`row`, `col`). Its tile sits in the
+        // middle of its grid, so that's one up and to the left of the cursor, counting the wall.
+        let dot_at = |game_state: &GameState, (row, col): (i32, i32)| {
+            game_state.validate_move(ivec2(col, row) - IVec2::ONE)
+        };
+        let seats: Vec<_> = game_state.config.start_corners[..4]
+            .iter()
+            .map(|&(row, col)| {
+                let (row, col) = (row as i32, col as i32);
+                // Which way is into the board from this corner.
+                let inward = (if row == 0 { 1 } else { -1 }, if col == 0 { 1 } else { -1 });
+                ((row, col), inward)
+            })
+            .collect();
+
+        // First moves, for each seat in turn.
+        for (seat, &((row, col), (dr, dc))) in seats.iter().enumerate() {
+            assert_eq!(game_state.current_player, seat);
+
+            // Can't fit: LINE5 lies across the middle of its grid, so centered on the corner
+            // half of it hangs off the board.
+            game_state.select_piece(Some(9));
+            assert!(!game_state.try_advance_turn(row as usize, col as usize));
+            let corner = ivec2(col, row) - IVec2::ONE;
+            assert_eq!(
+                game_state.validate_move(corner),
+                Err(MoveError::OutOfBounds)
+            );
+
+            // Wrong corner: one in from their own, or on someone else's that's still empty.
+            game_state.select_piece(Some(0));
+            let (in_row, in_col) = (row + dr, col + dc);
+            assert!(!game_state.try_advance_turn(in_row as usize, in_col as usize));
+            assert_eq!(
+                dot_at(&game_state, (in_row, in_col)),
+                Err(MoveError::NoDiagonalContact)
+            );
+            if let Some(&(their_corner, _)) = seats.get(seat + 1) {
+                assert_eq!(
+                    dot_at(&game_state, their_corner),
+                    Err(MoveError::NoDiagonalContact)
+                );
+            }
+
+            // The square right in the corner is fine.
+            let opener = game_state
+                .enumerate_legal_moves()
+                .into_iter()
+                .find(|mv| mv.piece_id == 7)
+                .expect("The square fits in the corner.");
+            game_state.apply_move(&opener);
+        }
+
+        // Second moves, with the dot, now that everyone has a square in their corner.
+        for (seat, &((row, col), (dr, dc))) in seats.iter().enumerate() {
+            assert_eq!(game_state.current_player, seat);
+            game_state.select_piece(Some(0));
+
+            // On top of their own square.
+            assert_eq!(dot_at(&game_state, (row, col)), Err(MoveError::Overlap));
+            // Adjacent, same color: right below (or above) the square.
+            let beside = (row + 2 * dr, col);
+            assert!(!game_state.try_advance_turn(beside.0 as usize, beside.1 as usize));
+            assert_eq!(
+                dot_at(&game_state, beside),
+                Err(MoveError::AdjacentSameColor)
+            );
+            // Middle of nowhere.
+            assert!(!game_state.try_advance_turn(10, 10));
+            assert_eq!(
+                dot_at(&game_state, (10, 10)),
+                Err(MoveError::NoDiagonalContact)
+            );
+            // Off the square's far corner is where it can go.
+            let diagonal = (row + 2 * dr, col + 2 * dc);
+            assert_eq!(dot_at(&game_state, diagonal), Ok(()));
+            assert!(game_state.try_advance_turn(diagonal.0 as usize, diagonal.1 as usize));
+        }
+    }
+
+    /// Tiny xorshift so the property test doesn't pull in a whole RNG crate.
+    struct XorShift(u64);
+
+    impl XorShift {
+        fn next(&mut self, bound: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % bound as u64) as usize
+        }
+    }
+
+    #[test]
+    fn frontier_scan_matches_brute_force() {
+        use piece::Orientation;
+
+        for seed in 1..=8u64 {
+            let mut rng = XorShift(seed.wrapping_mul(0x9e3779b97f4a7c15));
+            let mut game_state = GameState::new(2 + seed as usize % 3);
+            let width = game_state.board.len();
+            let mut failed_turns = 0;
+
+            // Play until nobody manages to put anything down for a full round.
+            while failed_turns < game_state.players.len() {
+                assert_eq!(
+                    game_state.can_make_move(),
+                    game_state.can_make_move_brute_force()
+                );
+                for (idx, frontier) in game_state.frontiers.iter().enumerate() {
+                    assert_eq!(*frontier, game_state.compute_frontier(idx));
+                }
+
+                // Throw random pieces at random spots on the frontier. Plenty of these won't
+                // stick, which is fine.
+                let remaining: Vec<_> = game_state
+                    .current_player()
+                    .remaining_pieces
+                    .iter()
+                    .collect();
+                let frontier: Vec<_> = game_state.frontiers[game_state.current_player]
+                    .iter()
+                    .collect();
+                let placed = !remaining.is_empty()
+                    && !frontier.is_empty()
+                    && (0..50).any(|_| {
+                        let piece_id = remaining[rng.next(remaining.len())];
+                        game_state.select_piece(Some(piece_id));
+                        game_state.piece_buffer =
+                            Orientation::ALL[rng.next(8)].apply(game_state.piece_buffer);
+
+                        let cell = frontier[rng.next(frontier.len())];
+                        let tiles: Vec<_> = piece::cells(&game_state.piece_buffer).collect();
+                        let (dr, dc) = tiles[rng.next(tiles.len())];
+                        let corner = ivec2(
+                            (cell % width) as i32 - dc as i32,
+                            (cell / width) as i32 - dr as i32,
+                        );
+                        let ok = game_state.fits_on_board(&game_state.piece_buffer, corner)
+                            && game_state.valid_move(corner);
+                        if ok {
+                            game_state.place_piece(corner - IVec2::ONE).unwrap();
+                        }
+                        ok
+                    });
+
+                failed_turns = if placed { 0 } else { failed_turns + 1 };
+                game_state.end_turn();
+            }
+        }
+    }
+
+    #[test]
+    fn cached_answer_matches_fresh_one() {
+        let mut game_state = GameState::new(2);
+        // (piece, row, col) -- alternating blue in the bottom right, yellow in the top left.
+        let script = [
+            (10, 18, 18),
+            (10, 2, 0),
+            (0, 15, 17),
+            (0, 4, 2),
+            (1, 14, 16),
+            (1, 6, 3),
+            (7, 12, 14),
+            (7, 8, 4),
+        ];
+
+        for (piece_id, row, col) in script {
+            for _ in 0..2 {
+                assert_eq!(
+                    game_state.can_make_move(),
+                    game_state.any_legal_move_for(game_state.current_player)
+                );
+            }
+            game_state.select_piece(Some(piece_id));
+            assert!(game_state.try_advance_turn(row, col));
+        }
+
+        // Either player can still move, and asking about the other one leaves the turn alone.
+        let current = game_state.current_player;
+        assert!(game_state.any_legal_move_for(1 - current));
+        assert_eq!(game_state.current_player, current);
+    }
+
+    #[test]
+    fn enumerated_moves_are_legal() {
+        let mut game_state = GameState::new(2);
+        let moves = game_state.enumerate_legal_moves();
+        assert!(!moves.is_empty());
+
+        for mv in &moves {
+            game_state.piece_buffer = mv.shape(&game_state.config.pieces);
+            assert!(game_state.valid_move(mv.corner + IVec2::ONE));
+        }
+
+        // Someone who's out of pieces has nothing left to do.
+        game_state.players[0].remaining_pieces.clear();
+        assert!(game_state.enumerate_legal_moves().is_empty());
+    }
+
+    #[test]
+    fn suggestions_only_when_moves_exist() {
+        let mut game_state = GameState::new(4);
+        // Follow the suggestions all the way to the end, so some players get stuck.
+        while !game_state.is_game_over() {
+            let suggestion = game_state.suggest_move();
+            assert_eq!(suggestion.is_some(), game_state.can_make_move());
+            let Some(mv) = suggestion else {
+                game_state.pass_turn();
+                continue;
+            };
+
+            let biggest = game_state
+                .enumerate_legal_moves()
+                .iter()
+                .map(|mv| mv.tile_count(&game_state.config.pieces))
+                .max();
+            assert_eq!(Some(mv.tile_count(&game_state.config.pieces)), biggest);
+            game_state.select_piece(Some(mv.piece_id));
+            game_state.piece_buffer = mv.shape(&game_state.config.pieces);
+            assert!(game_state.valid_move(mv.corner + IVec2::ONE));
+            // The hint button points the cursor at the middle of the grid, which had
+            // better lead back to the same spot.
+            let center = mv.corner + IVec2::splat(2);
+            assert_eq!(
+                game_state.check_bounds_and_recenter(center),
+                Some(mv.corner)
+            );
+            game_state.place_piece(mv.corner).unwrap();
+            game_state.end_turn();
+        }
+    }
+
+    #[test]
+    fn everyone_starts_in_a_corner() {
+        let mut game_state = GameState::new(4);
+        let mut corners = Vec::new();
+        for _ in 0..4 {
+            corners.push(game_state.first_move_corners()[0]);
+            game_state.end_turn();
+        }
+        corners.sort();
+        assert_eq!(corners, [(0, 0), (0, 19), (19, 0), (19, 19)]);
+
+        // Once you're on the board, you're on your own.
+        game_state.select_piece(Some(0));
+        assert!(game_state.try_advance_turn(19, 19));
+        game_state.current_player = 0;
+        assert!(game_state.first_move_corners().is_empty());
+
+        game_state.config.first_move_must_cover_corner = false;
+        game_state.current_player = 1;
+        assert!(game_state.first_move_corners().is_empty());
+    }
+
+    #[test]
+    fn first_move_covers_the_corner() {
+        let mut game_state = GameState::new(2);
+        game_state.select_piece(Some(7));
+        // The square tucked into the corner covers it...
+        assert!(game_state.valid_move(ivec2(17, 18)));
+        // ...but one step up and to the left only touches it diagonally, which isn't enough.
+        assert_eq!(
+            game_state.validate_move(ivec2(16, 17)),
+            Err(MoveError::NoDiagonalContact)
+        );
+        // Nor is hugging it along an edge.
+        assert_eq!(
+            game_state.validate_move(ivec2(16, 18)),
+            Err(MoveError::NoDiagonalContact)
+        );
+
+        assert!(game_state.try_advance_turn(19, 18));
+        assert_eq!(game_state.players[0].pieces_placed, 1);
+        assert_eq!(game_state.players[1].pieces_placed, 0);
+    }
+
+    #[test]
+    fn classic_board_dimensions() {
+        let game_state = GameState::new(4);
+        assert_eq!(game_state.play_area_dim(), 20);
+        assert_eq!(game_state.wall_thickness(), 1);
+        assert_eq!(game_state.board.len(), 22);
+
+        let duo = GameState::with_players(Player::default_order(2), GameConfig::duo());
+        assert_eq!(duo.play_area_dim(), 14);
+        assert_eq!(duo.wall_thickness(), 1);
+    }
+
+    #[test]
+    fn count_occupied_cells() {
+        let mut game_state = GameState::new(4);
+        assert_eq!(game_state.occupied_cells().count(), 0);
+
+        let mut tiles_placed = 0;
+        for _ in 0..8 {
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            tiles_placed += mv.tile_count(&game_state.config.pieces);
+            game_state.apply_move(&mv);
+        }
+        assert_eq!(game_state.occupied_cells().count(), tiles_placed);
+        // Every cell it hands back really is that color.
+        for (row, col, tile) in game_state.occupied_cells() {
+            assert_eq!(game_state.board[row + 1][col + 1], tile);
+        }
+    }
+
+    #[test]
+    fn pie_rule_swaps_seats() {
+        let config = GameConfig::classic().pie_rule(true);
+        let mut game_state = GameState::with_players(Player::default_order(2), config.clone());
+        assert!(!game_state.pie_swap_available());
+        let opening = game_state.enumerate_legal_moves()[0];
+        game_state.apply_move(&opening);
+        assert!(game_state.pie_swap_available());
+        game_state.swap_seats();
+        // It's a one-time offer.
+        assert!(!game_state.pie_swap_available());
+
+        // Should look just like a game where Yellow sat first and Blue opened out of turn.
+        let mut corners = config.start_corners.clone();
+        corners.swap(0, 2);
+        let mut players = Player::default_order(2);
+        players.swap(0, 1);
+        let mut mirrored = GameState::with_players(players, config.start_corners(corners));
+        mirrored.current_player = 1;
+        mirrored.apply_move(&opening);
+
+        assert_eq!(game_state.board, mirrored.board);
+        assert_eq!(game_state.current_player, mirrored.current_player);
+        assert_eq!(game_state.config, mirrored.config);
+        for (a, b) in game_state.players.iter().zip(&mirrored.players) {
+            assert_eq!(a.color, b.color);
+            assert_eq!(a.remaining_pieces, b.remaining_pieces);
+            assert_eq!(a.last_piece, b.last_piece);
+            assert_eq!(a.pieces_placed, b.pieces_placed);
+        }
+        // Yellow still has to start from its own corner.
+        assert_eq!(game_state.first_move_corners().as_slice(), [(0, 0)]);
+        assert_eq!(
+            game_state.enumerate_legal_moves(),
+            mirrored.enumerate_legal_moves()
+        );
+    }
+
+    /// How validation worked before the bitboard: a tile at a time, straight off `board`.
+    fn validate_cell_by_cell(
+        game_state: &GameState,
+        player_idx: usize,
+        piece_buffer: &piece::Shape,
+        corner: IVec2,
+    ) -> Result<(), MoveError> {
+        let IVec2 {
+            x: adj_col,
+            y: adj_row,
+        } = corner;
+        let color = game_state.players[player_idx].color;
+        let width = game_state.board.len();
+        let mut any_diagonal_matches = false;
+        // There's nothing of ours to touch on our first move, so the piece has to cover our
+        // start corner instead.
+        let start_cell = (game_state.config.first_move_must_cover_corner
+            && !game_state.has_played(player_idx))
+        .then(|| {
+            let (row, col) = game_state.start_corner(player_idx);
+            let wall = game_state.wall_thickness() as i32;
+            (row as i32 + wall, col as i32 + wall)
+        });
+        let mut covers_start = false;
+        let (footprint, (top, left)) = piece::footprint(piece_buffer);
+        let (top, left) = (adj_row + top, adj_col + left);
+
+        for &(dr, dc) in &footprint.cells {
+            let (r_coord, c_coord) = (top + dr, left + dc);
+
+            // Every tile has to land inside the wall, on an empty cell.
+            let in_bounds =
+                (0..width as i32).contains(&r_coord) && (0..width as i32).contains(&c_coord);
+            match in_bounds.then(|| game_state.tile(r_coord, c_coord)) {
+                Some(TileColor::Empty) => {}
+                None | Some(TileColor::Wall) => return Err(MoveError::OutOfBounds),
+                Some(_) => return Err(MoveError::Overlap),
+            }
+
+            // Some tile has to touch our color diagonally. The frontier already tracks which
+            // cells do that (and holds our start corner on our first move), so just ask it.
+            any_diagonal_matches = any_diagonal_matches
+                || game_state.frontiers[player_idx]
+                    .contains(r_coord as usize * width + c_coord as usize);
+            covers_start |= start_cell == Some((r_coord, c_coord));
+        }
+
+        // No tiles adjacent. The piece is inside the wall, so everything around it is on the board.
+        if footprint
+            .edges
+            .iter()
+            .any(|&(dr, dc)| game_state.tile(top + dr, left + dc) == color)
+        {
+            return Err(MoveError::AdjacentSameColor);
+        }
+
+        let connected = match start_cell {
+            Some(_) => covers_start,
+            None => any_diagonal_matches,
+        };
+        if connected {
+            Ok(())
+        } else {
+            Err(MoveError::NoDiagonalContact)
+        }
+    }
+
+    /// How validation used to work: look all around every tile of the piece.
+    fn validate_tile_by_tile(
+        game_state: &GameState,
+        piece_buffer: &piece::Shape,
+        corner: IVec2,
+    ) -> bool {
+        let player_idx = game_state.current_player;
+        let color = game_state.players[player_idx].color;
+        let width = game_state.board.len() as i32;
+        let mut any_diagonal_matches = false;
+        for (dr, dc) in piece::cells(piece_buffer) {
+            let (r, c) = (corner.y + dr as i32, corner.x + dc as i32);
+            if !(0..width).contains(&r) || !(0..width).contains(&c) {
+                return false;
+            }
+            if game_state.tile(r, c) == TileColor::Wall || game_state.tile(r, c) != TileColor::Empty
+            {
+                return false;
+            }
+            let adjacents = [(r - 1, c), (r, c - 1), (r + 1, c), (r, c + 1)];
+            if adjacents
+                .into_iter()
+                .any(|(r, c)| game_state.tile(r, c) == color)
+            {
+                return false;
+            }
+            any_diagonal_matches |=
+                game_state.frontiers[player_idx].contains((r * width + c) as usize);
+        }
+        any_diagonal_matches
+    }
+
+    #[test]
+    fn footprints_read_the_board_less() {
+        let mut game_state = GameState::new(4);
+        for _ in 0..12 {
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            game_state.apply_move(&mv);
+        }
+
+        let reads = || BOARD_READS.with(Cell::get);
+        let (mut old_reads, mut new_reads) = (0, 0);
+        let width = game_state.board.len() as i32;
+        for piece_id in game_state.current_player().remaining_pieces.iter() {
+            for (_, shape) in piece::orientations(piece::SHAPES[piece_id]) {
+                for row in -4..width {
+                    for col in -4..width {
+                        let corner = ivec2(col, row);
+                        let before = reads();
+                        let old = validate_tile_by_tile(&game_state, &shape, corner);
+                        let middle = reads();
+                        let new = game_state
+                            ._validate_move(game_state.current_player, &shape, corner)
+                            .is_ok();
+                        old_reads += middle - before;
+                        new_reads += reads() - middle;
+                        assert_eq!(old, new, "piece {piece_id} at {corner}");
+                    }
+                }
+            }
+        }
+        assert!(
+            new_reads * 3 < old_reads * 2,
+            "{new_reads} reads vs. {old_reads} before"
+        );
+    }
+
+    #[test]
+    fn bitboard_agrees_with_cell_by_cell() {
+        let rng = RandGenerator::new();
+        rng.srand(816);
+        for game in 0..8 {
+            let config = if game % 4 == 3 {
+                GameConfig::duo()
+            } else {
+                GameConfig::classic()
+            };
+            let players = config.start_corners.len().min(4);
+            let mut game_state = GameState::with_players(Player::default_order(players), config);
+            for _ in 0..rng.gen_range(0, 40) {
+                match ai::choose_move_with(&game_state, ai::Difficulty::Easy, &rng) {
+                    Some(mv) => game_state.apply_move(&mv),
+                    None => game_state.end_turn(),
+                }
+            }
+
+            let width = game_state.board.len() as i32;
+            for _ in 0..2000 {
+                let player_idx = rng.gen_range(0, game_state.players.len());
+                let piece_id = rng.gen_range(0, piece::SHAPES.len());
+                let shapes = piece::orientations(piece::SHAPES[piece_id]);
+                let (_, shape) = shapes[rng.gen_range(0, shapes.len())];
+                let corner = ivec2(rng.gen_range(-6, width + 1), rng.gen_range(-6, width + 1));
+                assert_eq!(
+                    game_state._validate_move(player_idx, &shape, corner),
+                    validate_cell_by_cell(&game_state, player_idx, &shape, corner),
+                    "game {game}, player {player_idx}, piece {piece_id} at {corner}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn drafts_are_seeded() {
+        let config = GameConfig::classic().draft_mode(true).draft_seed(42);
+        let draft = |config: &GameConfig| {
+            GameState::with_players(Player::default_order(2), config.clone())
+                .players
+                .iter()
+                .map(|p| p.draw_order.clone())
+                .collect::<Vec<_>>()
+        };
+        let orders = draft(&config);
+        assert_eq!(orders, draft(&config));
+        assert_ne!(orders, draft(&config.clone().draft_seed(43)));
+        // Everyone draws from the same shuffle, which still has every piece in it once.
+        assert_eq!(orders[0], orders[1]);
+        let mut sorted = orders[0].clone();
+        sorted.sort();
+        assert_eq!(sorted, (0..21).collect::<Vec<_>>());
+
+        // Only the piece on top can be picked up or played.
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        let drawn = orders[0][0];
+        let other = orders[0][1];
+        game_state.select_piece(Some(other));
+        assert_eq!(game_state.selected_piece, None);
+        game_state.select_piece(Some(drawn));
+        assert_eq!(game_state.selected_piece, Some(drawn));
+        assert!(game_state
+            .enumerate_legal_moves()
+            .iter()
+            .all(|mv| mv.piece_id == drawn));
+    }
+
+    #[test]
+    fn drawn_pieces_that_cant_go_down_get_skipped() {
+        let config = GameConfig::classic()
+            .draft_mode(true)
+            .allow_monominoes_first(false)
+            .descending_size(true);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        // Blue draws the domino, then the big L, then the dot. Yellow draws the dot first.
+        let rest = 3..21;
+        game_state.players[0].draw_order = [1, 10, 0].into_iter().chain(rest.clone()).collect();
+        game_state.players[1].draw_order = [0, 1, 2].into_iter().chain(rest).collect();
+
+        // Drawing the dot first isn't a choice, so the opening ban doesn't apply.
+        assert_eq!(game_state.drawn_piece(1), Some(0));
+        assert!(game_state.may_play(1, 0));
+
+        for _ in 0..2 {
+            let mv = game_state.enumerate_legal_moves()[0];
+            game_state.apply_move(&mv);
+        }
+        // After the domino the L is too big, so Blue goes on to the dot instead of passing.
+        assert_eq!(game_state.drawn_piece(0), Some(0));
+        assert!(!game_state.may_play(0, 10));
+        assert!(game_state.can_make_move());
+
+        // With nothing small enough left after the dot, the top of the pile stays put.
+        let mv = game_state.enumerate_legal_moves()[0];
+        assert_eq!(mv.piece_id, 0);
+        game_state.apply_move(&mv);
+        // Yellow's in the same spot after their dot.
+        assert!(!game_state.can_make_move());
+        game_state.pass_turn();
+        assert_eq!(game_state.drawn_piece(0), Some(10));
+        assert!(!game_state.can_make_move());
+    }
+
+    #[test]
+    fn highlight_where_pieces_fit() {
+        let mut game_state = GameState::new(2);
+        // The dot fits in exactly one place on the first move: Blue's corner.
+        game_state.select_piece(Some(0));
+        assert_eq!(game_state.legal_anchors(), HashSet::from([ivec2(19, 19)]));
+
+        // Turning the bar sideways moves where it can go.
+        game_state.select_piece(Some(9));
+        let upright = game_state.legal_anchors();
+        assert!(!upright.is_empty());
+        game_state.piece_buffer = piece::rotate(game_state.piece_buffer, piece::RotateDir::Right);
+        let sideways = game_state.legal_anchors();
+        assert!(!sideways.is_empty());
+        assert_ne!(upright, sideways);
+
+        // Nowhere to go means nothing to highlight.
+        let config = GameConfig::classic().allow_monominoes_first(false);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        game_state.select_piece(Some(0));
+        assert!(game_state.legal_anchors().is_empty());
+        game_state.select_piece(None);
+        assert!(game_state.legal_anchors().is_empty());
+    }
+
+    #[test]
+    fn descending_sizes_only_under_the_house_rule() {
+        // Both players open with a three-tile piece.
+        let opened = |descending: bool| {
+            let config = GameConfig::classic().descending_size(descending);
+            let mut game_state = GameState::with_players(Player::default_order(2), config);
+            for _ in 0..2 {
+                let mv = game_state
+                    .enumerate_legal_moves()
+                    .into_iter()
+                    .find(|mv| mv.tile_count(&game_state.config.pieces) == 3)
+                    .unwrap();
+                game_state.apply_move(&mv);
+            }
+            game_state
+        };
+        let (free, strict) = (opened(false), opened(true));
+        assert_eq!(strict.last_piece_size(0), Some(3));
+
+        let bigger = free
+            .enumerate_legal_moves()
+            .into_iter()
+            .find(|mv| mv.tile_count(&free.config.pieces) == 5)
+            .expect("Blue has room for a pentomino.");
+        assert_eq!(free.check_move(&bigger), Ok(()));
+        assert_eq!(strict.check_move(&bigger), Err(MoveError::BiggerThanLast));
+        // Nothing bigger turns up as a legal move either, but smaller ones still do.
+        let sizes: Vec<_> = strict
+            .enumerate_legal_moves()
+            .iter()
+            .map(|mv| mv.tile_count(&strict.config.pieces))
+            .collect();
+        assert!(sizes.iter().all(|&size| size <= 3));
+        assert!(sizes.contains(&2));
+    }
+
+    #[test]
+    fn rotated_pieces_fit_flush_against_the_walls() {
+        let mut game_state = GameState::new(2);
         game_state.select_piece(Some(10));
-        assert!(game_state.try_advance_turn(18, 18));
+        // An L5 on its side sits on and above the middle of its grid, to the left:
+        // ...#
+        // ####
+        game_state.piece_buffer = piece::rotate(game_state.piece_buffer, piece::RotateDir::Left);
+
+        // Its long edge lies along the bottom wall with its foot against the right wall,
+        // which is just where Blue's corner is.
+        assert_eq!(
+            game_state.check_bounds_and_recenter(ivec2(18, 19)),
+            Some(ivec2(16, 17))
+        );
+        // One more step right or down and it's through the wall.
+        assert_eq!(game_state.check_bounds_and_recenter(ivec2(19, 19)), None);
+        assert_eq!(game_state.check_bounds_and_recenter(ivec2(18, 20)), None);
+        assert!(game_state.try_advance_turn(19, 18));
+
+        // Turned halfway round instead, it hangs below and right of the middle:
+        // ##
+        // .#
+        // .#
+        // .#
+        game_state.select_piece(Some(10));
+        game_state.piece_buffer = piece::rotate(game_state.piece_buffer, piece::RotateDir::Right);
+        game_state.piece_buffer = piece::rotate(game_state.piece_buffer, piece::RotateDir::Right);
+        // So the cursor can go right up to the right wall, but not as close to the bottom.
+        assert_eq!(
+            game_state.check_bounds_and_recenter(ivec2(19, 17)),
+            Some(ivec2(17, 15))
+        );
+        assert_eq!(game_state.check_bounds_and_recenter(ivec2(20, 17)), None);
+        assert_eq!(game_state.check_bounds_and_recenter(ivec2(19, 18)), None);
+    }
+
+    #[test]
+    fn everyone_plays_the_neutral_color() {
+        let config = GameConfig::classic().three_player_neutral(true);
+        let mut game_state = GameState::with_players(Player::default_order(3), config);
+        assert_eq!(game_state.players.len(), 4);
+        let neutral = &game_state.players[3];
+        assert!(neutral.is_neutral);
+        assert_eq!(neutral.color, TileColor::Green);
+
+        // Three rounds: each player gets their own turn, then one for the neutral color.
+        let mut neutral_left = game_state.remaining_tile_count(3);
+        for round in 0..3 {
+            for seat in 0..3 {
+                assert_eq!(game_state.controller(), seat);
+                let mv = ai::choose_move(&game_state).expect("Early game has room.");
+                game_state.apply_move(&mv);
+            }
+            assert_eq!(game_state.current_player, 3);
+            assert_eq!(game_state.controller(), round);
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            game_state.apply_move(&mv);
+
+            let left = game_state.remaining_tile_count(3);
+            assert!(left < neutral_left);
+            neutral_left = left;
+        }
+
+        // Everyone passing includes the neutral color.
+        for _ in 0..3 {
+            game_state.pass_turn();
+        }
+        assert!(!game_state.is_game_over());
+        game_state.pass_turn();
+        assert!(game_state.is_game_over());
+
+        let config = GameConfig::classic().three_player_neutral(true);
+        assert_eq!(
+            GameState::try_with_players(Player::default_order(2), config).err(),
+            Some(SetupError::NeutralNeedsThree)
+        );
+    }
+
+    #[test]
+    fn forfeits_leave_the_neutral_color() {
+        let config = GameConfig::classic().three_player_neutral(true);
+        let mut game_state = GameState::with_players(Player::default_order(3), config);
+
+        // Blue gives up right away, so the neutral color's turns go to Yellow and Red.
+        game_state.forfeit(0);
+        for round in 0..4 {
+            for seat in 1..3 {
+                assert_eq!(game_state.current_player, seat);
+                let mv = ai::choose_move(&game_state).expect("Early game has room.");
+                game_state.apply_move(&mv);
+            }
+            assert_eq!(game_state.current_player, 3);
+            assert_eq!(game_state.controller(), 1 + round % 2);
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            game_state.apply_move(&mv);
+        }
+
+        // With only the neutral color left, the game's over, pieces and all.
+        game_state.forfeit(1);
+        assert!(!game_state.is_game_over());
+        game_state.forfeit(2);
+        assert!(game_state.players[3].still_playing());
+        assert!(game_state.is_game_over());
+    }
+
+    #[test]
+    fn slow_players_get_passed() {
+        let config = GameConfig::classic().turn_seconds(Some(30));
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+
+        // The first look at the clock starts it.
+        assert!(!game_state.turn_expired(100.));
+        assert_eq!(game_state.turn_time_left(115.), Some(0.5));
+        assert!(!game_state.turn_expired(129.9));
+        assert!(game_state.turn_expired(130.));
+        game_state.pass_turn();
+        assert_eq!(game_state.pass_counter, 1);
+        assert_eq!(game_state.current_player, 1);
+
+        // The next player gets a fresh clock.
+        assert_eq!(game_state.turn_time_left(130.), None);
+        assert!(!game_state.turn_expired(130.));
+        assert!(!game_state.turn_expired(150.));
+
+        // Untimed games never run out.
+        let mut untimed = GameState::new(2);
+        assert!(!untimed.turn_expired(0.));
+        assert!(!untimed.turn_expired(1e9));
+        assert_eq!(untimed.turn_time_left(1e9), None);
+    }
+
+    #[test]
+    fn held_pieces_become_moves() {
+        let mut game_state = GameState::new(2);
+        game_state.select_piece(Some(15));
+        // However the player turned it, the move has to put the tiles where they see them.
+        for turns in 0..4 {
+            let corner = game_state.check_bounds_and_recenter(ivec2(10, 10)).unwrap();
+            let mv = game_state.held_move(corner).unwrap();
+            let mut expected: Vec<_> = piece::cells(&game_state.piece_buffer)
+                .map(|(r, c)| (r + corner.y as usize + 1, c + corner.x as usize + 1))
+                .collect();
+            let mut actual: Vec<_> = mv.board_cells(&game_state.config.pieces).collect();
+            expected.sort();
+            actual.sort();
+            assert_eq!(actual, expected, "after {turns} turns");
+
+            game_state.piece_buffer = piece::flip(
+                piece::rotate(game_state.piece_buffer, piece::RotateDir::Left),
+                piece::FlipDir::Horizontal,
+            );
+        }
+    }
+
+    #[test]
+    fn explain_rejected_moves() {
+        let mut game_state = GameState::new(2);
+        game_state.select_piece(Some(9));
+        // LINE5 stands up in the middle column, so this pokes into the top wall...
+        assert_eq!(
+            game_state.validate_move(ivec2(0, 0)),
+            Err(MoveError::OutOfBounds)
+        );
+        // ...and this doesn't even land on the board.
+        assert_eq!(
+            game_state.validate_move(ivec2(-5, -5)),
+            Err(MoveError::OutOfBounds)
+        );
+        // Nowhere near the start corner.
+        assert_eq!(
+            game_state.validate_move(ivec2(3, 3)),
+            Err(MoveError::NoDiagonalContact)
+        );
 
-        // Dot piece, bottom left:
         game_state.select_piece(Some(0));
-        assert!(game_state.try_advance_turn(19, 0));
+        assert!(game_state.try_advance_turn(19, 19));
+        // Player 1 tries to stack their dot on top.
+        game_state.select_piece(Some(0));
+        assert_eq!(
+            game_state.validate_move(ivec2(18, 18)),
+            Err(MoveError::Overlap)
+        );
+        game_state.end_turn();
 
-        // Notch square, top left:
-        game_state.select_piece(Some(14));
-        assert!(game_state.try_advance_turn(0, 1));
+        // Player 0 goes right next to their own dot.
+        game_state.select_piece(Some(1));
+        assert_eq!(
+            game_state.validate_move(ivec2(17, 18)),
+            Err(MoveError::AdjacentSameColor)
+        );
+
+        game_state.config.allow_monominoes_first = false;
+        game_state.end_turn();
+        game_state.select_piece(Some(0));
+        assert_eq!(
+            game_state.validate_move(ivec2(-1, 18)),
+            Err(MoveError::NotAllowedYet)
+        );
     }
 
     #[test]
-    fn reject_incorrect_move() {
-        // Can't fit
+    fn everyone_passing_ends_the_game() {
+        let mut game_state = GameState::new(4);
+        // Nobody's stuck on an empty board.
+        assert!(!game_state.may_pass());
+        game_state.config.allow_voluntary_pass = true;
+        assert!(game_state.may_pass());
+
+        for _ in 0..3 {
+            game_state.pass_turn();
+            assert!(!game_state.is_game_over());
+        }
+        // Placing something means not everyone passed after all.
+        game_state.select_piece(Some(0));
+        assert!(game_state.try_advance_turn(0, 19));
+        assert_eq!(game_state.pass_counter, 0);
+
+        for _ in 0..4 {
+            assert!(!game_state.is_game_over());
+            game_state.pass_turn();
+        }
+        assert!(game_state.is_game_over());
+    }
+
+    #[test]
+    fn pieces_that_reach_a_pocket() {
+        let mut game_state = GameState::new(2);
+        // Wall off everything but a pocket shaped like the notched square, with one of
+        // Blue's tiles touching its top-left corner.
+        let pocket = [(10, 10), (10, 11), (11, 10), (11, 11), (12, 10)];
+        let wall = game_state.wall_thickness();
+        for (r, row) in game_state.board.iter_mut().enumerate() {
+            for (c, tile) in row.iter_mut().enumerate() {
+                if !pocket.contains(&(r.wrapping_sub(wall), c.wrapping_sub(wall))) {
+                    *tile = TileColor::Wall;
+                }
+            }
+        }
+        game_state.board[9 + wall][9 + wall] = TileColor::Blue;
+        game_state.players[0].pieces_placed = 1;
+        game_state.players[1].pieces_placed = 1;
+        game_state.refresh_frontiers();
+
+        let names: Vec<_> = game_state
+            .reachable_pieces_for_region(&pocket, 0)
+            .into_iter()
+            .map(|piece_id| piece::PIECE_NAMES[piece_id])
+            .collect();
+        // The zig-zag fits too, but not while covering the corner Blue touches.
+        assert_eq!(
+            names,
+            [
+                "DOT",
+                "LINE2",
+                "LINE3",
+                "L3",
+                "L4",
+                "SQUARE",
+                "TEE",
+                "NOTCH-SQUARE"
+            ]
+        );
+
+        // Yellow has nothing touching the pocket at all.
+        assert!(game_state
+            .reachable_pieces_for_region(&pocket, 1)
+            .is_empty());
+        // And without the top row, nothing touches Blue's corner either.
+        assert!(game_state
+            .reachable_pieces_for_region(&pocket[2..], 0)
+            .is_empty());
+    }
+
+    #[test]
+    fn counter_clockwise_seating() {
+        let config = GameConfig::classic().seating(Seating::CounterClockwise);
+        let mut game_state = GameState::with_players(Player::default_order(4), config.clone());
+        let mut seats = Vec::new();
+        for _ in 0..4 {
+            seats.push((
+                game_state.current_player,
+                game_state.first_move_corners()[0],
+            ));
+            let mv = ai::choose_move(&game_state).expect("Every corner's open.");
+            game_state.apply_move(&mv);
+        }
+        // Seats come up in reverse, and each one sits at the next corner back around the
+        // board, so everyone still has a corner to open in.
+        assert_eq!(
+            seats,
+            [(0, (19, 19)), (3, (0, 19)), (2, (0, 0)), (1, (19, 0))]
+        );
+        assert_eq!(game_state.current_player, 0);
+        assert!((0..4).all(|idx| game_state.players[idx].pieces_placed == 1));
+
+        // With a seat to spare, the empty corner ends up on the other side.
+        let game_state = GameState::with_players(Player::default_order(3), config);
+        assert_eq!(game_state.start_corner(0), (19, 19));
+        assert_eq!(game_state.start_corner(2), (0, 19));
+        assert_eq!(game_state.start_corner(1), (0, 0));
+    }
+
+    #[test]
+    fn sizing_up_a_small_board() {
+        let config = GameConfig::classic()
+            .board_dim(5)
+            .start_corners(vec![(4, 4), (0, 0)]);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        let wall = game_state.wall_thickness();
+        // Y . . . .
+        // . . . . .
+        // . . . . .
+        // . . . B .
+        // . . . . B
+        for (row, col, color) in [
+            (0, 0, TileColor::Yellow),
+            (3, 3, TileColor::Blue),
+            (4, 4, TileColor::Blue),
+        ] {
+            game_state.board[row + wall][col + wall] = color;
+        }
+        game_state.players[0].pieces_placed = 2;
+        game_state.players[1].pieces_placed = 1;
+        game_state.refresh_frontiers();
+
+        assert_eq!(game_state.controlled_corners(0), 1);
+        assert_eq!(game_state.controlled_corners(1), 1);
+        // Blue can go off (2, 2), (2, 4) or (4, 2). Yellow only has (1, 1).
+        assert_eq!(game_state.frontier_size(0), 3);
+        assert_eq!(game_state.frontier_size(1), 1);
+        // Everything empty except the four cells next to Blue, or the two next to Yellow.
+        assert_eq!(game_state.territory(0), 22 - 4);
+        assert_eq!(game_state.territory(1), 22 - 2);
+
+        // A wall across the middle leaves each of them a side.
+        for col in 0..5 {
+            game_state.board[2 + wall][col + wall] = TileColor::Wall;
+        }
+        game_state.refresh_frontiers();
+        assert_eq!(game_state.frontier_size(0), 1);
+        assert_eq!(game_state.territory(0), 5);
+        assert_eq!(game_state.territory(1), 7);
+
+        // Nobody's anywhere before they've played, besides their start corner.
+        let fresh = GameState::new(2);
+        assert_eq!(fresh.controlled_corners(0), 0);
+        assert_eq!(fresh.frontier_size(0), 1);
+        assert_eq!(fresh.territory(0), 20 * 20);
+    }
+
+    #[test]
+    fn game_ends_when_everyone_is_stuck() {
+        let mut game_state = GameState::new(3);
+        // Wall off the whole play area, so nobody has anywhere to go.
+        for row in game_state.board.iter_mut() {
+            for tile in row.iter_mut() {
+                *tile = TileColor::Wall;
+            }
+        }
+        game_state.refresh_frontiers();
+
+        let mut passes = 0;
+        while !game_state.is_game_over() {
+            assert!(!game_state.can_make_move());
+            assert!(game_state.may_pass());
+            game_state.pass_turn();
+            passes += 1;
+            assert_eq!(game_state.pass_counter, passes);
+        }
+        assert_eq!(passes, game_state.players.len());
+        // Everyone's stuck with the same pieces, so nobody wins.
+        assert_eq!(
+            game_state.drain_events().last(),
+            Some(&GameEvent::GameOver { winner: None })
+        );
+    }
+
+    #[test]
+    fn placing_empty_handed_is_an_error() {
+        let mut game_state = GameState::new(2);
+        let before = game_state.board.clone();
+        assert_eq!(
+            game_state.place_piece(ivec2(17, 17)),
+            Err(PlaceError::NothingSelected)
+        );
+
+        // Holding the dot but with something else in the buffer doesn't work either.
+        game_state.select_piece(Some(0));
+        game_state.piece_buffer = game_state.config.pieces.shape(1);
+        assert_eq!(
+            game_state.place_piece(ivec2(17, 17)),
+            Err(PlaceError::ShapeMismatch(0))
+        );
+        assert_eq!(game_state.board, before);
+        assert!(game_state.stats().is_empty());
+        assert!(game_state.drain_events().is_empty());
+        assert!(game_state.players[0].remaining_pieces.contains(0));
+    }
+
+    #[test]
+    fn mutations_emit_events() {
+        let mut game_state = GameState::new(2);
+        game_state.select_piece(Some(0));
+        game_state.place_piece(ivec2(17, 17)).unwrap();
+        assert_eq!(
+            game_state.drain_events(),
+            [GameEvent::PiecePlaced {
+                player: 0,
+                mv: Move {
+                    piece_id: 0,
+                    orientation: piece::Orientation::default(),
+                    corner: ivec2(17, 17)
+                }
+            }]
+        );
 
-        // Wrong corner
-        // Adjacent, same color
-        // Middle of nowhere
+        game_state.end_turn();
+        assert_eq!(
+            game_state.drain_events(),
+            [GameEvent::TurnEnded { next: 1 }]
+        );
+        game_state.pass_turn();
+        assert_eq!(
+            game_state.drain_events(),
+            [
+                GameEvent::Passed { player: 1 },
+                GameEvent::TurnEnded { next: 0 }
+            ]
+        );
+        // Nothing new since the last look.
+        assert!(game_state.drain_events().is_empty());
+    }
+
+    #[test]
+    fn count_remaining_tiles() {
+        let mut game_state = GameState::new(2);
+        assert_eq!(game_state.remaining_tile_count(0), 89);
+        assert_eq!(game_state.score(0), -89);
+
+        game_state.select_piece(Some(9));
+        assert!(game_state.try_advance_turn(17, 19));
+        assert_eq!(game_state.remaining_tile_count(0), 84);
+        assert_eq!(game_state.score(0), -84);
+        assert_eq!(game_state.remaining_tile_count(1), 89);
+
+        // Everything's down, and the dot went last.
+        game_state.players[0].remaining_pieces.clear();
+        assert_eq!(game_state.score(0), 15);
+        assert!(!game_state.dot_went_last(0));
+        game_state.players[0].last_piece = Some(0);
+        assert_eq!(game_state.score(0), 20);
+        assert!(game_state.played_every_piece(0) && game_state.dot_went_last(0));
+    }
+
+    #[test]
+    fn duo_starts_in_the_middle() {
+        let mut game_state = GameState::with_players(Player::default_order(2), GameConfig::duo());
+        assert_eq!(game_state.board.len(), 16);
+
+        // Nowhere near the seed.
+        game_state.select_piece(Some(7));
+        assert!(!game_state.try_advance_turn(0, 0));
+        // Corners mean nothing on this board.
+        assert!(!game_state.try_advance_turn(13, 13));
+
+        // Square covering (4, 4).
+        assert!(game_state.try_advance_turn(4, 4));
+        game_state.select_piece(Some(0));
+        assert!(game_state.try_advance_turn(9, 9));
+    }
+
+    #[test]
+    fn classic_config_matches_old_setup() {
+        assert_eq!(GameConfig::default(), GameConfig::classic());
+
+        let game_state = GameState::new(4);
+        assert_eq!(game_state.board.len(), 22);
+        assert!(game_state.board[0].iter().all(|&t| t == TileColor::Wall));
+        assert!(game_state.board[21].iter().all(|&t| t == TileColor::Wall));
+        let corners: Vec<_> = (0..4).map(|idx| game_state.start_corner(idx)).collect();
+        assert_eq!(corners, [(19, 19), (19, 0), (0, 0), (0, 19)]);
+
+        // Two players still sit across from each other.
+        let game_state = GameState::new(2);
+        assert_eq!(game_state.start_corner(0), (19, 19));
+        assert_eq!(game_state.start_corner(1), (0, 0));
+    }
+
+    #[test]
+    fn custom_two_seat_config() {
+        let config = GameConfig::classic()
+            .board_dim(10)
+            .start_corners(vec![(0, 9), (9, 0)])
+            .allow_monominoes_first(false)
+            .first_move_must_cover_corner(false);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        assert_eq!(game_state.board.len(), 12);
+
+        // No opening with the dot.
+        game_state.select_piece(Some(0));
+        assert!(!game_state.try_advance_turn(5, 5));
+        // But anything else can go wherever it likes.
+        game_state.select_piece(Some(7));
+        assert!(game_state.try_advance_turn(5, 5));
+        game_state.select_piece(Some(4));
+        assert!(game_state.try_advance_turn(2, 2));
+
+        // After that, it's back to the usual diagonal rule, and the dot is fair game.
+        game_state.select_piece(Some(0));
+        assert!(!game_state.try_advance_turn(8, 8));
+        assert!(game_state.try_advance_turn(6, 4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn too_few_start_corners() {
+        GameState::with_players(Player::default_order(4), GameConfig::duo());
+    }
+
+    #[test]
+    fn canonical_color_order() {
+        use TileColor::*;
+        assert_eq!(TileColor::all_playable(), [Blue, Yellow, Red, Green]);
+
+        // Tests, the demo, and the setup screen all hand out colors the same way.
+        let colors: Vec<_> = GameState::new(4).players.iter().map(|p| p.color).collect();
+        assert_eq!(colors, TileColor::all_playable());
+        assert_eq!(Player::default_order(2)[1].color, Yellow);
+    }
+
+    #[test]
+    fn playable_colors_are_distinct() {
+        let colors = TileColor::all_playable();
+        assert!(!colors.contains(&TileColor::Empty));
+        assert!(!colors.contains(&TileColor::Wall));
+        for (i, color) in colors.iter().enumerate() {
+            assert!(!colors[i + 1..].contains(color));
+            // Each one gets its own character in saves and its own color on screen.
+            let c = color.to_string().chars().next().unwrap();
+            assert_eq!(TileColor::from_char(c), Some(*color));
+            assert_ne!(Color::from(*color), BLANK);
+        }
+    }
+
+    #[test]
+    fn stats_count_corners_and_time() {
+        let mut game_state = GameState::new(2);
+        let l3 = |game_state: &GameState| {
+            game_state
+                .enumerate_legal_moves()
+                .into_iter()
+                .find(|mv| mv.piece_id == 3)
+                .expect("The L fits in the corner.")
+        };
+        // However it's turned, an L in the corner has two new diagonals off its ends and
+        // none in the elbow, which its own tiles fence off.
+        game_state.turn_expired(10.);
+        game_state.turn_expired(12.5);
+        game_state.apply_move(&l3(&game_state));
+        // Nobody looked at the clock this time.
+        game_state.apply_move(&l3(&game_state));
+
+        let stats = game_state.stats();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(
+            stats[0],
+            MoveStats {
+                player: 0,
+                piece_id: 3,
+                tiles: 3,
+                corners_opened: 2,
+                corners_closed: 0,
+                seconds: Some(2.5),
+            }
+        );
+        assert_eq!((stats[1].player, stats[1].seconds), (1, None));
+        assert_eq!(game_state.frontiers[0].len(), 2);
+    }
+
+    #[test]
+    fn bad_moves_leave_the_game_alone() {
+        let config = GameConfig::classic().allow_monominoes_first(false);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        let square = game_state
+            .enumerate_legal_moves()
+            .into_iter()
+            .find(|mv| mv.piece_id == 7)
+            .expect("The square fits in the corner.");
+        let snapshot = |game_state: &GameState| {
+            (
+                game_state.board.clone(),
+                game_state.current_player,
+                game_state.selected_piece,
+                game_state.piece_buffer,
+                game_state.pass_counter,
+                game_state.players[0].remaining_pieces.clone(),
+            )
+        };
+        let expect_refusal = |game_state: &mut GameState, mv: Move, why: MoveError| {
+            // Whatever they were holding stays in their hand.
+            game_state.select_piece(Some(2));
+            let before = snapshot(game_state);
+            assert_eq!(game_state.try_apply_move(&mv), Err(why));
+            assert_eq!(snapshot(game_state), before);
+            assert!(game_state.drain_events().is_empty());
+        };
+
+        let at = |mv: Move, corner: IVec2| Move { corner, ..mv };
+        expect_refusal(
+            &mut game_state,
+            at(square, ivec2(10, 10)),
+            MoveError::NoDiagonalContact,
+        );
+        expect_refusal(
+            &mut game_state,
+            at(square, square.corner + ivec2(2, 2)),
+            MoveError::OutOfBounds,
+        );
+        expect_refusal(
+            &mut game_state,
+            Move {
+                piece_id: 0,
+                ..square
+            },
+            MoveError::NotAllowedYet,
+        );
+
+        assert_eq!(game_state.try_apply_move(&square), Ok(()));
+        assert_eq!(game_state.current_player, 1);
+        assert!(!game_state.players[0].remaining_pieces.contains(7));
+        game_state.pass_turn();
+        game_state.drain_events();
+
+        expect_refusal(&mut game_state, square, MoveError::AlreadyPlayed);
+        let line2 = Move {
+            piece_id: 1,
+            ..square
+        };
+        expect_refusal(&mut game_state, line2, MoveError::Overlap);
+        expect_refusal(
+            &mut game_state,
+            at(line2, square.corner - ivec2(0, 2)),
+            MoveError::AdjacentSameColor,
+        );
+    }
+
+    #[test]
+    fn one_player_finishing_doesnt_end_the_game() {
+        let mut game_state = GameState::new(3);
+        // Blue is down to the dot.
+        game_state.players[0].remaining_pieces = BitSet::from_iter([0]);
+        let dot = game_state.enumerate_legal_moves()[0];
+        game_state.apply_move(&dot);
+        assert!(!game_state.players[0].still_playing());
+        assert!(!game_state.is_game_over());
+        assert_eq!(game_state.current_player, 1);
+
+        // The other two keep going, and Blue's turns get skipped.
+        for _ in 0..2 {
+            let mv = ai::choose_move(&game_state).expect("It's early, there's room.");
+            game_state.apply_move(&mv);
+            assert!(!game_state.is_game_over());
+        }
+        assert_eq!(game_state.current_player, 1);
+
+        // It only ends once both of them are stuck.
+        game_state.drain_events();
+        game_state.pass_turn();
+        assert!(!game_state.is_game_over());
+        game_state.pass_turn();
+        assert!(game_state.is_game_over());
+        assert!(game_state
+            .drain_events()
+            .iter()
+            .any(|e| matches!(e, GameEvent::GameOver { .. })));
+    }
+
+    #[test]
+    fn forfeited_seats_get_skipped() {
+        let mut game_state = GameState::new(3);
+        let play = |game_state: &mut GameState| {
+            let mv = ai::choose_move(game_state).expect("It's early, there's room.");
+            game_state.apply_move(&mv);
+        };
+        play(&mut game_state);
+        play(&mut game_state);
+        let score = game_state.score(1);
+
+        // Seat 1 gives up when it isn't even their turn...
+        game_state.drain_events();
+        game_state.forfeit(1);
+        assert_eq!(
+            game_state.drain_events(),
+            [GameEvent::Forfeited { player: 1 }]
+        );
+        assert_eq!(game_state.current_player, 2);
+        // ...and from then on the turn goes right past them.
+        play(&mut game_state);
+        assert_eq!(game_state.current_player, 0);
+        play(&mut game_state);
+        assert_eq!(game_state.current_player, 2);
+        assert!(!game_state.players[1].still_playing());
+        assert_eq!(game_state.score(1), score);
+
+        // Only the two still playing have to pass for it to end, and the one who left
+        // can't win, however well they were doing.
+        // Say they'd somehow gotten every piece down, for the best score at the table.
+        game_state.players[1].remaining_pieces = BitSet::default();
+        game_state.pass_turn();
+        assert!(!game_state.is_game_over());
+        game_state.drain_events();
+        game_state.pass_turn();
+        assert!(game_state.is_game_over());
+        let winner = game_state.drain_events().into_iter().find_map(|e| match e {
+            GameEvent::GameOver { winner } => Some(winner),
+            _ => None,
+        });
+        assert!(matches!(winner, Some(Some(0 | 2) | None)));
+
+        // Everyone leaving ends it too, with nobody winning.
+        let mut game_state = GameState::new(2);
+        game_state.forfeit(1);
+        assert!(!game_state.is_game_over());
+        game_state.forfeit(0);
+        assert!(game_state.is_game_over());
+        assert_eq!(
+            game_state.drain_events().last(),
+            Some(&GameEvent::GameOver { winner: None })
+        );
+    }
+
+    #[test]
+    fn solitaire_runs_until_stuck() {
+        let config = GameConfig::classic().solitaire(true);
+        assert_eq!(
+            GameState::try_with_players(Player::default_order(2), config.clone()).err(),
+            Some(SetupError::SolitaireNeedsOne)
+        );
+
+        let mut game_state = GameState::with_players(Player::default_order(1), config);
+        assert!(game_state.can_make_move());
+        assert!(!game_state.is_game_over());
+        // Any corner will do to start.
+        assert_eq!(game_state.first_move_corners().len(), 4);
+        let starts: HashSet<_> = game_state
+            .enumerate_legal_moves()
+            .iter()
+            .flat_map(|mv| mv.board_cells(&game_state.config.pieces))
+            .filter(|&(r, c)| game_state.first_move_corners().contains(&(r - 1, c - 1)))
+            .collect();
+        assert_eq!(starts.len(), 4);
+
+        while !game_state.is_game_over() {
+            assert!(game_state.can_make_move());
+            let mv = ai::choose_move(&game_state).expect("There's a move left.");
+            game_state.apply_move(&mv);
+        }
+        assert!(!game_state.can_make_move());
+        assert_eq!(
+            game_state.score(0) as usize,
+            game_state.solitaire_target() - game_state.remaining_tile_count(0)
+        );
+        assert!(game_state.score(0) > 0);
+    }
+
+    #[test]
+    fn one_piece_a_turn() {
+        let mut game_state = GameState::new(2);
+        let mv = ai::choose_move(&game_state).expect("The empty board has room.");
+        game_state.select_piece(Some(mv.piece_id));
+        game_state.piece_buffer = mv.shape(&game_state.config.pieces);
+        game_state.place_piece(mv.corner).unwrap();
+        assert!(game_state.turn_done());
+
+        // Nothing else goes down until the turn's over, however it's asked for.
+        let board = game_state.board.clone();
+        game_state.select_piece(Some(0));
+        assert_eq!(
+            game_state.place_piece(ivec2(10, 10)),
+            Err(PlaceError::TurnOver)
+        );
+        assert_eq!(game_state.board, board);
+        game_state.end_turn();
+        assert_eq!(game_state.current_player, 1);
+        assert_eq!(game_state.placements_this_turn, 0);
+    }
+
+    #[test]
+    fn two_pieces_a_turn() {
+        let config = GameConfig::classic().pieces_per_turn(2);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        let play = |game_state: &mut GameState| {
+            let mv = ai::choose_move(game_state).expect("It's early, there's room.");
+            game_state.apply_move(&mv);
+        };
+
+        play(&mut game_state);
+        assert_eq!(game_state.current_player, 0);
+        assert!(!game_state.turn_done());
+        play(&mut game_state);
+        assert_eq!(game_state.current_player, 1);
+        assert_eq!(game_state.players[0].pieces_placed, 2);
+
+        // Stopping after one ends the turn without counting as a pass.
+        play(&mut game_state);
+        assert!(game_state.may_pass());
+        game_state.drain_events();
+        game_state.pass_turn();
+        assert_eq!(game_state.current_player, 0);
+        assert_eq!(game_state.pass_counter, 0);
+        assert_eq!(
+            game_state.drain_events(),
+            [GameEvent::TurnEnded { next: 0 }]
+        );
+
+        // It survives a save, halfway through a turn.
+        play(&mut game_state);
+        let loaded = GameState::from_save_string(&game_state.to_save_string()).unwrap();
+        assert_eq!(loaded.config.pieces_per_turn, 2);
+        assert_eq!(loaded.placements_this_turn, 1);
+    }
+
+    #[test]
+    fn reject_bad_player_lists() {
+        let setup = |players| GameState::try_with_players(players, GameConfig::classic()).err();
+        assert_eq!(setup(SmallVec::new()), Some(SetupError::NoPlayers));
+
+        let mut five = Player::default_order(4);
+        five.push(Player::new(TileColor::Red, piece::SHAPES.len()));
+        assert_eq!(setup(five), Some(SetupError::TooManyPlayers(5)));
+
+        let mut twins = Player::default_order(2);
+        twins[1].color = twins[0].color;
+        assert_eq!(
+            setup(twins),
+            Some(SetupError::DuplicateColor(TileColor::Blue))
+        );
+
+        assert_eq!(setup(Player::default_order(3)), None);
+        assert_eq!(
+            GameState::try_with_players(Player::default_order(3), GameConfig::duo()).err(),
+            Some(SetupError::NotEnoughStartCorners)
+        );
+    }
+
+    #[test]
+    fn bigger_piece_sets() {
+        let hexomino = |cells: [(usize, usize); 6]| {
+            let mut shape = piece::EMPTY_SHAPE;
+            for (r, c) in cells {
+                shape[r].set(c, true);
+            }
+            shape
+        };
+        let pieces = PieceSet::classic()
+            .with_piece(
+                "RECT6",
+                hexomino([(1, 1), (1, 2), (1, 3), (2, 1), (2, 2), (2, 3)]),
+            )
+            .with_piece(
+                "LONG L6",
+                hexomino([(0, 1), (1, 1), (2, 1), (3, 1), (4, 1), (4, 2)]),
+            )
+            .with_piece(
+                "BIG S6",
+                hexomino([(1, 1), (1, 2), (2, 2), (3, 2), (3, 3), (4, 3)]),
+            );
+        assert_eq!(pieces.len(), 24);
+        assert_eq!(pieces.size(21), 6);
+        let blue = Player::new(TileColor::Blue, pieces.len());
+        assert_eq!(blue.remaining_pieces.len(), 24);
+
+        // The new pieces play like any other.
+        let yellow = Player::new(TileColor::Yellow, pieces.len());
+        let config = GameConfig::classic().pieces(pieces);
+        let mut game_state = GameState::with_players(SmallVec::from_iter([blue, yellow]), config);
+        let opener = game_state
+            .enumerate_legal_moves()
+            .into_iter()
+            .find(|mv| mv.piece_id == 21)
+            .expect("The rectangle fits in the corner.");
+        let notation = opener.to_notation(TileColor::Blue, &game_state.config.pieces);
+        assert!(notation.starts_with("B:RECT6@"), "{notation}");
+        assert_eq!(
+            Move::from_notation(&notation, &game_state),
+            Ok((TileColor::Blue, opener))
+        );
+        game_state.apply_move(&opener);
+        assert_eq!(game_state.players[0].remaining_pieces.len(), 23);
+        assert_eq!(game_state.remaining_tile_count(0), 89 + 2 * 6);
+
+        // Nobody can bring a piece the set doesn't have.
+        let mut stray = Player::default_order(2);
+        stray[1].remaining_pieces.insert(23);
+        assert_eq!(
+            GameState::try_with_players(stray, GameConfig::classic()).err(),
+            Some(SetupError::UnknownPiece(23))
+        );
     }
 
     #[test]
@@ -386,4 +3631,188 @@ mod tests {
         // Nowhere near done.
         assert!(game_state.can_make_move());
     }
+
+    #[test]
+    fn notation_round_trips() {
+        let mut game_state = GameState::new(4);
+        let mut seen = 0;
+        // A few rounds of openings and answers, turned every which way.
+        while seen < 12 {
+            let mv = game_state.enumerate_legal_moves()[seen % 5];
+            let color = game_state.current_player().color;
+            let notation = mv.to_notation(color, &game_state.config.pieces);
+            assert_eq!(Move::from_notation(&notation, &game_state), Ok((color, mv)));
+            game_state.apply_move(&mv);
+            seen += 1;
+        }
+
+        let l5 = Move {
+            piece_id: 10,
+            orientation: piece::Orientation::new(true, 3),
+            corner: ivec2(4, 2),
+        };
+        let notation = l5.to_notation(TileColor::Red, &game_state.config.pieces);
+        assert!(notation.starts_with("R:L5@"), "{notation}");
+        assert!(notation.ends_with("/flip/rot3"), "{notation}");
+        assert_eq!(
+            Move::from_notation(&notation, &game_state),
+            Ok((TileColor::Red, l5))
+        );
+
+        // Longer names are still one word, so a move stays one argument to `blorus replay`.
+        let zig = Move { piece_id: 11, ..l5 };
+        let notation = zig.to_notation(TileColor::Red, &game_state.config.pieces);
+        assert!(notation.starts_with("R:EXT-ZIG@"), "{notation}");
+        assert!(!notation.contains(char::is_whitespace), "{notation}");
+        assert_eq!(
+            Move::from_notation(&notation, &game_state),
+            Ok((TileColor::Red, zig))
+        );
+    }
+
+    #[test]
+    fn rematch_deals_everyone_back_in() {
+        let mut players = Player::default_order(3);
+        players[1].name = Some("Ana".to_string());
+        players[2].is_bot = true;
+        let config = GameConfig::classic().three_player_neutral(true);
+        let mut game_state = GameState::with_players(players, config);
+        for _ in 0..6 {
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            game_state.apply_move(&mv);
+        }
+        game_state.forfeit(0);
+
+        let colors = |game_state: &GameState| -> Vec<TileColor> {
+            game_state.players.iter().map(|p| p.color).collect()
+        };
+        let again = game_state.rematch(false);
+        assert_eq!(colors(&again), colors(&game_state));
+        assert_eq!(again.board, GameState::new(4).board);
+        assert_eq!(again.current_player, 0);
+        for (fresh, old) in again.players.iter().zip(&game_state.players) {
+            assert_eq!(fresh.remaining_pieces.len(), 21);
+            assert_eq!((fresh.pieces_placed, fresh.forfeited), (0, false));
+            assert_eq!((&fresh.name, fresh.is_bot), (&old.name, old.is_bot));
+        }
+
+        // Everyone moves up a seat, and the neutral color stays at the end.
+        let rotated = game_state.rematch(true);
+        assert_eq!(
+            colors(&rotated),
+            [
+                TileColor::Yellow,
+                TileColor::Red,
+                TileColor::Blue,
+                TileColor::Green
+            ]
+        );
+        assert!(rotated.players[3].is_neutral);
+    }
+
+    #[test]
+    fn boxed_in_cells_are_dead() {
+        let config =
+            GameConfig::classic()
+                .board_dim(5)
+                .start_corners(vec![(0, 0), (0, 4), (4, 4), (4, 0)]);
+        let full = || BitSet::from_iter(0..21);
+        let hands = || vec![full(), full(), full(), full()];
+        // Every color touches the middle cell, so nobody can ever have it.
+        let surrounded = [
+            "#######", //
+            "#.....#", "#..R..#", "#.B.Y.#", "#..G..#", "#.....#", "#######",
+        ];
+        let game_state = GameState::from_scenario(config.clone(), &surrounded, hands()).unwrap();
+        assert_eq!(game_state.dead_cells(), [(2, 2)]);
+
+        // Without the red tile, Red could still take it, even if Blue (whose turn it is)
+        // can't. Until Red's out of pieces, anyway.
+        let mut open = surrounded;
+        open[2] = "#.....#";
+        let game_state = GameState::from_scenario(config.clone(), &open, hands()).unwrap();
+        assert!(game_state.dead_cells().is_empty());
+        let mut red_done = hands();
+        red_done[2] = BitSet::default();
+        let game_state = GameState::from_scenario(config, &open, red_done).unwrap();
+        assert_eq!(game_state.dead_cells(), [(2, 2)]);
+    }
+
+    #[test]
+    fn scenarios_round_trip() {
+        let board = [
+            "#######", //
+            "#B....#", "#BB...#", "#..Y..#", "#..YY.#", "#....Y#", "#######",
+        ];
+        let config = GameConfig::classic()
+            .board_dim(5)
+            .start_corners(vec![(0, 0), (4, 4)]);
+        let hands = vec![BitSet::from_iter([0, 1, 2]), BitSet::from_iter(3..21)];
+        let game_state = GameState::from_scenario(config.clone(), &board, hands).unwrap();
+
+        // Drawn back out, it's the same board.
+        let drawn: Vec<String> = game_state
+            .board
+            .iter()
+            .map(|row| row.iter().map(|tile| tile.to_string()).collect())
+            .collect();
+        assert_eq!(drawn, board);
+        assert_eq!(game_state.players[0].remaining_pieces.len(), 3);
+        assert_eq!(game_state.players[1].pieces_placed, 3);
+        // Blue's played, so it has to build off its own corners, and there's only one free.
+        let moves = game_state.enumerate_legal_moves();
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|mv| mv
+            .board_cells(&game_state.config.pieces)
+            .any(|cell| cell == (1, 3))));
+
+        assert_eq!(
+            GameState::from_scenario(config.clone(), &board[1..], Vec::new()).err(),
+            Some(ScenarioError::WrongSize(7))
+        );
+        let mut smudged = board;
+        smudged[3] = "#..X..#";
+        assert_eq!(
+            GameState::from_scenario(config.clone(), &smudged, Vec::new()).err(),
+            Some(ScenarioError::UnknownTile('X'))
+        );
+        let mut leaky = board;
+        leaky[4] = "#..YY..";
+        assert_eq!(
+            GameState::from_scenario(config.clone(), &leaky, Vec::new()).err(),
+            Some(ScenarioError::BrokenWall(4, 6))
+        );
+        let too_many = vec![BitSet::from_iter([0, 21]), BitSet::default()];
+        assert_eq!(
+            GameState::from_scenario(config.clone(), &board, too_many).err(),
+            Some(ScenarioError::UnknownPiece(21))
+        );
+        let crowd = vec![BitSet::default(); 5];
+        assert_eq!(
+            GameState::from_scenario(config, &board, crowd).err(),
+            Some(ScenarioError::Setup(SetupError::TooManyPlayers(5)))
+        );
+    }
+
+    #[test]
+    fn notation_rejects_nonsense() {
+        let game_state = GameState::new(2);
+        assert_eq!(
+            Move::from_notation("B:OCTOPUS@r3c4/rot1", &game_state),
+            Err(NotationError::UnknownPiece("OCTOPUS".to_string()))
+        );
+        // Only Blue and Yellow are playing.
+        assert_eq!(
+            Move::from_notation("R:L5@r3c4/rot1", &game_state),
+            Err(NotationError::UnknownColor(TileColor::Red))
+        );
+        assert_eq!(
+            Move::from_notation("B:LINE5@r19c19/rot0", &game_state),
+            Err(NotationError::OffBoard)
+        );
+        assert!(matches!(
+            Move::from_notation("B:DOT@r3c4/rot9", &game_state),
+            Err(NotationError::Malformed(_))
+        ));
+    }
 }
@@ -4,19 +4,52 @@
 //! networked programming in an environment where the performance requirements aren't too stringent.
 
 use macroquad::{
-    audio::{load_sound, play_sound, PlaySoundParams},
+    miniquad,
     prelude::*,
-    ui::{root_ui, widgets::Button},
+    rand::RandGenerator,
+    ui::{
+        root_ui,
+        widgets::{Button, Editbox},
+    },
 };
-use smallvec::{SmallVec, ToSmallVec};
-use std::env::args;
+use smallvec::SmallVec;
+use std::{collections::HashMap, env::args};
 
-mod debug;
+mod actions;
+mod ai;
+mod assets;
+mod bitboard;
+mod config;
+#[cfg(feature = "serde")]
+mod json;
+mod keys;
+mod lobby;
 mod logic;
 mod net;
+mod pad;
+mod palette;
 mod piece;
+mod replay;
+mod save;
+mod screenshot;
+mod session;
+mod sim;
+mod theme;
+mod tile;
+mod tui;
 
-use logic::{GameState, Player, TileColor};
+use ai::Difficulty;
+use assets::Assets;
+use config::{GameConfig, Seating};
+use keys::{Action, KeyBindings};
+use logic::{GameEvent, GameState, Move, MoveError, Player, TileColor};
+use pad::{PadInput, Pads};
+use palette::{Palette, Symbol};
+use piece::{Orientation, PieceSet};
+use replay::Replay;
+use session::{LocalSession, Session};
+use theme::Theme;
+use tile::TileStyle;
 
 // Modify these to move or scale the board as a proportion of the screen.
 // The board automatically resizes itself with the window.
@@ -24,23 +57,35 @@ const BOARD_SIZE: f32 = 0.5;
 const BOARD_HORIZ_OFFSET: f32 = 0.25;
 const BOARD_VERT_OFFSET: f32 = 0.25;
 
-#[macroquad::main("Blorus")]
-async fn main() {
+/// Where "Save picture" puts the final board.
+const SCREENSHOT_PATH: &str = "blorus.png";
+
+fn main() {
+    let mut args = args().skip(1);
+    // The terminal and the bots don't need a window, so they don't get one.
+    match args.next() {
+        // Plays in the terminal instead: `blorus --tui 2` for a two-player game.
+        Some(flag) if flag == "tui" || flag == "--tui" => {
+            let players = args.next().and_then(|n| n.parse().ok()).unwrap_or(4);
+            tui::play(players);
+        }
+        // Bots only: `blorus sim 1000` to play a thousand games, and `--moves` to see them.
+        Some(flag) if flag == "sim" => {
+            let games = args.next().and_then(|n| n.parse().ok()).unwrap_or(100);
+            let log_moves = args.next().is_some_and(|flag| flag == "--moves");
+            sim::report(games, log_moves);
+        }
+        flag => macroquad::Window::new("Blorus", windowed(flag, args.collect())),
+    }
+}
+
+/// Everything that needs a window, starting with whatever `flag` asks for, or the setup
+/// screen without one. `args` are whatever came after `flag`.
+async fn windowed(flag: Option<String>, args: Vec<String>) {
     // File I/O in Macroquad isn't *actually* async, unless you're in a browser.
     // TODO: Remove conditional compilation if this ever becomes async on all platforms.
     #[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
-    coroutines::start_coroutine(async move {
-        match load_sound("assets/SneakySnitch.ogg").await {
-            Ok(music) => play_sound(
-                music,
-                PlaySoundParams {
-                    looped: true,
-                    volume: 1.,
-                },
-            ),
-            Err(e) => eprintln!("Failed to load epic music :( -- {e}"),
-        }
-    });
+    coroutines::start_coroutine(assets::play_music());
 
     #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
     {
@@ -53,25 +98,61 @@ async fn main() {
             BLACK,
         );
         next_frame().await;
-        match load_sound("assets/SneakySnitch.ogg").await {
-            Ok(music) => play_sound(
-                music,
-                PlaySoundParams {
-                    looped: true,
-                    volume: 1.,
-                },
-            ),
-            Err(e) => eprintln!("Failed to load epic music :( -- {e}"),
-        }
+        assets::play_music().await;
     }
 
-    let mut args = args().skip(1);
     // I feel like I should've been able to pattern-match this.
     // Anyway, I left this branch in so I could still play the game quickly.
-    if let Some(demo_flag) = args.next() {
+    if let Some(demo_flag) = flag {
         if demo_flag == "demo" {
-            let players = TileColor::DEFAULT_ORDER.map(Player::new);
-            game_loop(players.into()).await;
+            let players = Player::default_order(4);
+            // Skip the fade-in so quick games stay quick.
+            let game_state = GameState::with_players(players, GameConfig::classic());
+            game_loop(
+                LocalSession::new(game_state),
+                false,
+                Look::default(),
+                Placing::default(),
+            )
+            .await;
+        } else if demo_flag == "replay" {
+            // Picks up a four-player game from moves written down like `blorus sim 1 --moves`
+            // prints them: `blorus replay B:DOT@r19c19/rot0 Y:L3@r18c0/rot0 ...`
+            let mut game_state =
+                GameState::with_players(Player::default_order(4), GameConfig::classic());
+            for notation in args {
+                let mv = match Move::from_notation(&notation, &game_state) {
+                    Ok((color, _)) if color != game_state.current_player().color => {
+                        eprintln!(
+                            "{notation}: it's {:?}'s turn!",
+                            game_state.current_player().color
+                        );
+                        return;
+                    }
+                    Ok((_, mv)) => mv,
+                    Err(e) => {
+                        eprintln!("{notation}: {e}");
+                        return;
+                    }
+                };
+                if let Err(e) = game_state.try_apply_move(&mv) {
+                    eprintln!("{notation}: that piece {e}.");
+                    return;
+                }
+            }
+            // Handy for setting up puzzles, which should look the same from every seat.
+            if game_state.occupied_cells().next().is_some() {
+                if let Some(symmetry) = game_state.is_symmetric() {
+                    println!("This board has {symmetry:?} symmetry.");
+                }
+            }
+            game_loop(
+                LocalSession::new(game_state),
+                false,
+                Look::default(),
+                Placing::default(),
+            )
+            .await;
         }
     } else {
         setup_screen().await;
@@ -81,11 +162,25 @@ async fn main() {
 /// Local multiplayer setup screen
 async fn setup_screen() {
     let mut players = Player::default_order(2);
+    let mut config = GameConfig::classic();
+    let mut palette = Palette::default();
+    let mut tile_style = TileStyle::default();
+    let mut theme = Theme::default();
+    let mut coordinates = false;
+    let mut piece_outlines = false;
+    let mut tray_order = TrayOrder::default();
+    let mut turn_board = false;
+    let mut placing = Placing::default();
+    // Shown until the next time someone tries to start.
+    let mut setup_error = None;
     // Change to "while not (exit condition)"
     loop {
         let mut dropped_players = SmallVec::<[usize; 4]>::new();
+        let mut toggled_bots = SmallVec::<[usize; 4]>::new();
+        let mut recolored_players = SmallVec::<[usize; 4]>::new();
+        let mut swapped_players = SmallVec::<[usize; 4]>::new();
 
-        clear_background(BEIGE);
+        clear_background(theme.background);
         // You know, maybe it would be fun for networked multiplayer to let you fidget
         // with the piece that represents you and have it display to everyone in the lobby.
         // That's a good sort of thing to have if you're waiting on someone to arrive.
@@ -109,8 +204,22 @@ async fn setup_screen() {
             player_status_region_dims,
         );
         let tile_size = 0.1 * player_status_region_dims.y;
-        for (i, p) in players.iter().enumerate() {
+        let player_count = players.len();
+        for (i, p) in players.iter_mut().enumerate() {
             let elem_x = player_status_dims.x * i as f32 + player_status_padding * (i + 1) as f32;
+
+            // Their name goes over their piece. Leaving it blank goes back to the color.
+            let mut name = p.name.clone().unwrap_or_default();
+            Editbox::new(
+                macroquad::hash!("player name", i),
+                vec2(player_status_dims.x, 1.5 * tile_size),
+            )
+            .position(player_status_region_pos + vec2(elem_x, -2. * tile_size))
+            .multiline(false)
+            .ui(&mut root_ui(), &mut name);
+            let name = name.trim();
+            p.name = (!name.is_empty()).then(|| name.to_string());
+
             // Now, each player gets drawn here.
             let player_repr = piece::SHAPES[17 + i];
             draw_piece(
@@ -119,6 +228,15 @@ async fn setup_screen() {
                 player_status_region_pos + vec2(elem_x, 0.),
                 tile_size,
                 true,
+                Look {
+                    palette,
+                    tile_style,
+                    theme,
+                    coordinates,
+                    piece_outlines,
+                    tray_order,
+                    turn_board,
+                },
             );
 
             // Under each player there will be a "drop",
@@ -128,6 +246,51 @@ async fn setup_screen() {
             if drop_button.ui(&mut root_ui()) {
                 dropped_players.push(i);
             }
+
+            let bot_label = match (p.is_bot, p.difficulty) {
+                (false, _) => "Human",
+                (true, Difficulty::Easy) => "Easy bot",
+                (true, Difficulty::Medium) => "Medium bot",
+                (true, Difficulty::Hard) => "Hard bot",
+            };
+            let bot_button = Button::new(bot_label)
+                .position(player_status_region_pos + vec2(elem_x, 7. * tile_size));
+            if bot_button.ui(&mut root_ui()) {
+                toggled_bots.push(i);
+            }
+
+            let color_button = Button::new("Change color")
+                .position(player_status_region_pos + vec2(elem_x, 9. * tile_size));
+            if color_button.ui(&mut root_ui()) {
+                recolored_players.push(i);
+            }
+
+            if player_count > 1 {
+                let swap_button = Button::new("Swap color")
+                    .position(player_status_region_pos + vec2(elem_x, 11. * tile_size));
+                if swap_button.ui(&mut root_ui()) {
+                    swapped_players.push(i);
+                }
+            }
+        }
+
+        for p_ind in recolored_players {
+            lobby::change_color(&mut players, p_ind);
+        }
+
+        for p_ind in swapped_players {
+            lobby::swap_colors(&mut players, p_ind);
+        }
+
+        // Human -> Easy -> Medium -> Hard -> Human
+        for p_ind in toggled_bots {
+            let p = &mut players[p_ind];
+            (p.is_bot, p.difficulty) = match (p.is_bot, p.difficulty) {
+                (false, _) => (true, Difficulty::Easy),
+                (true, Difficulty::Easy) => (true, Difficulty::Medium),
+                (true, Difficulty::Medium) => (true, Difficulty::Hard),
+                (true, Difficulty::Hard) => (false, Difficulty::default()),
+            };
         }
 
         // We defer dropping the players until now since Rust understandably
@@ -148,15 +311,314 @@ async fn setup_screen() {
         if add_player_button.ui(&mut root_ui()) {
             // Okay, let's add a player, first color not already in the list.
             if players.len() < 4 {
-                if let Some(color) = TileColor::DEFAULT_ORDER
-                    .into_iter()
-                    .find(|c| players.iter().all(|p| p.color != *c))
-                {
-                    players.push(Player::new(color));
+                if let Some(color) = lobby::first_unused_color(&players) {
+                    players.push(Player::new(color, config.pieces.len()));
                 }
             }
         }
 
+        // Duo is strictly a two-player affair. Switching boards keeps the house rules.
+        let duo = GameConfig::duo();
+        let is_duo = config.board_dim == duo.board_dim;
+        if is_duo && players.len() != 2 {
+            let classic = GameConfig::classic();
+            config = config
+                .board_dim(classic.board_dim)
+                .start_corners(classic.start_corners);
+        }
+        let board_label = if is_duo {
+            "Board: Duo"
+        } else {
+            "Board: Classic"
+        };
+        let right_of_buttons = vec2(player_button_dims.x + medium_ui_button_padding(), 0.);
+        let board_button = Button::new(board_label)
+            .position(player_button_pos + right_of_buttons)
+            .size(player_button_dims);
+        if board_button.ui(&mut root_ui()) && players.len() == 2 {
+            let other = if is_duo { GameConfig::classic() } else { duo };
+            config = config
+                .board_dim(other.board_dim)
+                .start_corners(other.start_corners);
+        }
+
+        let palette_label = match palette {
+            Palette::Classic => "Colors: classic",
+            Palette::DeuteranopiaSafe => "Colors: color-blind",
+        };
+        let palette_button = Button::new(palette_label)
+            .position(
+                player_button_pos
+                    + right_of_buttons
+                    + vec2(0., 1. / 16. * screen_height() + medium_ui_button_padding()),
+            )
+            .size(player_button_dims);
+        if palette_button.ui(&mut root_ui()) {
+            palette = palette.next();
+        }
+
+        let tile_style_label = match tile_style {
+            TileStyle::Flat => "Tiles: flat",
+            TileStyle::Rounded => "Tiles: rounded",
+        };
+        let tile_style_button = Button::new(tile_style_label)
+            .position(
+                player_button_pos - right_of_buttons
+                    + vec2(0., 1. / 16. * screen_height() + medium_ui_button_padding()),
+            )
+            .size(player_button_dims);
+        if tile_style_button.ui(&mut root_ui()) {
+            tile_style = tile_style.next();
+        }
+
+        let theme_label = if theme.is_dark() {
+            "Theme: dark"
+        } else {
+            "Theme: light"
+        };
+        let theme_button = Button::new(theme_label)
+            .position(
+                player_button_pos - right_of_buttons
+                    + 2. * vec2(0., 1. / 16. * screen_height() + medium_ui_button_padding()),
+            )
+            .size(player_button_dims);
+        if theme_button.ui(&mut root_ui()) {
+            theme = theme.next();
+        }
+        let coordinates_label = if coordinates {
+            "Labels: on"
+        } else {
+            "Labels: off"
+        };
+        let coordinates_button = Button::new(coordinates_label)
+            .position(player_button_pos - right_of_buttons)
+            .size(player_button_dims);
+        if coordinates_button.ui(&mut root_ui()) {
+            coordinates = !coordinates;
+        }
+        let look = Look {
+            palette,
+            tile_style,
+            theme,
+            coordinates,
+            piece_outlines,
+            tray_order,
+            turn_board,
+        };
+
+        // House rules go down the left edge, where there's room for however many we come up with.
+        let rule_row = |row: f32| {
+            vec2(0.05 * screen_width(), 0.05 * screen_height())
+                + row * vec2(0., 1. / 16. * screen_height() + medium_ui_button_padding())
+        };
+        let dot_label = if config.allow_monominoes_first {
+            "Dot first: allowed"
+        } else {
+            "Dot first: banned"
+        };
+        let dot_button = Button::new(dot_label)
+            .position(rule_row(0.))
+            .size(player_button_dims);
+        if dot_button.ui(&mut root_ui()) {
+            let allow = !config.allow_monominoes_first;
+            config = config.allow_monominoes_first(allow);
+        }
+
+        let start_label = if config.first_move_must_cover_corner {
+            "Start: corner"
+        } else {
+            "Start: anywhere"
+        };
+        let start_button = Button::new(start_label)
+            .position(rule_row(1.))
+            .size(player_button_dims);
+        if start_button.ui(&mut root_ui()) {
+            let must_cover = !config.first_move_must_cover_corner;
+            config = config.first_move_must_cover_corner(must_cover);
+        }
+
+        let pass_label = if config.allow_voluntary_pass {
+            "Passing: anytime"
+        } else {
+            "Passing: when stuck"
+        };
+        let pass_button = Button::new(pass_label)
+            .position(rule_row(2.))
+            .size(player_button_dims);
+        if pass_button.ui(&mut root_ui()) {
+            let allow = !config.allow_voluntary_pass;
+            config = config.allow_voluntary_pass(allow);
+        }
+
+        let timer_label = match config.turn_seconds {
+            Some(seconds) => format!("Timer: {seconds}s"),
+            None => "Timer: off".to_string(),
+        };
+        let timer_button = Button::new(timer_label)
+            .position(rule_row(3.))
+            .size(player_button_dims);
+        if timer_button.ui(&mut root_ui()) {
+            // Off -> 30s -> 60s -> off
+            let seconds = match config.turn_seconds {
+                None => Some(30),
+                Some(30) => Some(60),
+                Some(_) => None,
+            };
+            config = config.turn_seconds(seconds);
+        }
+
+        // Swapping seats only makes sense with two.
+        if players.len() != 2 {
+            config = config.pie_rule(false);
+        }
+        let pie_label = if config.pie_rule {
+            "Pie rule: on"
+        } else {
+            "Pie rule: off"
+        };
+        let pie_button = Button::new(pie_label)
+            .position(rule_row(4.))
+            .size(player_button_dims);
+        if pie_button.ui(&mut root_ui()) && players.len() == 2 {
+            let enabled = !config.pie_rule;
+            config = config.pie_rule(enabled);
+        }
+
+        let draft_label = if config.draft_mode {
+            "Pieces: drawn"
+        } else {
+            "Pieces: all"
+        };
+        let draft_button = Button::new(draft_label)
+            .position(rule_row(5.))
+            .size(player_button_dims);
+        if draft_button.ui(&mut root_ui()) {
+            let enabled = !config.draft_mode;
+            // A fresh shuffle every time, but everyone at the table draws from the same one.
+            config = config
+                .draft_mode(enabled)
+                .draft_seed(miniquad::date::now() as u64);
+        }
+
+        // Only three players leave a color over.
+        if players.len() != 3 {
+            config = config.three_player_neutral(false);
+        }
+        let neutral_label = if config.three_player_neutral {
+            "Fourth color: shared"
+        } else {
+            "Fourth color: unused"
+        };
+        let neutral_button = Button::new(neutral_label)
+            .position(rule_row(6.))
+            .size(player_button_dims);
+        if neutral_button.ui(&mut root_ui()) && players.len() == 3 {
+            let enabled = !config.three_player_neutral;
+            config = config.three_player_neutral(enabled);
+        }
+
+        // Just you against the board.
+        if players.len() != 1 {
+            config = config.solitaire(false);
+        }
+        let solitaire_label = if config.solitaire {
+            "Solitaire: on"
+        } else {
+            "Solitaire: off"
+        };
+        let solitaire_button = Button::new(solitaire_label)
+            .position(rule_row(8.))
+            .size(player_button_dims);
+        if solitaire_button.ui(&mut root_ui()) && players.len() == 1 {
+            let enabled = !config.solitaire;
+            config = config.solitaire(enabled);
+        }
+
+        let per_turn_label = format!("Pieces a turn: {}", config.pieces_per_turn);
+        let per_turn_button = Button::new(per_turn_label)
+            .position(rule_row(9.))
+            .size(player_button_dims);
+        if per_turn_button.ui(&mut root_ui()) {
+            // Anything past three and the first player's taken half the board.
+            let count = config.pieces_per_turn % 3 + 1;
+            config = config.pieces_per_turn(count);
+        }
+
+        let seating_label = match config.seating {
+            Seating::Clockwise => "Turns go: clockwise",
+            Seating::CounterClockwise => "Turns go: counter-clockwise",
+        };
+        let seating_button = Button::new(seating_label)
+            .position(rule_row(10.))
+            .size(player_button_dims);
+        if seating_button.ui(&mut root_ui()) {
+            let seating = config.seating.next();
+            config = config.seating(seating);
+        }
+
+        // The left edge is full, so this starts a second column.
+        let descending_label = if config.descending_size {
+            "Piece sizes: shrinking"
+        } else {
+            "Piece sizes: any"
+        };
+        let descending_button = Button::new(descending_label)
+            .position(rule_row(0.) + right_of_buttons)
+            .size(player_button_dims);
+        if descending_button.ui(&mut root_ui()) {
+            let enabled = !config.descending_size;
+            config = config.descending_size(enabled);
+        }
+
+        // Not a rule as such, but it's tournaments that want it.
+        let outlines_label = if piece_outlines {
+            "Piece outlines: on"
+        } else {
+            "Piece outlines: off"
+        };
+        let outlines_button = Button::new(outlines_label)
+            .position(rule_row(11.))
+            .size(player_button_dims);
+        if outlines_button.ui(&mut root_ui()) {
+            piece_outlines = !piece_outlines;
+        }
+
+        let tray_order_label = match tray_order {
+            TrayOrder::Id => "Tray: set order",
+            TrayOrder::SizeAsc => "Tray: smallest first",
+            TrayOrder::SizeDesc => "Tray: biggest first",
+        };
+        let tray_order_button = Button::new(tray_order_label)
+            .position(rule_row(1.) + right_of_buttons)
+            .size(player_button_dims);
+        if tray_order_button.ui(&mut root_ui()) {
+            tray_order = tray_order.next();
+        }
+
+        let turn_board_label = if turn_board {
+            "Board: turns to each seat"
+        } else {
+            "Board: stays put"
+        };
+        let turn_board_button = Button::new(turn_board_label)
+            .position(rule_row(2.) + right_of_buttons)
+            .size(player_button_dims);
+        if turn_board_button.ui(&mut root_ui()) {
+            turn_board = !turn_board;
+        }
+
+        let placing_label = match placing {
+            Placing::OneClick => "Placing: one click",
+            Placing::TwoClicks => "Placing: click twice",
+            Placing::Drag => "Placing: drag and drop",
+        };
+        let placing_button = Button::new(placing_label)
+            .position(rule_row(7.))
+            .size(player_button_dims);
+        if placing_button.ui(&mut root_ui()) {
+            placing = placing.next();
+        }
+
         let start_game_button = Button::new("Begin!")
             .position(
                 player_button_pos
@@ -167,420 +629,3420 @@ async fn setup_screen() {
             // Player data is just two integers, pretty cheap to copy.
             // I wonder why BitSets do not implement `Copy`. They should just be
             // integers, unlike BitVecs which have a notion of "push/pop".
-            game_loop(players.clone()).await;
+            match GameState::try_with_players(players.clone(), config.clone()) {
+                Ok(game_state) => {
+                    setup_error = None;
+                    game_loop(LocalSession::new(game_state), true, look, placing).await;
+                }
+                Err(e) => setup_error = Some(e.to_string()),
+            }
+        }
+
+        // Picks up right where the last "Save game" left off, players and house rules included.
+        let load_button = Button::new("Load game")
+            .position(
+                player_button_pos
+                    + right_of_buttons
+                    + 2. * vec2(0., 1. / 16. * screen_height() + medium_ui_button_padding()),
+            )
+            .size(player_button_dims);
+        if load_button.ui(&mut root_ui()) {
+            match save::load_game(save::QUICK_SAVE) {
+                Ok(game_state) => {
+                    setup_error = None;
+                    game_loop(LocalSession::new(game_state), true, look, placing).await;
+                }
+                Err(e) => setup_error = Some(format!("Couldn't load: {e}")),
+            }
+        }
+
+        if let Some(text) = &setup_error {
+            let font_size = 0.04 * screen_height();
+            let dims = measure_text(text, None, font_size as u16, 1.);
+            draw_text(
+                text,
+                0.5 * (screen_width() - dims.width),
+                0.95 * screen_height(),
+                font_size,
+                RED,
+            );
         }
 
         next_frame().await;
     }
 }
 
-async fn game_loop(players: SmallVec<[Player; 4]>) {
-    let mut game_state = GameState::with_players(players);
+/// Plays out a game, wherever its players are.
+async fn game_loop(mut session: impl Session, animate: bool, look: Look, placing: Placing) {
+    let mut fades = TileFades::new(&session.current_state().board, animate);
+    let mut turn_banner = TurnBanner::default();
     // TODO: Put this somewhere more sane -- it now has the final say on whether or not the player
     // is making a valid move!
     let mut placement_hint = None;
-    let win_texture = Texture2D::from_file_with_format(include_bytes!("../assets/WIN.png"), None);
+    // Where the player clicked once, in two-click mode, waiting on a second click.
+    let mut pending_placement = None;
+    let mut controls = Controls {
+        placing,
+        keys: save::load_bindings(),
+        pad: PadInput::default(),
+    };
+    let mut pads = Pads::new();
+    let rng = RandGenerator::new();
+    rng.srand(miniquad::date::now() as u64);
+    let assets = Assets::load();
+    // Something to tell the players, and the time at which to stop showing it.
+    let mut flash: Option<(String, f64)> = None;
+    // Whoever clicked "Pass" once and has to click again to mean it.
+    let mut confirm_pass = None;
+    // The same, for "Forfeit".
+    let mut confirm_forfeit = None;
+    let mut wheel = WheelNotches::default();
+    // The piece in the tray under the mouse, if the player has it.
+    let mut hovered_piece = None;
+    // Whether the second player has already turned down the first player's opening.
+    let mut pie_declined = false;
+    // Everything that gets put down, for looking back over once the game's done.
+    let mut replay = Replay::new(session.current_state());
+    // How fast the bots go with nobody else at the table, and whether someone's asked for
+    // just one more move.
+    let mut pace = BotPace::default();
+    let mut step_once = false;
+    let mut board_view = BoardView::default();
+    // Empty cells nobody can use anymore. Only a placement or a forfeit changes these.
+    let mut dead_cells = session.current_state().dead_cells();
+    // Who's ahead so far. The same things change it.
+    let mut live_standings = standings(session.current_state());
+    // Who the board's turned toward, if anyone's playing here. See `facing_seat`.
+    let mut facing = None;
 
-    while !game_state.is_game_over() {
-        if !game_state.can_make_move() {
-            game_state.end_turn();
-            game_state.pass_counter += 1;
+    while !session.current_state().is_game_over() {
+        // Laid out once a frame, so drawing and input agree on where everything is even if
+        // the window changes size partway through.
+        let game_state = session.current_state();
+        facing = facing_seat(game_state, facing, |idx| session.is_remote(idx));
+        let view_rotation = match facing {
+            Some(seat) if look.turn_board => seat_rotation(game_state, seat),
+            _ => 0,
+        };
+        let base_screen = ScreenInfo::compute(&game_state.config)
+            .with_tray_order(look.tray_order)
+            .with_view_rotation(view_rotation);
+        let hands_empty = session.current_state().selected_piece.is_none();
+        steer_board_view(&mut board_view, &base_screen, &controls.keys, hands_empty);
+        let screen = base_screen.viewed_through(board_view);
+        session.poll_remote();
+        // Whoever's playing a remote seat makes their own moves, passes included.
+        let remote = session.is_remote(session.current_state().current_player);
+        // The clock only runs while the game does, so the game over screen stops it for good.
+        let timed_out = session.current_state_mut().turn_expired(get_time());
+        let game_state = session.current_state();
+        // With nobody at the table, there's nobody to place pieces -- just watch.
+        let bots_only = game_state.players.iter().all(|p| p.is_bot || p.is_neutral);
+        // Only the host's clock counts, so it passes for remote seats too.
+        if timed_out && session.keeps_time() {
+            let name = game_state.current_player().display_name();
+            flash = Some((format!("{name} ran out of time"), get_time() + 2.));
+            session.current_state_mut().select_piece(None);
+            session.pass_turn();
+        } else if bots_only {
+            let turns = pace.turns_due(get_time(), std::mem::take(&mut step_once));
+            for _ in 0..turns {
+                let game_state = session.current_state();
+                if game_state.is_game_over() {
+                    break;
+                }
+                if game_state.can_make_move() {
+                    play_bot_move(&mut session, &rng);
+                } else {
+                    flash = Some(pass_message(game_state));
+                    session.pass_turn();
+                }
+            }
+            if turns > 0 {
+                pace.last_turn = get_time();
+            }
+        } else if !remote && !game_state.can_make_move() {
+            flash = Some(pass_message(game_state));
+            session.pass_turn();
+        } else if !remote && game_state.players[game_state.controller()].is_bot {
+            play_bot_move(&mut session, &rng);
         }
 
-        clear_background(BEIGE);
+        clear_background(look.theme.background);
 
-        let tile_size = screen_height() * 0.045 * BOARD_SIZE;
-        // x = board_left's x coord, y = board_top's y coord
-        let board_top_left = Vec2::new(
-            screen_width() * BOARD_SIZE - screen_height() * BOARD_HORIZ_OFFSET,
-            screen_height() * BOARD_VERT_OFFSET,
+        let game_state = session.current_state();
+        fades.update(&game_state.board, get_time());
+        let view = if bots_only {
+            ViewMode::Spectator
+        } else {
+            ViewMode::Player(game_state.current_player)
+        };
+        draw_game_screen(
+            game_state,
+            &placement_hint,
+            hovered_piece,
+            &screen,
+            &fades,
+            view,
+            look,
         );
+        if look.piece_outlines {
+            draw_piece_outlines(session.current_state(), &screen, look.theme);
+        }
+        draw_dead_cells(&dead_cells, &screen, look.theme.text);
+        draw_live_standings(&live_standings, look);
+        // Whatever's being dragged shows whether it'd go down if they let go now.
+        let dragging = controls.placing == Placing::Drag && pending_placement.is_some();
+        if dragging && session.current_state().selected_piece.is_some() && !remote {
+            draw_dragged_piece(session.current_state(), &screen, look.palette);
+        }
+        turn_banner.draw(game_state, get_time(), look);
+        // Pads get read every frame, so presses made out of turn don't pile up.
+        controls.pad = pads.poll(session.current_state());
+        if view != ViewMode::Spectator && !remote {
+            let game_state = session.current_state_mut();
+            // When drawing pieces there's only one to pick up, so hand it right over.
+            if game_state.config.draft_mode && game_state.selected_piece.is_none() {
+                let drawn = game_state.drawn_piece(game_state.current_player);
+                game_state.select_piece(drawn);
+            }
+            let mv = handle_input(
+                game_state,
+                &mut placement_hint,
+                &mut pending_placement,
+                &controls,
+                &mut hovered_piece,
+                &screen,
+                &mut wheel,
+            );
+            if let Some(mv) = mv {
+                if let Err(e) = session.submit_move(mv) {
+                    flash = Some((e.to_string(), get_time() + 2.));
+                }
+            }
+        } else {
+            hovered_piece = None;
+        }
 
-        let play_area_top_left = Vec2::new(
-            board_top_left.x + screen_height() * 0.05 * BOARD_SIZE,
-            board_top_left.y + screen_height() * 0.05 * BOARD_SIZE,
-        );
+        let game_state = session.current_state();
+        let local_human = !session.is_remote(game_state.current_player)
+            && !game_state.players[game_state.controller()].is_bot;
+        if local_human {
+            let hint_dims = medium_ui_button_dims();
+            let hint_button = Button::new("Show me a move")
+                .position(vec2(0.05 * screen_width(), 0.8 * screen_height()))
+                .size(hint_dims);
+            if hint_button.ui(&mut root_ui()) {
+                // Just show them -- it's still up to them to click.
+                match session.current_state().suggest_move() {
+                    Some(mv) => {
+                        let game_state = session.current_state_mut();
+                        game_state.select_piece(Some(mv.piece_id));
+                        game_state.piece_buffer = mv.shape(&game_state.config.pieces);
+                        // The cursor points at the middle of the piece's grid.
+                        game_state.cursor = mv.corner + IVec2::splat(2);
+                        placement_hint = Some(mv.corner);
+                    }
+                    None => {
+                        flash = Some(pass_message(session.current_state()));
+                        session.pass_turn();
+                    }
+                }
+            }
 
-        // wanted to halve the area so I multiply the side length by sqrt(2)/2.
-        let ui_tile_size = tile_size * 0.5 * 1.414;
-        // each piece graphic is 5 UI tiles wide, and there are at most 11 per row.
-        let avail_pieces = Vec2::new(
-            0.5 * screen_width() - 5. * 5.5 * ui_tile_size,
-            0.8 * screen_height(),
-        );
+            let current_player = session.current_state().current_player;
+            if session.current_state().may_pass() {
+                // Make them click twice, so nobody passes by accident.
+                let confirming = confirm_pass == Some(current_player);
+                // With more pieces to a turn, stopping after the first isn't passing.
+                let placed = session.current_state().placements_this_turn > 0;
+                let pass_label = match (confirming, placed) {
+                    (false, false) => "Pass",
+                    (true, false) => "Really pass?",
+                    (false, true) => "End turn",
+                    (true, true) => "Really end turn?",
+                };
+                let pass_button = Button::new(pass_label)
+                    .position(vec2(
+                        0.05 * screen_width(),
+                        0.8 * screen_height() + hint_dims.y + medium_ui_button_padding(),
+                    ))
+                    .size(hint_dims);
+                if pass_button.ui(&mut root_ui()) {
+                    if confirming {
+                        session.current_state_mut().select_piece(None);
+                        session.pass_turn();
+                    } else {
+                        confirm_pass = Some(current_player);
+                    }
+                }
+            }
 
-        draw_game_screen(
-            &game_state,
-            &placement_hint,
-            board_top_left,
-            play_area_top_left,
-            avail_pieces,
-            tile_size,
-            ui_tile_size,
-        );
+            // Leaving early. The neutral color isn't anybody's to give up.
+            if !session.current_state().current_player().is_neutral {
+                let confirming = confirm_forfeit == Some(current_player);
+                let forfeit_label = if confirming {
+                    "Really forfeit?"
+                } else {
+                    "Forfeit"
+                };
+                let forfeit_button = Button::new(forfeit_label)
+                    .position(vec2(
+                        0.05 * screen_width() + hint_dims.x + medium_ui_button_padding(),
+                        0.8 * screen_height() + hint_dims.y + medium_ui_button_padding(),
+                    ))
+                    .size(hint_dims);
+                if forfeit_button.ui(&mut root_ui()) {
+                    if confirming {
+                        placement_hint = None;
+                        session.forfeit(current_player);
+                    } else {
+                        confirm_forfeit = Some(current_player);
+                    }
+                }
+            }
 
-        handle_input(
-            &mut game_state,
-            &mut placement_hint,
-            play_area_top_left,
-            avail_pieces,
-            tile_size,
-            ui_tile_size,
-        );
+            // The pie rule: asked once, right after the opening.
+            if session.current_state().pie_swap_available() && !pie_declined {
+                let players = &session.current_state().players;
+                let (theirs, ours) = (players[0].color, players[1].color);
+                let swap_pos = vec2(
+                    0.05 * screen_width(),
+                    0.05 * screen_height() + hint_dims.y + medium_ui_button_padding(),
+                );
+                let swap_button = Button::new(format!("Take {theirs:?}'s opening"))
+                    .position(swap_pos)
+                    .size(hint_dims);
+                let keep_button = Button::new(format!("Stay {ours:?}"))
+                    .position(swap_pos + vec2(0., hint_dims.y + medium_ui_button_padding()))
+                    .size(hint_dims);
+                if swap_button.ui(&mut root_ui()) {
+                    placement_hint = None;
+                    session.current_state_mut().select_piece(None);
+                    session.swap_seats();
+                    flash = Some((format!("Swapped! Now playing {theirs:?}"), get_time() + 2.));
+                } else if keep_button.ui(&mut root_ui()) {
+                    pie_declined = true;
+                }
+            }
+        }
+
+        if bots_only {
+            let dims = medium_ui_button_dims();
+            let row = |n: f32| {
+                vec2(
+                    0.05 * screen_width(),
+                    0.8 * screen_height() + n * (dims.y + medium_ui_button_padding()),
+                )
+            };
+            let speed_button = Button::new(format!("Speed: {}", pace.speed.label()))
+                .position(row(0.))
+                .size(dims);
+            if speed_button.ui(&mut root_ui()) {
+                pace.speed = pace.speed.next();
+            }
+            let pause_label = if pace.paused { "Resume" } else { "Pause" };
+            let pause_button = Button::new(pause_label).position(row(1.)).size(dims);
+            if pause_button.ui(&mut root_ui()) {
+                pace.paused = !pace.paused;
+            }
+            if pace.paused {
+                let step_button = Button::new("Step")
+                    .position(row(1.) + vec2(dims.x + medium_ui_button_padding(), 0.))
+                    .size(dims);
+                step_once |= step_button.ui(&mut root_ui());
+            }
+        }
+
+        let save_button = Button::new("Save game")
+            .position(vec2(0.05 * screen_width(), 0.05 * screen_height()))
+            .size(medium_ui_button_dims());
+        if save_button.ui(&mut root_ui()) {
+            let message = match save::save_game(session.current_state(), save::QUICK_SAVE) {
+                Ok(()) => "Game saved".to_string(),
+                Err(e) => format!("Couldn't save: {e}"),
+            };
+            flash = Some((message, get_time() + 2.));
+        }
+        // Dump the game so it can be attached to a bug report (and fed to outside tools, as JSON).
+        if controls.keys.fired(Action::DumpState, is_key_pressed) {
+            let message = match save::dump_game(session.current_state()) {
+                Ok(()) => format!("Game written to {}/{}", save::SAVES_DIR, save::DUMP_SLOT),
+                Err(e) => format!("Couldn't write the game out: {e}"),
+            };
+            flash = Some((message, get_time() + 2.));
+        }
+
+        if !bots_only && view != ViewMode::Spectator {
+            let dims = medium_ui_button_dims();
+            let undo_button = Button::new("Undo")
+                .position(vec2(
+                    0.05 * screen_width() + dims.x + medium_ui_button_padding(),
+                    0.05 * screen_height(),
+                ))
+                .size(dims);
+            if undo_button.ui(&mut root_ui()) {
+                if session.request_undo() {
+                    placement_hint = None;
+                    pending_placement = None;
+                } else {
+                    flash = Some(("Nothing of yours to take back".to_string(), get_time() + 2.));
+                }
+            }
+        }
+        if session.take_undo_answer() == Some(false) {
+            flash = Some(("They said no to the undo".to_string(), get_time() + 2.));
+        }
+        // Over the network, taking a move back needs everyone's say-so.
+        if let Some(mover) = session.undo_vote_pending() {
+            let dims = medium_ui_button_dims();
+            let name = session.current_state().players[mover].display_name();
+            let yes_button = Button::new(format!("Let {name} undo"))
+                .position(vec2(
+                    0.5 * screen_width() - dims.x - 0.5 * medium_ui_button_padding(),
+                    0.05 * screen_height(),
+                ))
+                .size(dims);
+            let no_button = Button::new("Keep it")
+                .position(vec2(
+                    0.5 * screen_width() + 0.5 * medium_ui_button_padding(),
+                    0.05 * screen_height(),
+                ))
+                .size(dims);
+            if yes_button.ui(&mut root_ui()) {
+                session.vote_undo(true);
+            } else if no_button.ui(&mut root_ui()) {
+                session.vote_undo(false);
+            }
+        }
+        // An undo doesn't leave any events behind, just fewer pieces on the board.
+        if replay.rewind_to(session.current_state()) {
+            dead_cells = session.current_state().dead_cells();
+            live_standings = standings(session.current_state());
+        }
+
+        let events = session.current_state_mut().drain_events();
+        if events.iter().any(|event| {
+            matches!(
+                event,
+                GameEvent::PiecePlaced { .. } | GameEvent::Forfeited { .. }
+            )
+        }) {
+            dead_cells = session.current_state().dead_cells();
+            live_standings = standings(session.current_state());
+        }
+        for event in events {
+            replay.record(&event, session.current_state());
+            match event {
+                // Whatever the last player was aiming or about to confirm is theirs, not the
+                // next player's.
+                GameEvent::TurnEnded { next } => {
+                    placement_hint = None;
+                    pending_placement = None;
+                    confirm_pass = None;
+                    confirm_forfeit = None;
+                    if animate {
+                        turn_banner.announce(next, get_time());
+                    }
+                }
+                GameEvent::Forfeited { player } => {
+                    let name = session.current_state().players[player].display_name();
+                    flash = Some((format!("{name} forfeited"), get_time() + 2.));
+                }
+                // Our own passes already say why. Other people's would go by unnoticed.
+                GameEvent::Passed { player } if session.is_remote(player) => {
+                    let name = session.current_state().players[player].display_name();
+                    flash = Some((format!("{name} passed"), get_time() + 2.));
+                }
+                // The game over screen has the last word on who won.
+                GameEvent::GameOver { .. }
+                | GameEvent::PiecePlaced { .. }
+                | GameEvent::Passed { .. } => {}
+            }
+        }
+
+        if let Some((message, until)) = &flash {
+            if get_time() < *until {
+                let font_size = 0.05 * screen_height();
+                let dims = measure_text(message, None, font_size as u16, 1.);
+                draw_text(
+                    message,
+                    0.5 * (screen_width() - dims.width),
+                    0.15 * screen_height(),
+                    font_size,
+                    look.theme.text,
+                );
+            } else {
+                flash = None;
+            }
+        }
 
         next_frame().await;
     }
 
     // Game over screen
-    loop {
+    let standings = standings(session.current_state());
+    let summary = stats_summary(session.current_state());
+    let rematch = loop {
+        // Over the network, the host might start a rematch without us.
+        session.poll_remote();
+        if !session.current_state().is_game_over() {
+            break true;
+        }
+        let game_state = session.current_state();
         let play_again_dims = medium_ui_button_dims();
         let play_again_pos = centered_at(
             vec2(screen_width() / 2., 0.75 * screen_height()),
             play_again_dims,
         );
-        let draw_params = DrawTextureParams {
-            dest_size: Some(Vec2::new(screen_width(), screen_height())),
-            ..Default::default()
-        };
-        draw_texture_ex(win_texture, 0., 0., WHITE, draw_params);
-        let winning_player = &game_state.players[game_state.current_player];
-        draw_text(
-            &format!("{:?}", winning_player.color),
-            screen_width() / 2.,
-            screen_height() / 2.,
-            72.,
-            winning_player.color.into(),
-        );
+        assets.win.draw_fullscreen(BLACK);
+        let font_size = 0.04 * screen_height();
+        for (i, row) in standings.iter().enumerate() {
+            let rank = if row.tied {
+                format!("{}=", row.rank)
+            } else {
+                format!("{}.", row.rank)
+            };
+            let mut bonuses = String::new();
+            if row.all_pieces {
+                bonuses.push_str(", all pieces +15");
+            }
+            if row.dot_last {
+                bonuses.push_str(", dot last +5");
+            }
+            let forfeited = if row.forfeited { " (forfeited)" } else { "" };
+            let line = format!(
+                "{rank} {}{forfeited}: {} points, {} pieces left{bonuses}",
+                row.name, row.score, row.pieces_left
+            );
+            let dims = measure_text(&line, None, font_size as u16, 1.);
+            let pos = vec2(
+                0.5 * (screen_width() - dims.width),
+                0.4 * screen_height() + i as f32 * 1.5 * font_size,
+            );
+            // A backing so the rows stay legible over the banner, edged in the player's color.
+            let backing = Rect::new(
+                pos.x - 0.25 * font_size,
+                pos.y - dims.offset_y - 0.25 * font_size,
+                dims.width + 0.5 * font_size,
+                dims.height + 0.5 * font_size,
+            );
+            let Rect { x, y, w, h } = backing;
+            draw_rectangle(x, y, w, h, Color::new(1., 1., 1., 0.8));
+            draw_rectangle_lines(x, y, w, h, 4., look.palette.color(row.color));
+            draw_text(&line, pos.x, pos.y, font_size, BLACK);
+        }
+        if let Some(summary) = &summary {
+            let dims = measure_text(summary, None, font_size as u16, 1.);
+            draw_text(
+                summary,
+                0.5 * (screen_width() - dims.width),
+                0.4 * screen_height() + (standings.len() as f32 + 0.5) * 1.5 * font_size,
+                font_size,
+                BLACK,
+            );
+        }
 
         let play_again_button = Button::new("Return to lobby")
             .position(play_again_pos)
             .size(play_again_dims);
 
         if play_again_button.ui(&mut root_ui()) {
-            break;
+            break false;
         }
 
-        next_frame().await;
-    }
-}
+        let save_picture_button = Button::new("Save picture")
+            .position(play_again_pos + vec2(0., play_again_dims.y + medium_ui_button_padding()))
+            .size(play_again_dims);
+        if save_picture_button.ui(&mut root_ui()) {
+            let message = match screenshot::save_board_png(game_state, SCREENSHOT_PATH) {
+                Ok(()) => format!("Saved to {SCREENSHOT_PATH}"),
+                Err(e) => format!("Couldn't save the picture: {e}"),
+            };
+            flash = Some((message, get_time() + 2.));
+        }
+
+        let review_button = Button::new("Review")
+            .position(
+                play_again_pos + 2. * vec2(0., play_again_dims.y + medium_ui_button_padding()),
+            )
+            .size(play_again_dims);
+        if !replay.is_empty() && review_button.ui(&mut root_ui()) {
+            review_game(&replay, look).await;
+        }
+
+        // Same players, same rules, but whoever went second goes first.
+        let rematch_button = Button::new("Rematch")
+            .position(play_again_pos + vec2(play_again_dims.x + medium_ui_button_padding(), 0.))
+            .size(play_again_dims);
+        if rematch_button.ui(&mut root_ui()) {
+            if session.rematch(true) {
+                break true;
+            }
+            flash = Some((
+                "Only the host can start a rematch".to_string(),
+                get_time() + 2.,
+            ));
+        }
+        if let Some((message, until)) = &flash {
+            if get_time() < *until {
+                draw_text(
+                    message,
+                    play_again_pos.x,
+                    0.95 * screen_height(),
+                    32.,
+                    BLACK,
+                );
+            }
+        }
+
+        next_frame().await;
+    };
+    if rematch {
+        // Boxed, since a future can't hold another copy of itself.
+        Box::pin(game_loop(session, animate, look, placing)).await;
+    }
+}
+
+/// Steps back through a finished game, with the move each step made outlined on the board.
+/// The arrow keys go a move at a time, and the timeline under the board jumps anywhere.
+async fn review_game(replay: &Replay, look: Look) {
+    let theme = look.theme;
+    let mut step = replay.len();
+    let mut game_state = replay.step(step);
+    loop {
+        let last_step = step;
+        if is_key_pressed(KeyCode::Left) {
+            step = step.saturating_sub(1);
+        }
+        if is_key_pressed(KeyCode::Right) {
+            step = (step + 1).min(replay.len());
+        }
+        let timeline = Timeline {
+            rect: Rect::new(
+                0.1 * screen_width(),
+                0.93 * screen_height(),
+                0.8 * screen_width(),
+                0.03 * screen_height(),
+            ),
+            steps: replay.len(),
+        };
+        let mouse_pos = Vec2::from(mouse_position());
+        if is_mouse_button_down(MouseButton::Left) && timeline.rect.contains(mouse_pos) {
+            step = timeline.step_at(mouse_pos.x);
+        }
+        if step != last_step {
+            game_state = replay.step(step);
+        }
+
+        clear_background(theme.background);
+        let screen = ScreenInfo::compute(&game_state.config).with_tray_order(look.tray_order);
+        draw_game_screen(
+            &game_state,
+            &None,
+            None,
+            &screen,
+            &TileFades::new(&game_state.board, false),
+            ViewMode::Spectator,
+            look,
+        );
+        if look.piece_outlines {
+            draw_piece_outlines(&game_state, &screen, theme);
+        }
+
+        let wall = game_state.wall_thickness();
+        let placed = replay.placed_at(step);
+        if let Some((_, mv)) = placed {
+            for (row, col) in mv.board_cells(&game_state.config.pieces) {
+                let cell = ivec2((col - wall) as i32, (row - wall) as i32);
+                let Rect { x, y, w, h } = screen.cell_rect(cell);
+                draw_rectangle_lines(x, y, w, h, theme.border_thickness, theme.text);
+            }
+        }
+
+        // The timeline: filled in up to the current step, in the color that just moved.
+        let Rect { x, y, w, h } = timeline.rect;
+        draw_rectangle(x, y, w, h, theme.board_fill);
+        if let Some((player, _)) = placed {
+            let fill = look.palette.highlight(game_state.players[player].color);
+            draw_rectangle(x, y, timeline.marker_x(step) - x, h, fill);
+        }
+        draw_rectangle_lines(x, y, w, h, theme.grid_thickness, theme.grid);
+        let marker_x = timeline.marker_x(step);
+        draw_line(
+            marker_x,
+            y - 0.25 * h,
+            marker_x,
+            y + 1.25 * h,
+            theme.border_thickness,
+            theme.grid,
+        );
+        let caption = match placed {
+            Some((player, mv)) => format!(
+                "Move {step} of {}: {}",
+                replay.len(),
+                mv.to_notation(game_state.players[player].color, &game_state.config.pieces)
+            ),
+            None => format!("Before the first move (of {})", replay.len()),
+        };
+        draw_text(&caption, x, y - 0.5 * h, 0.03 * screen_height(), theme.text);
+
+        let back_button = Button::new("Back")
+            .position(vec2(0.05 * screen_width(), 0.05 * screen_height()))
+            .size(medium_ui_button_dims());
+        if back_button.ui(&mut root_ui()) || is_key_pressed(KeyCode::Escape) {
+            return;
+        }
+
+        next_frame().await;
+    }
+}
+
+/// A bar to scrub through a replay with, from before the first move on the left to after the
+/// last move on the right.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Timeline {
+    rect: Rect,
+    /// How many moves there are. There's one more step than that, counting the start.
+    steps: usize,
+}
+
+impl Timeline {
+    /// The step nearest to `x`, on screen.
+    fn step_at(&self, x: f32) -> usize {
+        let along = ((x - self.rect.x) / self.rect.w).clamp(0., 1.);
+        (along * self.steps as f32).round() as usize
+    }
+
+    /// Where on screen `step` sits.
+    fn marker_x(&self, step: usize) -> f32 {
+        match self.steps {
+            0 => self.rect.x,
+            steps => self.rect.x + self.rect.w * step as f32 / steps as f32,
+        }
+    }
+}
+
+/// One line of the scoreboard at the end of the game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct StandingsRow {
+    /// 1 for the winner. Everyone tied on a score shares the best rank among them.
+    rank: usize,
+    /// Whether somebody else has this rank too.
+    tied: bool,
+    color: TileColor,
+    name: String,
+    score: i32,
+    pieces_left: usize,
+    all_pieces: bool,
+    dot_last: bool,
+    forfeited: bool,
+    /// Whether they can't put anything else down, so their score is as good as final.
+    done: bool,
+}
+
+/// Everyone's results, best first: how it ended once the game's over, and how it stands so
+/// far before then. The shared color in a three-player game has nobody to win for, so it's
+/// left out.
+fn standings(game_state: &GameState) -> Vec<StandingsRow> {
+    let mut rows: Vec<StandingsRow> = (0..game_state.players.len())
+        .filter(|&idx| !game_state.players[idx].is_neutral)
+        .map(|idx| StandingsRow {
+            rank: 0,
+            tied: false,
+            color: game_state.players[idx].color,
+            name: game_state.players[idx].display_name(),
+            score: game_state.score(idx),
+            pieces_left: game_state.players[idx].remaining_pieces.len(),
+            all_pieces: game_state.played_every_piece(idx),
+            dot_last: game_state.dot_went_last(idx),
+            forfeited: game_state.players[idx].forfeited,
+            done: !game_state.players[idx].still_playing() || !game_state.any_legal_move_for(idx),
+        })
+        .collect();
+    // Stable, so ties stay in turn order.
+    rows.sort_by_key(|row| std::cmp::Reverse(row.score));
+
+    let scores: Vec<i32> = rows.iter().map(|row| row.score).collect();
+    for row in &mut rows {
+        row.rank = 1 + scores.iter().filter(|&&score| score > row.score).count();
+        row.tied = scores.iter().filter(|&&score| score == row.score).count() > 1;
+    }
+    rows
+}
+
+/// Who's ahead, in the top margin across from the tray: a line each, best first. Anyone
+/// who's given up or run out of moves is marked, since their score won't change.
+fn draw_live_standings(rows: &[StandingsRow], look: Look) {
+    let font_size = 0.025 * screen_height();
+    let left = 0.75 * screen_width();
+    for (i, row) in rows.iter().enumerate() {
+        let rank = if row.tied {
+            format!("{}=", row.rank)
+        } else {
+            format!("{}.", row.rank)
+        };
+        let status = if row.forfeited {
+            " (out)"
+        } else if row.done {
+            " (done)"
+        } else {
+            ""
+        };
+        let y = 0.05 * screen_height() + i as f32 * 1.5 * font_size;
+        draw_rectangle(left, y, font_size, font_size, look.palette.color(row.color));
+        draw_text(
+            &format!("{rank} {}: {}{status}", row.name, row.score),
+            left + 1.5 * font_size,
+            y + 0.8 * font_size,
+            font_size,
+            look.theme.text,
+        );
+    }
+}
+
+/// A line about how the game went for under the standings: how long moves took on average
+/// and the biggest piece anyone put down. Nothing if nobody placed anything.
+fn stats_summary(game_state: &GameState) -> Option<String> {
+    let stats = game_state.stats();
+    // The first biggest piece wins ties, so it's whoever got there first.
+    let biggest = stats.iter().rev().max_by_key(|stats| stats.tiles)?;
+    let times: Vec<f64> = stats.iter().filter_map(|stats| stats.seconds).collect();
+    let average = match times.len() {
+        0 => String::new(),
+        n => format!(
+            "average move {:.1}s, ",
+            times.iter().sum::<f64>() / n as f64
+        ),
+    };
+    Some(format!(
+        "{average}biggest piece {} by {} ({} tiles)",
+        game_state.config.pieces.name(biggest.piece_id),
+        game_state.players[biggest.player].display_name(),
+        biggest.tiles,
+    ))
+}
+
+/// Where everything on the game screen goes, in pixels. Drawing and input handling share
+/// one of these per frame so they can't disagree about where the board is.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ScreenInfo {
+    /// The window this was laid out for.
+    width: f32,
+    height: f32,
+    /// Top left of the gray board, border and all.
+    board_top_left: Vec2,
+    /// Side length of the gray board.
+    board_size: f32,
+    /// Top left of the cells pieces actually go on.
+    play_area_top_left: Vec2,
+    /// Where the current player's pieces go, under the board.
+    tray: TrayLayout,
+    /// Where everyone else's leftover pieces go, right of the board under the scoreboard.
+    opponents_margin: Rect,
+    /// Side length of a board cell. Cells get drawn snapped to whole pixels, so on screen
+    /// they're a pixel either way of this (see `snap_to_pixel`).
+    tile_size: f32,
+    /// Side length of a cell in the piece tray.
+    ui_tile_size: f32,
+    /// How many cells across the play area is.
+    board_dim: usize,
+    /// Quarter turns clockwise the board's drawn at. Only the drawing turns: cells keep their
+    /// coordinates, and clicks get turned back on the way in.
+    view_rotation: u8,
+}
+
+/// A grid of piece slots, one per piece whether or not it's still in hand, filled in a row
+/// at a time in the order `order` puts them in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct TrayLayout {
+    top_left: Vec2,
+    /// Side length of a cell in a piece's 5x5 grid.
+    ui_tile_size: f32,
+    columns: usize,
+    /// How many slots there are: one for every piece in the set.
+    pieces: usize,
+    order: TrayOrder,
+}
+
+/// Which pieces go first in the tray.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum TrayOrder {
+    /// The order the set lists them in, which is how notation numbers them.
+    #[default]
+    Id,
+    /// Monomino first, pentominoes last.
+    SizeAsc,
+    /// Pentominoes first, for getting the big ones out early.
+    SizeDesc,
+}
+
+impl TrayOrder {
+    /// The pieces in `pieces`, in the order their slots go. Ties keep set order.
+    fn arrangement(self, pieces: &PieceSet) -> Vec<logic::PieceID> {
+        let mut order: Vec<_> = (0..pieces.len()).collect();
+        match self {
+            Self::Id => {}
+            Self::SizeAsc => order.sort_by_key(|&piece_id| pieces.size(piece_id)),
+            Self::SizeDesc => {
+                order.sort_by_key(|&piece_id| std::cmp::Reverse(pieces.size(piece_id)))
+            }
+        }
+        order
+    }
+
+    /// The next order, for the button that cycles through them.
+    fn next(self) -> Self {
+        match self {
+            Self::Id => Self::SizeAsc,
+            Self::SizeAsc => Self::SizeDesc,
+            Self::SizeDesc => Self::Id,
+        }
+    }
+}
+
+impl TrayLayout {
+    /// The most pieces a row ever holds, which is enough to fit all `pieces` in two.
+    fn max_columns(pieces: usize) -> usize {
+        pieces.div_ceil(2)
+    }
+
+    /// The biggest tray for `pieces` pieces that fits in `space`, up to `ui_tile_size` a
+    /// cell, centered across it. Narrow spaces get more, shorter rows, and tiles shrink if
+    /// that's still not enough.
+    fn fit(space: Rect, ui_tile_size: f32, pieces: usize) -> Self {
+        let (columns, ui_tile_size) = (1..=Self::max_columns(pieces))
+            .map(|columns| {
+                let rows = pieces.div_ceil(columns);
+                let fits = (space.w / columns as f32).min(space.h / rows as f32) / 5.;
+                (columns, ui_tile_size.min(fits))
+            })
+            // Ties go to more columns, since later items win `max_by`.
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .expect("There's at least one column.");
+        let width = columns as f32 * 5. * ui_tile_size;
+        Self {
+            top_left: vec2(space.center().x - 0.5 * width, space.y),
+            ui_tile_size,
+            columns,
+            pieces,
+            order: TrayOrder::default(),
+        }
+    }
+
+    /// The `position`th slot, counting across each row.
+    fn slot_at(&self, position: usize) -> Rect {
+        let piece_size = 5. * self.ui_tile_size;
+        let (row, col) = (position / self.columns, position % self.columns);
+        Rect::new(
+            self.top_left.x + col as f32 * piece_size,
+            self.top_left.y + row as f32 * piece_size,
+            piece_size,
+            piece_size,
+        )
+    }
+
+    /// The whole tray, empty slots at the end of the last row included.
+    fn rect(&self) -> Rect {
+        let piece_size = 5. * self.ui_tile_size;
+        let rows = self.pieces.div_ceil(self.columns);
+        Rect::new(
+            self.top_left.x,
+            self.top_left.y,
+            self.columns as f32 * piece_size,
+            rows as f32 * piece_size,
+        )
+    }
+
+    /// Every piece in `pieces` with its slot. Drawing and clicking both go through this, so
+    /// they always agree.
+    fn slots(&self, pieces: &PieceSet) -> impl Iterator<Item = (logic::PieceID, Rect)> + '_ {
+        let arrangement = self.order.arrangement(pieces);
+        arrangement
+            .into_iter()
+            .enumerate()
+            .map(|(position, piece_id)| (piece_id, self.slot_at(position)))
+    }
+
+    /// Which piece's slot `pos` is over, whether or not the player still has it.
+    fn piece_at(&self, pos: Vec2, pieces: &PieceSet) -> Option<logic::PieceID> {
+        self.slots(pieces)
+            .find(|(_, slot)| slot.contains(pos))
+            .map(|(piece_id, _)| piece_id)
+    }
+}
+
+impl ScreenInfo {
+    /// Lays out the screen for the current window and a game played with `config`'s board
+    /// and pieces.
+    fn compute(config: &GameConfig) -> Self {
+        Self::for_window(screen_width(), screen_height(), config)
+    }
+
+    fn for_window(width: f32, height: f32, config: &GameConfig) -> Self {
+        // Tall, narrow windows would push the board off the sides, so it shrinks to fit
+        // and slides back on screen.
+        let board_size = (height * BOARD_SIZE).min(0.9 * width);
+        // x = board_left's x coord, y = board_top's y coord
+        let board_top_left = vec2(
+            (width * BOARD_SIZE - height * BOARD_HORIZ_OFFSET).clamp(0., width - board_size),
+            height * BOARD_VERT_OFFSET,
+        );
+        let play_area_top_left = board_top_left + Vec2::splat(0.05 * board_size);
+        // The play area always takes up 90% of the board, however many tiles it has.
+        let tile_size = 0.9 * board_size / config.board_dim as f32;
+
+        // wanted to halve the area so I multiply the side length by sqrt(2)/2.
+        // Sized off of the classic board's tiles so the tray doesn't balloon on smaller boards.
+        let ui_tile_size = 0.045 * board_size * 0.5 * 1.414;
+        // The tray gets everything under the board, give or take a margin. Wide windows fit
+        // it in two rows of 11; narrow ones wrap it into more rows and shrink it if they must.
+        let tray_space = Rect::new(0.025 * width, 0.8 * height, 0.95 * width, 0.18 * height);
+        let tray = TrayLayout::fit(tray_space, ui_tile_size, config.pieces.len());
+        let ui_tile_size = tray.ui_tile_size;
+        // The scoreboard takes the top of the right margin, with room for four players.
+        let margin_left = board_top_left.x + board_size + 0.04 * height;
+        let opponents_margin = Rect::new(
+            margin_left,
+            board_top_left.y + 0.25 * height,
+            (0.98 * width - margin_left).max(0.),
+            board_size - 0.25 * height,
+        );
+
+        Self {
+            width,
+            height,
+            board_top_left,
+            board_size,
+            play_area_top_left,
+            tray,
+            opponents_margin,
+            tile_size,
+            ui_tile_size,
+            board_dim: config.board_dim,
+            view_rotation: 0,
+        }
+    }
+
+    fn board_rect(&self) -> Rect {
+        let Vec2 { x, y } = self.board_top_left;
+        let (left, top) = (x.round(), y.round());
+        let (right, bottom) = ((x + self.board_size).round(), (y + self.board_size).round());
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    /// Exactly `board_dim` cells across, so its border lands right on the last grid lines.
+    fn play_area_rect(&self) -> Rect {
+        let Vec2 { x, y } = self.play_area_top_left;
+        let dim = self.board_dim as i32;
+        let (left, top) = (x.round(), y.round());
+        let right = snap_to_pixel(x, self.tile_size, dim);
+        let bottom = snap_to_pixel(y, self.tile_size, dim);
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    /// Where the play area cell `cell` (x = column, y = row) is drawn. Neighbors share an
+    /// edge exactly, so there are no seams between them.
+    fn cell_rect(&self, cell: IVec2) -> Rect {
+        self.spot_rect(self.turned(cell))
+    }
+
+    /// The `spot`th square of the grid on screen, counting from the top left whichever way
+    /// the board's turned.
+    fn spot_rect(&self, spot: IVec2) -> Rect {
+        let Vec2 { x, y } = self.play_area_top_left;
+        let left = snap_to_pixel(x, self.tile_size, spot.x);
+        let top = snap_to_pixel(y, self.tile_size, spot.y);
+        let right = snap_to_pixel(x, self.tile_size, spot.x + 1);
+        let bottom = snap_to_pixel(y, self.tile_size, spot.y + 1);
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    /// Which play area cell `pos` is over, if any. The inverse of `cell_rect`, so clicks
+    /// land on the cell that was drawn under them.
+    fn cell_at(&self, pos: Vec2) -> Option<IVec2> {
+        self.play_area_rect().contains(pos).then(|| {
+            let mut spot = ((pos - self.play_area_top_left) / self.tile_size).as_ivec2();
+            // Snapping moves each edge up to half a pixel, so the cell drawn under `pos` might
+            // be the next one over.
+            let rect = self.spot_rect(spot);
+            spot.x += (pos.x >= rect.right()) as i32 - (pos.x < rect.x) as i32;
+            spot.y += (pos.y >= rect.bottom()) as i32 - (pos.y < rect.y) as i32;
+            // Right on the far edge still counts as the last cell.
+            self.unturned(spot.clamp(IVec2::ZERO, IVec2::splat(self.board_dim as i32 - 1)))
+        })
+    }
+
+    /// Where `cell` lands on screen once the board's turned by `view_rotation`.
+    fn turned(&self, cell: IVec2) -> IVec2 {
+        turn_cell(cell, self.board_dim, self.view_rotation)
+    }
+
+    /// Which cell ended up at `spot` on screen. The inverse of `turned`.
+    fn unturned(&self, spot: IVec2) -> IVec2 {
+        turn_cell(spot, self.board_dim, 4 - self.view_rotation % 4)
+    }
+
+    /// Which way a step of `nudge` on screen goes on the board, so the arrow keys move the
+    /// cursor the way they look like they should.
+    fn unturned_step(&self, nudge: IVec2) -> IVec2 {
+        self.unturned(nudge) - self.unturned(IVec2::ZERO)
+    }
+
+    fn avail_pieces_rect(&self) -> Rect {
+        self.tray.rect()
+    }
+
+    /// Which piece's slot in the tray `pos` is over, whether or not the player still has it.
+    fn tray_piece_at(&self, pos: Vec2, pieces: &PieceSet) -> Option<logic::PieceID> {
+        self.tray.piece_at(pos, pieces)
+    }
+
+    /// Where the `nth` of `opponents` opponents' `count` leftover pieces go, packed together
+    /// at a third of the tray's size at most. They split the margin between them.
+    fn opponent_tray(&self, nth: usize, opponents: usize, count: usize) -> TrayLayout {
+        let margin = self.opponents_margin;
+        let band = margin.h / opponents as f32;
+        let space = Rect::new(margin.x, margin.y + nth as f32 * band, margin.w, band);
+        TrayLayout::fit(space, self.ui_tile_size / 3., count.max(1))
+    }
+
+    /// This layout with the tray's pieces in `order`.
+    fn with_tray_order(mut self, order: TrayOrder) -> Self {
+        self.tray.order = order;
+        self
+    }
+
+    /// This layout with the board turned `quarter_turns` clockwise.
+    fn with_view_rotation(mut self, quarter_turns: u8) -> Self {
+        self.view_rotation = quarter_turns % 4;
+        self
+    }
+
+    /// This layout with the board zoomed and panned by `view`. Everything else stays put.
+    /// Clicks go through the same fields drawing does, so they follow the board around.
+    fn viewed_through(self, view: BoardView) -> Self {
+        let center = self.board_rect().center();
+        let moved = |point: Vec2| center + (point - center) * view.zoom + view.pan;
+        Self {
+            board_top_left: moved(self.board_top_left),
+            board_size: view.zoom * self.board_size,
+            play_area_top_left: moved(self.play_area_top_left),
+            tile_size: view.zoom * self.tile_size,
+            ..self
+        }
+    }
+}
+
+/// How far into the board the player has zoomed, and where they've dragged it to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct BoardView {
+    /// 1 shows the board the size it's laid out at.
+    zoom: f32,
+    /// How far the board's center has moved from where it's laid out, in pixels.
+    pan: Vec2,
+    /// Where the pointer was last frame, while the middle button's held down to drag.
+    dragging_from: Option<Vec2>,
+}
+
+impl Default for BoardView {
+    fn default() -> Self {
+        Self {
+            zoom: 1.,
+            pan: Vec2::ZERO,
+            dragging_from: None,
+        }
+    }
+}
+
+impl BoardView {
+    const MAX_ZOOM: f32 = 4.;
+    /// How fast the pan keys move the board, in pixels a second.
+    const PAN_SPEED: f32 = 600.;
+
+    /// Zooms in (or out, for `factor` under 1) while keeping whatever's under `pointer` where
+    /// it is. `base` is the screen before any zooming or panning.
+    fn zoom_at(&mut self, factor: f32, pointer: Vec2, base: &ScreenInfo) {
+        let center = base.board_rect().center();
+        // Where `pointer` would be on the board laid out as usual.
+        let unzoomed = center + (pointer - self.pan - center) / self.zoom;
+        self.zoom = (self.zoom * factor).clamp(1., Self::MAX_ZOOM);
+        self.pan = pointer - center - (unzoomed - center) * self.zoom;
+        self.clamp(base);
+    }
+
+    /// Keeps the middle of the board in the window, so there's always some of it showing.
+    fn clamp(&mut self, base: &ScreenInfo) {
+        let center = base.board_rect().center();
+        let window = vec2(base.width, base.height);
+        self.pan = (center + self.pan).clamp(Vec2::ZERO, window) - center;
+    }
+}
+
+/// Zooms and pans the board: Ctrl and the mouse wheel to zoom, the middle button (or the pan
+/// keys, WASD unless they've been rebound, with nothing in hand) to drag it around, and the
+/// reset key to put it back. `base` is the screen before any of that.
+fn steer_board_view(
+    view: &mut BoardView,
+    base: &ScreenInfo,
+    keys: &KeyBindings,
+    hands_empty: bool,
+) {
+    let pointer = Vec2::from(mouse_position());
+    if keys.fired(Action::ResetView, is_key_pressed) {
+        *view = BoardView::default();
+    }
+
+    let (_, wheel_y) = mouse_wheel();
+    if ctrl_down() && wheel_y != 0. {
+        view.zoom_at(1.25_f32.powf(wheel_y.signum()), pointer, base);
+    }
+
+    if is_mouse_button_down(MouseButton::Middle) {
+        if let Some(from) = view.dragging_from {
+            view.pan += pointer - from;
+        }
+        view.dragging_from = Some(pointer);
+    } else {
+        view.dragging_from = None;
+    }
+
+    // The pan keys flip whatever's in hand, so they only pan when there's nothing there.
+    if hands_empty {
+        let pans = [
+            (Action::PanUp, vec2(0., 1.)),
+            (Action::PanLeft, vec2(1., 0.)),
+            (Action::PanDown, vec2(0., -1.)),
+            (Action::PanRight, vec2(-1., 0.)),
+        ];
+        for (action, dir) in pans {
+            if keys.fired(action, is_key_down) {
+                view.pan += dir * BoardView::PAN_SPEED * get_frame_time();
+            }
+        }
+    }
+    view.clamp(base);
+}
+
+fn ctrl_down() -> bool {
+    is_key_down(KeyCode::LeftControl) || is_key_down(KeyCode::RightControl)
+}
+
+/// How long a freshly placed tile takes to fade in, in seconds.
+const FADE_IN_SECS: f64 = 0.15;
+
+/// Remembers when tiles showed up on the board so `draw_game_screen` can fade them in.
+/// Purely cosmetic: it watches the board rather than hooking into `place_piece`.
+struct TileFades {
+    /// When off, tiles are drawn solid the moment they're placed.
+    enabled: bool,
+    last_board: Vec<Vec<TileColor>>,
+    /// (row, col) in board coordinates -> when it was placed, in seconds.
+    placed_at: HashMap<(usize, usize), f64>,
+}
+
+impl TileFades {
+    fn new(board: &[Vec<TileColor>], enabled: bool) -> Self {
+        Self {
+            enabled,
+            last_board: board.to_vec(),
+            placed_at: HashMap::new(),
+        }
+    }
+
+    /// Notices any tiles placed since the last call and forgets ones done fading.
+    /// `now` is in seconds, like `get_time`.
+    fn update(&mut self, board: &[Vec<TileColor>], now: f64) {
+        if !self.enabled {
+            return;
+        }
+        // A whole new board, like after a resync to another size, starts over without fading.
+        if board.len() != self.last_board.len() {
+            self.placed_at.clear();
+            self.last_board = board.to_vec();
+            return;
+        }
+
+        for (row, (old_row, new_row)) in self.last_board.iter().zip(board).enumerate() {
+            for (col, (old, new)) in old_row.iter().zip(new_row).enumerate() {
+                if old != new {
+                    self.placed_at.insert((row, col), now);
+                }
+            }
+        }
+        self.placed_at
+            .retain(|_, placed_at| now - *placed_at < FADE_IN_SECS);
+        self.last_board.clone_from_slice(board);
+    }
+
+    /// How far the tile at (row, col) is through fading in, from 0 (just placed) to 1 (done).
+    fn progress(&self, row: usize, col: usize, now: f64) -> f32 {
+        match self.placed_at.get(&(row, col)) {
+            Some(placed_at) => ((now - placed_at) / FADE_IN_SECS).clamp(0., 1.) as f32,
+            None => 1.,
+        }
+    }
+}
+
+/// How long the banner saying whose turn it is stays up, in seconds.
+const TURN_BANNER_SECS: f64 = 1.;
+
+/// A banner naming whoever's turn it just became, which slides in and fades back out. It's
+/// only for show, so nothing waits on it.
+#[derive(Debug, Default)]
+struct TurnBanner {
+    /// Whose turn it's announcing, and when it went up, in seconds like `get_time`.
+    shown: Option<(usize, f64)>,
+}
+
+impl TurnBanner {
+    /// Puts the banner up for `player`, replacing whatever it said before.
+    fn announce(&mut self, player: usize, now: f64) {
+        self.shown = Some((player, now));
+    }
+
+    /// Who the banner's announcing and how far through it is, from 0 (just up) to 1, or
+    /// `None` once it's gone.
+    fn progress(&self, now: f64) -> Option<(usize, f32)> {
+        let (player, shown_at) = self.shown?;
+        let t = (now - shown_at) / TURN_BANNER_SECS;
+        (0. ..1.).contains(&t).then_some((player, t as f32))
+    }
+
+    fn draw(&self, game_state: &GameState, now: f64, look: Look) {
+        let Some((player_idx, t)) = self.progress(now) else {
+            return;
+        };
+        let player = &game_state.players[player_idx];
+        // In from the left over the first fifth, then fading out over the last third.
+        let slide = (t / 0.2).min(1.);
+        let alpha = ((1. - t) / 0.3).min(1.);
+
+        let text = format!("{}'s turn", player.display_name());
+        let font_size = 0.06 * screen_height();
+        let dims = measure_text(&text, None, font_size as u16, 1.);
+        let swatch = font_size;
+        let (w, h) = (dims.width + 2. * swatch, 1.6 * font_size);
+        // Eased, so it slows down as it arrives.
+        let x = -w + (0.5 * (screen_width() - w) + w) * (1. - (1. - slide).powi(2));
+        let y = 0.3 * screen_height();
+        let fill = Color {
+            a: 0.85 * alpha,
+            ..look.theme.background
+        };
+        let color = Color {
+            a: alpha,
+            ..look.palette.color(player.color)
+        };
+        draw_rectangle(x, y, w, h, fill);
+        draw_rectangle_lines(x, y, w, h, 4., color);
+        draw_rectangle(
+            x + 0.3 * swatch,
+            y + 0.5 * (h - 0.7 * swatch),
+            0.7 * swatch,
+            0.7 * swatch,
+            color,
+        );
+        draw_text(
+            &text,
+            x + 1.5 * swatch,
+            y + 0.5 * (h + dims.offset_y) - 0.1 * font_size,
+            font_size,
+            Color {
+                a: alpha,
+                ..look.theme.text
+            },
+        );
+    }
+}
+
+fn lerp_color(from: Color, to: Color, t: f32) -> Color {
+    Color::new(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+        from.a + (to.a - from.a) * t,
+    )
+}
+
+/// Everything the player picked about how the game is drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct Look {
+    palette: Palette,
+    tile_style: TileStyle,
+    theme: Theme,
+    /// Whether the board's rows and columns are labeled, for following along with notation.
+    coordinates: bool,
+    /// Whether each piece on the board gets its own outline, so two pieces of the same color
+    /// side by side can be told apart.
+    piece_outlines: bool,
+    tray_order: TrayOrder,
+    /// Whether the board turns so whoever's playing at this screen has their start corner at
+    /// the bottom left, like they're sitting at their side of a real board. Pieces in hand
+    /// and in the tray turn with it. See `facing_seat`.
+    turn_board: bool,
+}
+
+/// Whose eyes the game screen is drawn through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ViewMode {
+    /// Someone sitting at this seat. They see their own hand, and on their turn, the piece
+    /// they're holding.
+    Player(usize),
+    /// Someone watching, like a stream or a networked spectator. They see everyone's hands,
+    /// but nobody's piece in hand, since they aren't placing anything.
+    Spectator,
+}
+
+impl ViewMode {
+    /// Whether this viewer is the one taking the current turn.
+    fn is_their_turn(self, game_state: &GameState) -> bool {
+        self == Self::Player(game_state.current_player)
+    }
+
+    /// The placement hint this viewer gets to see, if any.
+    fn placement_hint(self, game_state: &GameState, hint: Option<IVec2>) -> Option<IVec2> {
+        hint.filter(|_| self.is_their_turn(game_state))
+    }
+}
+
+fn draw_game_screen(
+    game_state: &GameState,
+    placement_hint: &Option<IVec2>,
+    hovered_piece: Option<logic::PieceID>,
+    screen: &ScreenInfo,
+    fades: &TileFades,
+    view: ViewMode,
+    look: Look,
+) {
+    let Look {
+        palette,
+        tile_style,
+        theme,
+        coordinates,
+        ..
+    } = look;
+    let ScreenInfo {
+        width: screen_width,
+        height: screen_height,
+        tray,
+        tile_size,
+        ui_tile_size,
+        ..
+    } = *screen;
+    let board_rect = screen.board_rect();
+    let play_area_rect = screen.play_area_rect();
+    let placement_hint = view.placement_hint(game_state, *placement_hint);
+    let your_turn = view.is_their_turn(game_state);
 
-fn draw_game_screen(
-    game_state: &GameState,
-    placement_hint: &Option<IVec2>,
-    // mayhaps I should bundle these together into "screeninfo"
-    board_top_left: Vec2,
-    play_area_top_left: Vec2,
-    avail_pieces_top_left: Vec2,
-    tile_size: f32,
-    ui_tile_size: f32,
-) {
     // Board
     draw_rectangle(
-        board_top_left.x,
-        board_top_left.y,
-        screen_height() * BOARD_SIZE,
-        screen_height() * BOARD_SIZE,
-        GRAY,
+        board_rect.x,
+        board_rect.y,
+        board_rect.w,
+        board_rect.h,
+        theme.board_fill,
     );
 
-    // Draw the colorful tiles
-    for row in 0..20 {
-        for col in 0..20 {
-            draw_rectangle(
-                play_area_top_left.x + col as f32 * tile_size,
-                play_area_top_left.y + row as f32 * tile_size,
+    // Draw the colorful tiles. Empty cells are just the board showing through.
+    let board_dim = game_state.play_area_dim();
+    let wall = game_state.wall_thickness();
+    let now = get_time();
+    for (row, col, tile) in game_state.occupied_cells() {
+        // New tiles start out looking like the placement hint and settle into their color.
+        let color = lerp_color(
+            palette.highlight(tile),
+            palette.color(tile),
+            fades.progress(row + wall, col + wall, now),
+        );
+        let cell = screen.cell_rect(ivec2(col as i32, row as i32));
+        tile::draw_tile(tile_style, cell, color);
+        if let Some(symbol) = palette.symbol(tile) {
+            draw_symbol(symbol, cell.point(), tile_size);
+        }
+    }
+
+    // Dim the board and light up everywhere the held piece fits, as it's turned now.
+    if your_turn && game_state.selected_piece.is_some() {
+        let dim = Color::new(0., 0., 0., 0.2);
+        let Rect { x, y, w, h } = play_area_rect;
+        draw_rectangle(x, y, w, h, dim);
+        let spot_color = palette.highlight(game_state.current_player().color);
+        for anchor in game_state.legal_anchors() {
+            let top_left = screen.cell_rect(anchor).point();
+            let inset = 0.3 * tile_size;
+            let side = tile_size - 2. * inset;
+            draw_rectangle(
+                top_left.x + inset,
+                top_left.y + inset,
+                side,
+                side,
+                spot_color,
+            );
+        }
+    }
+
+    // Show new players where their first piece has to go.
+    for (row, col) in game_state.first_move_corners() {
+        let color = game_state.current_player().color;
+        let center = screen.cell_rect(ivec2(col as i32, row as i32)).center();
+        let radius = 0.45 * tile_size;
+        draw_poly(center.x, center.y, 4, radius, 0., palette.highlight(color));
+        draw_poly_lines(center.x, center.y, 4, radius, 0., 2., palette.color(color));
+    }
+
+    if let Some(IVec2 { x: l_col, y: l_row }) = placement_hint {
+        // Okay, looks like we have a placeable piece -- let's draw where it could be.
+        for (dr, r) in game_state.piece_buffer.iter().enumerate() {
+            for dc in r.iter_ones() {
+                let cell = screen.cell_rect(ivec2(l_col + dc as i32, l_row + dr as i32));
+                tile::draw_tile(
+                    tile_style,
+                    cell,
+                    palette.highlight(game_state.current_player().color),
+                );
+            }
+        }
+    }
+
+    // Tell the player why their piece won't go where they're pointing.
+    if your_turn && game_state.selected_piece.is_some() && placement_hint.is_none() {
+        let reason = game_state
+            .check_bounds_and_recenter(game_state.held_center(game_state.cursor))
+            .map_or(Err(MoveError::OutOfBounds), |corner| {
+                game_state.validate_move(corner + IVec2::splat(wall as i32))
+            });
+        if let Err(reason) = reason {
+            draw_text(
+                &format!("That piece {reason}."),
+                board_rect.x,
+                board_rect.bottom() + 0.03 * screen_height,
+                0.03 * screen_height,
+                theme.text,
+            );
+        }
+    }
+
+    // Solitaire's only opponent is the target.
+    if game_state.config.solitaire {
+        draw_text(
+            &format!(
+                "Placed {} of {} tiles",
+                game_state.score(0),
+                game_state.solitaire_target()
+            ),
+            board_rect.x,
+            board_rect.bottom() + 0.06 * screen_height,
+            0.03 * screen_height,
+            theme.text,
+        );
+    }
+
+    // Point at a pocket of empty cells, empty-handed, to see which pieces could still go in it.
+    if your_turn && game_state.selected_piece.is_none() {
+        if let Some(pocket) = pocket_at(game_state, game_state.cursor) {
+            let outline = palette.highlight(game_state.current_player().color);
+            for &(row, col) in &pocket {
+                let Rect { x, y, w, h } = screen.cell_rect(ivec2(col as i32, row as i32));
+                draw_rectangle_lines(x, y, w, h, theme.border_thickness, outline);
+            }
+            let names: Vec<_> = game_state
+                .reachable_pieces_for_region(&pocket, game_state.current_player)
+                .into_iter()
+                .map(|piece_id| game_state.config.pieces.name(piece_id))
+                .collect();
+            let text = if names.is_empty() {
+                "None of your pieces fit in here.".to_string()
+            } else {
+                format!("Fits in here: {}", names.join(", "))
+            };
+            draw_text(
+                &text,
+                board_rect.x,
+                board_rect.bottom() + 0.03 * screen_height,
+                0.03 * screen_height,
+                theme.text,
+            );
+        }
+    }
+
+    // Board Border
+    draw_rectangle_lines(
+        board_rect.x,
+        board_rect.y,
+        board_rect.w,
+        board_rect.h,
+        theme.border_thickness,
+        theme.grid,
+    );
+
+    // Play area border
+    draw_rectangle_lines(
+        play_area_rect.x,
+        play_area_rect.y,
+        play_area_rect.w,
+        play_area_rect.h,
+        theme.border_thickness,
+        theme.grid,
+    );
+
+    // grid time, right along the cells' (snapped) edges
+    // vertical lines:
+    for i in 1..board_dim {
+        let line_x = screen.spot_rect(ivec2(i as i32, 0)).x;
+        draw_line(
+            line_x,
+            play_area_rect.y,
+            line_x,
+            play_area_rect.bottom(),
+            theme.grid_thickness,
+            theme.grid,
+        );
+    }
+
+    // horizontal lines:
+    for i in 1..board_dim {
+        let line_y = screen.spot_rect(ivec2(0, i as i32)).y;
+        draw_line(
+            play_area_rect.x,
+            line_y,
+            play_area_rect.right(),
+            line_y,
+            theme.grid_thickness,
+            theme.grid,
+        );
+    }
+
+    if coordinates {
+        draw_coordinates(screen, board_dim, theme.text);
+    }
+
+    // The turn timer, shrinking along the top of the board.
+    if let Some(left) = game_state.turn_time_left(now) {
+        let bar = Rect::new(
+            board_rect.x,
+            board_rect.y - 0.03 * screen_height,
+            board_rect.w,
+            0.015 * screen_height,
+        );
+        let color = palette.color(game_state.current_player().color);
+        draw_rectangle(bar.x, bar.y, bar.w * left as f32, bar.h, color);
+        draw_rectangle_lines(bar.x, bar.y, bar.w, bar.h, theme.grid_thickness, theme.grid);
+    }
+
+    // Scoreboard: who has how much left to place, to the right of the board.
+    let swatch_size = 0.04 * screen_height;
+    for (idx, player) in game_state.players.iter().enumerate() {
+        let swatch_pos = vec2(
+            board_rect.right() + swatch_size,
+            board_rect.y + idx as f32 * 1.5 * swatch_size,
+        );
+        draw_rectangle(
+            swatch_pos.x,
+            swatch_pos.y,
+            swatch_size,
+            swatch_size,
+            palette.color(player.color),
+        );
+        // Whoever's up gets a thicker outline.
+        let outline = if idx == game_state.current_player {
+            theme.border_thickness
+        } else {
+            theme.grid_thickness
+        };
+        draw_rectangle_lines(
+            swatch_pos.x,
+            swatch_pos.y,
+            swatch_size,
+            swatch_size,
+            outline,
+            theme.grid,
+        );
+        draw_text(
+            &format!(
+                "{}{}: {} tiles left, score {}",
+                if player.is_neutral { "(shared) " } else { "" },
+                player.display_name(),
+                game_state.remaining_tile_count(idx),
+                game_state.score(idx)
+            ),
+            swatch_pos.x + 1.5 * swatch_size,
+            swatch_pos.y + 0.75 * swatch_size,
+            swatch_size,
+            theme.text,
+        );
+    }
+
+    let player = &game_state.players[game_state.current_player];
+    let piece_left = 0.05 * screen_width;
+    let piece_top = 0.35 * screen_height;
+    // A piece they're only looking at takes over the preview from the one in hand.
+    let preview = match hovered_piece {
+        Some(piece_id) => Some((
+            piece_id,
+            game_state.config.pieces.shape(piece_id),
+            Orientation::default(),
+        )),
+        None => game_state.selected_piece.map(|piece_id| {
+            let orientation = game_state
+                .held_move(IVec2::ZERO)
+                .map_or(Orientation::default(), |mv| mv.orientation);
+            (piece_id, game_state.piece_buffer, orientation)
+        }),
+    };
+    if let Some((piece_id, shape, orientation)) = preview.filter(|_| your_turn) {
+        // piece preview border
+        draw_rectangle(
+            piece_left - tile_size,
+            piece_top - tile_size,
+            7. * tile_size,
+            7. * tile_size,
+            theme.board_fill,
+        );
+
+        draw_rectangle_lines(
+            piece_left - tile_size,
+            piece_top - tile_size,
+            7. * tile_size,
+            7. * tile_size,
+            theme.border_thickness,
+            theme.grid,
+        );
+
+        // Piece preview, turned like the board so it looks like the piece on it.
+        draw_piece(
+            turned_shape(shape, screen.view_rotation),
+            player.color,
+            vec2(piece_left, piece_top),
+            tile_size,
+            true,
+            look,
+        );
+
+        // What it's called and how it's turned, in the border's top and bottom rows. Long
+        // names get shrunk to fit.
+        let (name, turned) = preview_labels(&game_state.config.pieces, piece_id, orientation);
+        let room = 6.5 * tile_size;
+        for (text, baseline) in [
+            (name, piece_top - 0.25 * tile_size),
+            (turned, piece_top + 5.75 * tile_size),
+        ] {
+            let font_size = 0.7 * tile_size;
+            let width = measure_text(&text, None, font_size as u16, 1.).width;
+            let font_size = if width > room {
+                font_size * room / width
+            } else {
+                font_size
+            };
+            draw_text(
+                &text,
+                piece_left - 0.75 * tile_size,
+                baseline,
+                font_size,
+                theme.text,
+            );
+        }
+    }
+
+    if your_turn && !game_state.players[game_state.controller()].is_bot {
+        let font_size = 0.025 * screen_height;
+        for (i, line) in CONTROLS_LEGEND.iter().enumerate() {
+            draw_text(
+                line,
+                piece_left - tile_size,
+                0.6 * screen_height + i as f32 * 1.2 * font_size,
+                font_size,
+                theme.text,
+            );
+        }
+    }
+
+    match view {
+        ViewMode::Player(idx) => {
+            draw_tray(
+                &game_state.players[idx],
+                &tray,
+                &game_state.config.pieces,
+                screen.view_rotation,
+                |piece_id| game_state.may_play(idx, piece_id),
+                look,
+            );
+            // Everyone else's pieces are out on the table too, just smaller and squeezed up.
+            let opponents = opponent_trays(game_state, idx, look.tray_order);
+            for (nth, (opponent, left)) in opponents.iter().enumerate() {
+                let tray = screen.opponent_tray(nth, opponents.len(), left.len());
+                let color = game_state.players[*opponent].color;
+                for (position, &piece_id) in left.iter().enumerate() {
+                    let shape = game_state.config.pieces.shape(piece_id);
+                    let shape = turned_shape(shape, screen.view_rotation);
+                    let at = tray.slot_at(position).point();
+                    draw_piece(shape, color, at, tray.ui_tile_size, true, look);
+                }
+            }
+        }
+        ViewMode::Spectator => {
+            // Everyone's hand, stacked down the left side at a third of the usual size.
+            let small_tile_size = ui_tile_size / 3.;
+            let pieces = &game_state.config.pieces;
+            for (idx, player) in game_state.players.iter().enumerate() {
+                let tray = TrayLayout {
+                    top_left: vec2(
+                        0.02 * screen_width,
+                        board_rect.y + idx as f32 * 12. * small_tile_size,
+                    ),
+                    ui_tile_size: small_tile_size,
+                    columns: TrayLayout::max_columns(pieces.len()),
+                    pieces: pieces.len(),
+                    order: look.tray_order,
+                };
+                draw_tray(player, &tray, pieces, screen.view_rotation, |_| true, look);
+            }
+        }
+    }
+}
+
+/// What everyone but `viewer` has left, in `order`, skipping whatever they've played. Their
+/// pieces are right out on the table in a real game, so anyone can look.
+fn opponent_trays(
+    game_state: &GameState,
+    viewer: usize,
+    order: TrayOrder,
+) -> Vec<(usize, Vec<logic::PieceID>)> {
+    let arrangement = order.arrangement(&game_state.config.pieces);
+    (0..game_state.players.len())
+        .filter(|&idx| idx != viewer)
+        .map(|idx| {
+            let remaining = &game_state.players[idx].remaining_pieces;
+            let left = arrangement
+                .iter()
+                .copied()
+                .filter(|&piece_id| remaining.contains(piece_id))
+                .collect();
+            (idx, left)
+        })
+        .collect()
+}
+
+/// Draws the pieces `player` has left out of `pieces`, each in its slot in `tray` and turned
+/// `quarter_turns` like the board. Any the house rules won't let them play right now are
+/// greyed out.
+fn draw_tray(
+    player: &Player,
+    tray: &TrayLayout,
+    pieces: &PieceSet,
+    quarter_turns: u8,
+    playable: impl Fn(logic::PieceID) -> bool,
+    look: Look,
+) {
+    // making the "executive" decision not to use the ui library (at least not for this)
+    let in_hand = tray
+        .slots(pieces)
+        .filter(|&(piece_id, _)| player.remaining_pieces.contains(piece_id));
+    for (piece_id, slot) in in_hand {
+        let shape = turned_shape(pieces.shape(piece_id), quarter_turns);
+        let at = slot.point();
+        draw_piece(shape, player.color, at, tray.ui_tile_size, true, look);
+        if !playable(piece_id) {
+            let grey = Color {
+                a: 0.6,
+                ..look.theme.background
+            };
+            for (row, col) in piece::cells(&shape) {
+                let cell = at + vec2(col as f32, row as f32) * tray.ui_tile_size;
+                draw_rectangle(cell.x, cell.y, tray.ui_tile_size, tray.ui_tile_size, grey);
+            }
+        }
+    }
+}
+
+/// Stretches of empty cells bigger than this are open board, not pockets.
+const MAX_POCKET_CELLS: usize = 16;
+
+/// The empty cells connected to `cell` (x = column, y = row in the play area) by their edges,
+/// as play area (row, col)s, if there are few enough of them to call it a pocket.
+fn pocket_at(game_state: &GameState, cell: IVec2) -> Option<Vec<(usize, usize)>> {
+    let board = &game_state.board;
+    let wall = game_state.wall_thickness();
+    let start = (cell.y as usize + wall, cell.x as usize + wall);
+    if board[start.0][start.1] != TileColor::Empty {
+        return None;
+    }
+
+    let mut pocket = vec![start];
+    let mut next = 0;
+    while let Some(&(row, col)) = pocket.get(next) {
+        next += 1;
+        // The walls keep this from wandering off the board.
+        for (r, c) in [
+            (row - 1, col),
+            (row + 1, col),
+            (row, col - 1),
+            (row, col + 1),
+        ] {
+            if board[r][c] == TileColor::Empty && !pocket.contains(&(r, c)) {
+                if pocket.len() == MAX_POCKET_CELLS {
+                    return None;
+                }
+                pocket.push((r, c));
+            }
+        }
+    }
+    Some(
+        pocket
+            .into_iter()
+            .map(|(r, c)| (r - wall, c - wall))
+            .collect(),
+    )
+}
+
+/// Lets the current player pick up, turn and aim a piece. Hands back the move once they
+/// put it down, for the session to play.
+fn handle_input(
+    game_state: &mut GameState,
+    placement_hint: &mut Option<IVec2>,
+    pending_placement: &mut Option<Placement>,
+    controls: &Controls,
+    hovered_piece: &mut Option<logic::PieceID>,
+    screen: &ScreenInfo,
+    wheel: &mut WheelNotches,
+) -> Option<Move> {
+    let Controls { placing, keys, pad } = controls;
+    let fired = |action| keys.fired(action, is_key_pressed) || pad.fired(action);
+    // click detection rects
+    let board_rect = screen.play_area_rect();
+    let held = (game_state.selected_piece, game_state.piece_buffer);
+
+    use piece::{FlipDir, RotateDir};
+
+    // Flip pieces. With the board turned on its side, side to side on screen is up and down
+    // on the board.
+    let (across, up_down) = if screen.view_rotation % 2 == 1 {
+        (FlipDir::Vertical, FlipDir::Horizontal)
+    } else {
+        (FlipDir::Horizontal, FlipDir::Vertical)
+    };
+    if fired(Action::FlipHorizontal) || is_mouse_button_pressed(MouseButton::Right) {
+        apply_transform(game_state, placement_hint, |shape| {
+            piece::flip(shape, across)
+        });
+    }
+
+    if fired(Action::FlipVertical) {
+        apply_transform(game_state, placement_hint, |shape| {
+            piece::flip(shape, up_down)
+        });
+    }
+
+    // Rotate pieces
+    if fired(Action::RotateLeft) {
+        apply_transform(game_state, placement_hint, |shape| {
+            piece::rotate(shape, RotateDir::Left)
+        });
+    }
+
+    if fired(Action::RotateRight) {
+        apply_transform(game_state, placement_hint, |shape| {
+            piece::rotate(shape, RotateDir::Right)
+        });
+    }
+
+    // Scrolling up turns the piece left, like PageUp. With Ctrl held it zooms instead.
+    let (_, wheel_y) = mouse_wheel();
+    let wheel_y = if ctrl_down() { 0. } else { wheel_y };
+    if let Some(dir) = wheel.turn(wheel_y, get_time()) {
+        apply_transform(game_state, placement_hint, |shape| {
+            piece::rotate(shape, dir)
+        });
+    }
+
+    // Pick a piece from the tray without the mouse.
+    for (digit, key) in DIGIT_KEYS.into_iter().enumerate() {
+        if is_key_pressed(key) {
+            if let Some(piece_id) = piece_for_digit(game_state, digit) {
+                game_state.select_piece(Some(piece_id));
+            }
+        }
+    }
+
+    // Flip through the tray one piece at a time, like a controller's shoulder buttons do.
+    // Shift turns either way around, so Shift+Tab goes back.
+    let shift = is_key_down(KeyCode::LeftShift) || is_key_down(KeyCode::RightShift);
+    let forward = if fired(Action::NextPiece) {
+        Some(!shift)
+    } else if fired(Action::PrevPiece) {
+        Some(shift)
+    } else {
+        None
+    };
+    if let Some(piece_id) = forward.and_then(|forward| cycle_piece(game_state, forward)) {
+        game_state.select_piece(Some(piece_id));
+    }
+
+    // Nudge the cursor a cell at a time, so nobody *needs* to aim with the mouse.
+    let nudges = [
+        (KeyCode::Left, ivec2(-1, 0)),
+        (KeyCode::Right, ivec2(1, 0)),
+        (KeyCode::Up, ivec2(0, -1)),
+        (KeyCode::Down, ivec2(0, 1)),
+    ];
+    let nudge = nudges
+        .into_iter()
+        .filter(|&(key, _)| is_key_pressed(key))
+        .fold(pad.nudge, |sum, (_, nudge)| sum + nudge);
+    if nudge != IVec2::ZERO {
+        let last_cell = game_state.play_area_dim() as i32 - 1;
+        let step = screen.unturned_step(nudge);
+        game_state.cursor = (game_state.cursor + step).clamp(IVec2::ZERO, IVec2::splat(last_cell));
+    }
+
+    let mouse_pos = Vec2::from(mouse_position());
+    // Only follow the mouse when it moves, otherwise it'd undo every nudge.
+    if let Some(cell) = screen.cell_at(mouse_pos) {
+        if mouse_delta_position() != Vec2::ZERO {
+            game_state.cursor = cell;
+        }
+    }
+
+    refresh_hint(game_state, placement_hint);
+    let dragging = *placing == Placing::Drag;
+    // Turning the piece or picking up another one lets go of the spot they clicked. A piece
+    // being dragged can be turned on the way.
+    if (game_state.selected_piece, game_state.piece_buffer) != held && !dragging {
+        *pending_placement = None;
+    }
+    // A spot they've clicked once stays put until they click again.
+    let under_pointer = *placement_hint;
+    if let Some(pending) = pending_placement.filter(|_| !dragging) {
+        *placement_hint = Some(pending.corner);
+    }
+
+    // Let them look at a piece before picking it up.
+    let remaining = &game_state.current_player().remaining_pieces;
+    *hovered_piece = screen
+        .tray_piece_at(mouse_pos, &game_state.config.pieces)
+        .filter(|&piece_id| remaining.contains(piece_id));
+
+    // Zoomed in, the board can reach under the tray, which gets first dibs on clicks.
+    let over_tray = screen.avail_pieces_rect().contains(mouse_pos);
+    let clicked_board =
+        is_mouse_button_pressed(MouseButton::Left) && board_rect.contains(mouse_pos) && !over_tray;
+    if dragging && game_state.selected_piece.is_some() {
+        // Pressing grabs the piece, and letting go puts it down, if it fits there.
+        if clicked_board {
+            *pending_placement = Some(Placement {
+                corner: game_state.cursor,
+                confirmed: false,
+            });
+            return None;
+        }
+        if pending_placement.is_some() && is_mouse_button_released(MouseButton::Left) {
+            *pending_placement = None;
+            return under_pointer.and_then(|corner| {
+                *placement_hint = None;
+                game_state.held_move(corner)
+            });
+        }
+    }
+    if clicked_board || fired(Action::Commit) {
+        // put a piece on the board -- we know where, since we already validated!
+        *pending_placement = placing.click(*pending_placement, under_pointer);
+        if let Some(Placement {
+            corner,
+            confirmed: true,
+        }) = *pending_placement
+        {
+            *pending_placement = None;
+            *placement_hint = None;
+            return game_state.held_move(corner);
+        }
+    } else if is_mouse_button_pressed(MouseButton::Left) {
+        *pending_placement = None;
+        if over_tray {
+            // Change selected piece.
+            if let Some(piece_id) = *hovered_piece {
+                game_state.select_piece(Some(piece_id));
+            }
+        } else {
+            game_state.select_piece(None);
+        }
+    } else if fired(Action::Cancel) {
+        *pending_placement = None;
+        game_state.select_piece(None);
+    }
+    None
+}
+
+/// The longest the hard bot gets to weigh its options each move, in seconds, so it can't
+/// stall a frame.
+const HARD_BOT_THINKING_TIME: f64 = 0.01;
+
+/// Has the bot whose turn it is make its move.
+fn play_bot_move(session: &mut impl Session, rng: &RandGenerator) {
+    let game_state = session.current_state();
+    let difficulty = game_state.players[game_state.controller()].difficulty;
+    let choice = match difficulty {
+        Difficulty::Hard => {
+            ai::choose_move_timed(game_state, difficulty, HARD_BOT_THINKING_TIME, get_time)
+        }
+        _ => ai::choose_move_with(game_state, difficulty, rng),
+    };
+    if let Some(mv) = choice {
+        // The bot only picks legal moves, so this can't fail.
+        let _ = session.submit_move(mv);
+    }
+}
+
+/// How often the bots move when they're the only ones playing.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum BotSpeed {
+    /// A move a frame.
+    #[default]
+    EveryFrame,
+    /// As many moves a frame as `BotPace::INSTANT_TURNS`, for getting through balance
+    /// tests quickly.
+    Instant,
+    /// A move every so many seconds, for following along.
+    Every(f64),
+}
+
+impl BotSpeed {
+    fn label(self) -> String {
+        match self {
+            Self::EveryFrame => "normal".to_string(),
+            Self::Instant => "instant".to_string(),
+            Self::Every(secs) => format!("{secs}s a move"),
+        }
+    }
+
+    /// The next speed, for the button that cycles through them.
+    fn next(self) -> Self {
+        match self {
+            Self::EveryFrame => Self::Instant,
+            Self::Instant => Self::Every(0.5),
+            Self::Every(secs) if secs < 2. => Self::Every(2.),
+            Self::Every(_) => Self::EveryFrame,
+        }
+    }
+}
+
+/// Decides when bots move in a game with nobody else playing. Games with people in them
+/// don't go through this, so the bots there still take their turns right away.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+struct BotPace {
+    speed: BotSpeed,
+    /// Whether the bots wait for someone to hit "Step" before each move.
+    paused: bool,
+    /// When the bots last moved, in seconds on `get_time`'s clock.
+    last_turn: f64,
+}
+
+impl BotPace {
+    /// How many turns `Instant` plays a frame. Enough to finish a game in well under a
+    /// second, without holding up the frame on a slow bot.
+    const INSTANT_TURNS: usize = 8;
+
+    /// How many turns the bots should take this frame, at `now`. `step` is whether someone
+    /// asked for one more move, which only matters while paused.
+    fn turns_due(&self, now: f64, step: bool) -> usize {
+        if self.paused {
+            return step as usize;
+        }
+        match self.speed {
+            BotSpeed::EveryFrame => 1,
+            BotSpeed::Instant => Self::INSTANT_TURNS,
+            BotSpeed::Every(secs) => (now - self.last_turn >= secs) as usize,
+        }
+    }
+}
+
+/// Lets everyone know the current player is stuck, for a couple of seconds.
+fn pass_message(game_state: &GameState) -> (String, f64) {
+    let name = game_state.current_player().display_name();
+    (format!("{name} has no moves, passing"), get_time() + 2.)
+}
+
+/// Turns or flips the piece in hand, then checks whether it still fits under the cursor.
+fn apply_transform(
+    game_state: &mut GameState,
+    placement_hint: &mut Option<IVec2>,
+    f: impl Fn(piece::Shape) -> piece::Shape,
+) {
+    game_state.piece_buffer = f(game_state.piece_buffer);
+    // Keep the tile that was under the pointer there.
+    game_state.grab = follow_cell(game_state.grab, &f);
+    refresh_hint(game_state, placement_hint);
+    if placement_hint.is_some() || game_state.selected_piece.is_none() {
+        return;
+    }
+
+    // That doesn't fit, but the pointer may still be able to stay on the piece if it holds
+    // on to a different tile.
+    let grabbed = game_state.grab;
+    let tiles: SmallVec<[IVec2; 5]> = piece::cells(&game_state.piece_buffer)
+        .map(|(r, c)| ivec2(c as i32, r as i32))
+        .collect();
+    for tile in tiles {
+        game_state.grab = tile;
+        refresh_hint(game_state, placement_hint);
+        if placement_hint.is_some() {
+            return;
+        }
+    }
+    game_state.grab = grabbed;
+}
+
+/// Where `cell` (x = column, y = row) of a piece's 5x5 grid ends up once `transform` turns the
+/// grid, found by turning a grid with only that cell filled in.
+fn follow_cell(cell: IVec2, transform: impl Fn(piece::Shape) -> piece::Shape) -> IVec2 {
+    let mut marker = piece::EMPTY_SHAPE;
+    marker[cell.y as usize].set(cell.x as usize, true);
+    piece::cells(&transform(marker))
+        .next()
+        .map_or(cell, |(r, c)| ivec2(c as i32, r as i32))
+}
+
+/// If the piece in hand can go where the cursor is, mark it as such for the drawing logic.
+fn refresh_hint(game_state: &GameState, placement_hint: &mut Option<IVec2>) {
+    *placement_hint = game_state
+        .selected_piece
+        .and_then(|_| update_suggestion(game_state, game_state.cursor));
+}
+
+/// How the player works the game: which keys do what, and how many clicks put a piece down.
+struct Controls {
+    placing: Placing,
+    keys: KeyBindings,
+    /// What the current player's controller did this frame, if they're using one.
+    pad: PadInput,
+}
+
+/// How many clicks it takes to put a piece down.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+enum Placing {
+    /// Click where it goes and it's down.
+    #[default]
+    OneClick,
+    /// The first click holds the piece there, and a second click on the same spot puts it
+    /// down. Touchpads and touchscreens make stray clicks easy, so this takes two.
+    TwoClicks,
+    /// Press on the board to grab the piece, drag it where it goes, and let go to put it
+    /// down. While it's held it shows green where it'd go down and red where it wouldn't.
+    Drag,
+}
+
+/// A spot the player has clicked to put their piece on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Placement {
+    /// Play area coordinates, like the placement hint.
+    corner: IVec2,
+    /// Whether they've clicked enough times to mean it.
+    confirmed: bool,
+}
+
+impl Placing {
+    /// What a click on the board does to what's `pending`, when the piece in hand would go at
+    /// `hint` (or wouldn't fit at all). Nothing pending afterwards means nothing's happening,
+    /// which is how a click somewhere else calls off the first click.
+    fn click(self, pending: Option<Placement>, hint: Option<IVec2>) -> Option<Placement> {
+        let corner = hint?;
+        let confirmed = match (self, pending) {
+            // Dragging only clicks by way of the commit key.
+            (Self::OneClick | Self::Drag, _) => true,
+            (Self::TwoClicks, None) => false,
+            (Self::TwoClicks, Some(pending)) if pending.corner == corner => true,
+            (Self::TwoClicks, Some(_)) => return None,
+        };
+        Some(Placement { corner, confirmed })
+    }
+
+    /// The other mode, for the toggle on the setup screen.
+    fn next(self) -> Self {
+        match self {
+            Self::OneClick => Self::TwoClicks,
+            Self::TwoClicks => Self::Drag,
+            Self::Drag => Self::OneClick,
+        }
+    }
+}
+
+/// How long to ignore the mouse wheel after it turns a piece, in seconds.
+const WHEEL_DEBOUNCE_SECS: f64 = 0.1;
+
+/// Turns mouse wheel movement into one rotation per notch. Some mice and touchpads spread
+/// a single notch over several frames, so after each turn the wheel is ignored for a moment.
+#[derive(Debug, Default)]
+struct WheelNotches {
+    /// When the wheel last turned a piece, in seconds.
+    last_turn: Option<f64>,
+}
+
+impl WheelNotches {
+    fn turn(&mut self, wheel_y: f32, now: f64) -> Option<piece::RotateDir> {
+        let cooling_down = self
+            .last_turn
+            .is_some_and(|last_turn| now - last_turn < WHEEL_DEBOUNCE_SECS);
+        if wheel_y == 0. || cooling_down {
+            return None;
+        }
+
+        self.last_turn = Some(now);
+        Some(if wheel_y > 0. {
+            piece::RotateDir::Left
+        } else {
+            piece::RotateDir::Right
+        })
+    }
+}
+
+/// What every control does, shown while a person is taking their turn.
+const CONTROLS_LEGEND: [&str; 5] = [
+    "Wheel, Q/E: rotate",
+    "Right click, A/D: flip sideways",
+    "W/S: flip upside down",
+    "1-9, 0: pick a piece",
+    "Arrows: move, Enter: place",
+];
+
+/// Number keys 1 through 9, then 0 for the tenth.
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Key1,
+    KeyCode::Key2,
+    KeyCode::Key3,
+    KeyCode::Key4,
+    KeyCode::Key5,
+    KeyCode::Key6,
+    KeyCode::Key7,
+    KeyCode::Key8,
+    KeyCode::Key9,
+    KeyCode::Key0,
+];
+
+/// Which piece the `digit`th number key (counting from 0) should pick up. The keys count
+/// through the current player's remaining pieces in tray order. Since there are more pieces
+/// than keys, pressing the same key again skips ahead ten pieces, wrapping back around.
+fn piece_for_digit(game_state: &GameState, digit: usize) -> Option<logic::PieceID> {
+    let candidates: SmallVec<[_; 3]> = game_state
+        .current_player()
+        .remaining_pieces
+        .iter()
+        .skip(digit)
+        .step_by(DIGIT_KEYS.len())
+        .collect();
+    let next = candidates
+        .iter()
+        .position(|&id| Some(id) == game_state.selected_piece)
+        .map_or(0, |i| (i + 1) % candidates.len());
+    candidates.get(next).copied()
+}
+
+/// The piece after (or before) the one the current player is holding, among the ones they still
+/// have, wrapping around at the ends. Starts from the first (or last) piece if they aren't
+/// holding anything.
+fn cycle_piece(game_state: &GameState, forward: bool) -> Option<logic::PieceID> {
+    let remaining = &game_state.current_player().remaining_pieces;
+    let held = game_state.selected_piece;
+    let pieces = game_state.config.pieces.len();
+    if forward {
+        let after = held.map_or(0, |id| id + 1);
+        (after..pieces)
+            .chain(0..after)
+            .find(|&id| remaining.contains(id))
+    } else {
+        let before = held.unwrap_or(pieces);
+        (0..before)
+            .rev()
+            .chain((before..pieces).rev())
+            .find(|&id| remaining.contains(id))
+    }
+}
+
+/// Given the center position and size of a UI element, return the position
+/// of its topleft corner.
+fn centered_at(center: Vec2, dims: Vec2) -> Vec2 {
+    center - 0.5 * dims
+}
+
+fn medium_ui_button_dims() -> Vec2 {
+    vec2(screen_height() / 4., screen_height() / 16.)
+}
+
+fn medium_ui_button_padding() -> f32 {
+    1. / 64. * screen_height()
+}
+
+/// The preview's labels for `piece_id` turned to `orientation`: its name and size, like
+/// "L5, 5 tiles", then how it's turned, like "rot 2, flipped".
+fn preview_labels(
+    pieces: &PieceSet,
+    piece_id: logic::PieceID,
+    orientation: Orientation,
+) -> (String, String) {
+    let size = pieces.size(piece_id);
+    let tiles = if size == 1 { "tile" } else { "tiles" };
+    let flipped = if orientation.flipped { ", flipped" } else { "" };
+    (
+        format!("{}, {size} {tiles}", pieces.name(piece_id)),
+        format!("rot {}{flipped}", orientation.rotation),
+    )
+}
+
+/// Crosses out the play area cells in `cells` (see `GameState::dead_cells`), faintly, so the
+/// board doesn't look like it has more room left than it does.
+fn draw_dead_cells(cells: &[(usize, usize)], screen: &ScreenInfo, color: Color) {
+    let color = Color { a: 0.25, ..color };
+    for &(row, col) in cells {
+        let cell = screen.cell_rect(ivec2(col as i32, row as i32));
+        let inset = 0.3 * cell.w;
+        let (left, right) = (cell.x + inset, cell.right() - inset);
+        let (top, bottom) = (cell.y + inset, cell.bottom() - inset);
+        draw_line(left, top, right, bottom, 2., color);
+        draw_line(left, bottom, right, top, 2., color);
+    }
+}
+
+/// A side of a cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Side {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+impl Side {
+    /// Which side this is after `quarter_turns` clockwise.
+    fn turned(self, quarter_turns: u8) -> Self {
+        (0..quarter_turns % 4).fold(self, |side, _| match side {
+            Self::Top => Self::Right,
+            Self::Right => Self::Bottom,
+            Self::Bottom => Self::Left,
+            Self::Left => Self::Top,
+        })
+    }
+}
+
+/// The sides of `cells` that don't face another one of `cells`. For a piece's cells, that's
+/// the outline around the whole piece.
+fn outline_edges(cells: &[(usize, usize)]) -> Vec<((usize, usize), Side)> {
+    let mut edges = Vec::new();
+    for &(row, col) in cells {
+        let neighbors = [
+            (Side::Top, row.checked_sub(1).map(|r| (r, col))),
+            (Side::Bottom, Some((row + 1, col))),
+            (Side::Left, col.checked_sub(1).map(|c| (row, c))),
+            (Side::Right, Some((row, col + 1))),
+        ];
+        for (side, neighbor) in neighbors {
+            if !neighbor.is_some_and(|cell| cells.contains(&cell)) {
+                edges.push(((row, col), side));
+            }
+        }
+    }
+    edges
+}
+
+/// Traces just inside the edge of every piece on the board, so each one stands apart from
+/// any others of its color it's touching.
+fn draw_piece_outlines(game_state: &GameState, screen: &ScreenInfo, theme: Theme) {
+    let wall = game_state.wall_thickness();
+    let inset = theme.grid_thickness;
+    for (_, mv) in game_state.moves() {
+        let cells: Vec<_> = mv
+            .board_cells(&game_state.config.pieces)
+            .map(|(row, col)| (row - wall, col - wall))
+            .collect();
+        let edges = outline_edges(&cells);
+        // Where the outline turns a corner, stop at the corner. Where it carries on into
+        // the next cell, run a little past the edge to meet it, round inside corners too.
+        // Sides here are as they're drawn, so they get turned back to look them up.
+        let back = 4 - screen.view_rotation;
+        let end = |cell, side: Side| {
+            if edges.contains(&(cell, side.turned(back))) {
+                inset
+            } else {
+                -inset
+            }
+        };
+        for &(cell, side) in &edges {
+            let (row, col) = cell;
+            let rect = screen.cell_rect(ivec2(col as i32, row as i32));
+            let left = rect.x + end(cell, Side::Left);
+            let right = rect.right() - end(cell, Side::Right);
+            let top = rect.y + end(cell, Side::Top);
+            let bottom = rect.bottom() - end(cell, Side::Bottom);
+            let (x1, y1, x2, y2) = match side.turned(screen.view_rotation) {
+                Side::Top => (left, rect.y + inset, right, rect.y + inset),
+                Side::Bottom => (left, rect.bottom() - inset, right, rect.bottom() - inset),
+                Side::Left => (rect.x + inset, top, rect.x + inset, bottom),
+                Side::Right => (rect.right() - inset, top, rect.right() - inset, bottom),
+            };
+            draw_line(x1, y1, x2, y2, theme.grid_thickness, theme.grid);
+        }
+    }
+}
+
+/// Where the `n`th line of a grid starting at `origin`, `step` pixels apart, goes, rounded to
+/// a whole pixel. Cells drawn between neighboring lines then share their edges exactly, even
+/// when `step` isn't a whole number of pixels, instead of leaving hairline seams.
+fn snap_to_pixel(origin: f32, step: f32, n: i32) -> f32 {
+    (origin + n as f32 * step).round()
+}
+
+/// Where `cell` goes on a `board_dim` square grid turned `quarter_turns` clockwise.
+fn turn_cell(cell: IVec2, board_dim: usize, quarter_turns: u8) -> IVec2 {
+    let last = board_dim as i32 - 1;
+    (0..quarter_turns % 4).fold(cell, |cell, _| ivec2(last - cell.y, cell.x))
+}
+
+/// `shape` the way it looks on a board turned `quarter_turns` clockwise, in the same 5x5 grid.
+fn turned_shape(shape: piece::Shape, quarter_turns: u8) -> piece::Shape {
+    (0..quarter_turns % 4).fold(shape, |shape, _| {
+        piece::rotate(shape, piece::RotateDir::Right)
+    })
+}
+
+/// Who the board faces with `Look::turn_board` on: whoever's deciding this turn, if they're a
+/// person at this screen, and otherwise whoever it faced `last`, so it doesn't spin around for
+/// bots or people playing from somewhere else. Before anyone here has had a turn, that's the
+/// first person here.
+fn facing_seat(
+    game_state: &GameState,
+    last: Option<usize>,
+    is_remote: impl Fn(usize) -> bool,
+) -> Option<usize> {
+    let here = |idx: usize| {
+        let player = &game_state.players[idx];
+        !player.is_bot && !player.is_neutral && !is_remote(idx)
+    };
+    let controller = game_state.controller();
+    if here(controller) {
+        return Some(controller);
+    }
+    last.or_else(|| (0..game_state.players.len()).find(|&idx| here(idx)))
+}
+
+/// How far to turn the board so `player_idx`'s start corner is at the bottom left, or as
+/// near as it gets for start points out in the middle of the board.
+fn seat_rotation(game_state: &GameState, player_idx: usize) -> u8 {
+    let (row, col) = game_state.start_corner(player_idx);
+    let board_dim = game_state.config.board_dim;
+    let corner = ivec2(col as i32, row as i32);
+    (0..4)
+        .min_by_key(|&turns| {
+            let spot = turn_cell(corner, board_dim, turns);
+            spot.x + (board_dim as i32 - 1 - spot.y)
+        })
+        .expect("There are four ways to turn the board.")
+}
+
+/// What column `col` of the play area is called in the labels around the board: A to Z,
+/// then AA, AB and so on, like a spreadsheet, for boards wider than the alphabet.
+fn column_label(col: usize) -> String {
+    let mut label = String::new();
+    let mut n = col + 1;
+    while n > 0 {
+        n -= 1;
+        label.insert(0, char::from(b'A' + (n % 26) as u8));
+        n /= 26;
+    }
+    label
+}
+
+/// What row `row` of the play area is called in the labels around the board. They count
+/// from 1, the way people do, even though notation counts from 0.
+fn row_label(row: usize) -> String {
+    (row + 1).to_string()
+}
+
+/// Letters the columns along the top and bottom of the play area and numbers the rows down
+/// both sides. They go in the board's border, so they stay clear of the tray.
+fn draw_coordinates(screen: &ScreenInfo, board_dim: usize, color: Color) {
+    let board_rect = screen.board_rect();
+    let play_area_rect = screen.play_area_rect();
+    let border = play_area_rect.y - board_rect.y;
+    let font_size = (0.6 * screen.tile_size).min(0.8 * border);
+    let label_at = |text: &str, center: Vec2| {
+        let dims = measure_text(text, None, font_size as u16, 1.);
+        let x = center.x - 0.5 * dims.width;
+        let y = center.y - 0.5 * dims.height + dims.offset_y;
+        draw_text(text, x, y, font_size, color);
+    };
+    // Whatever runs along the line of spots from `a` to `b` on screen. Turned a quarter,
+    // that's rows across the top and columns down the sides.
+    let label = |a: IVec2, b: IVec2| {
+        let (a, b) = (screen.unturned(a), screen.unturned(b));
+        if a.x == b.x {
+            column_label(a.x as usize)
+        } else {
+            row_label(a.y as usize)
+        }
+    };
+    for i in 0..board_dim as i32 {
+        let spot = screen.spot_rect(ivec2(i, i));
+        let across = label(ivec2(i, 0), ivec2(i, 1));
+        let down = label(ivec2(0, i), ivec2(1, i));
+        label_at(&across, vec2(spot.center().x, board_rect.y + 0.5 * border));
+        label_at(
+            &across,
+            vec2(spot.center().x, board_rect.bottom() - 0.5 * border),
+        );
+        label_at(&down, vec2(board_rect.x + 0.5 * border, spot.center().y));
+        label_at(
+            &down,
+            vec2(board_rect.right() - 0.5 * border, spot.center().y),
+        );
+    }
+}
+
+/// What a dragged piece is tinted with when the pointer's on `cursor`: green if letting go
+/// there puts it down, red if it doesn't (or whatever `palette` uses instead).
+fn drag_tint(game_state: &GameState, cursor: IVec2, palette: Palette) -> Color {
+    palette.fit_tint(update_suggestion(game_state, cursor).is_some())
+}
+
+/// Draws the piece being dragged under the pointer, tinted by `drag_tint`. Off the edge of the
+/// board, there's nowhere to draw it.
+fn draw_dragged_piece(game_state: &GameState, screen: &ScreenInfo, palette: Palette) {
+    let held_at = game_state.check_bounds_and_recenter(game_state.held_center(game_state.cursor));
+    let Some(corner) = held_at else {
+        return;
+    };
+    let tint = Color {
+        a: 0.5,
+        ..drag_tint(game_state, game_state.cursor, palette)
+    };
+    for (dr, dc) in piece::cells(&game_state.piece_buffer) {
+        let Rect { x, y, w, h } = screen.cell_rect(corner + ivec2(dc as i32, dr as i32));
+        draw_rectangle(x, y, w, h, tint);
+    }
+}
+
+/// Updates the coordinates for the potential next move.
+fn update_suggestion(game_state: &GameState, proposed: IVec2) -> Option<IVec2> {
+    // `valid_move` counts the wall, and the corner we have doesn't.
+    let wall = IVec2::splat(game_state.wall_thickness() as i32);
+    game_state
+        .check_bounds_and_recenter(game_state.held_center(proposed))
+        .filter(|&corner| game_state.valid_move(corner + wall))
+}
+
+fn draw_piece(
+    shape: piece::Shape,
+    color: TileColor,
+    at: Vec2,
+    tile_size: f32,
+    with_borders: bool,
+    look: Look,
+) {
+    let Look {
+        palette,
+        tile_style,
+        theme,
+        ..
+    } = look;
+    // piece preview
+    for (r_ind, row) in shape.iter().enumerate() {
+        for tile in row.iter_ones() {
+            let cell = Rect::new(
+                tile as f32 * tile_size + at.x,
+                r_ind as f32 * tile_size + at.y,
                 tile_size,
                 tile_size,
-                game_state.board[row + 1][col + 1].into(),
             );
-        }
-    }
+            tile::draw_tile(tile_style, cell, palette.color(color));
+            if let Some(symbol) = palette.symbol(color) {
+                draw_symbol(
+                    symbol,
+                    at + vec2(tile as f32, r_ind as f32) * tile_size,
+                    tile_size,
+                );
+            }
 
-    if let Some(IVec2 { x: l_col, y: l_row }) = *placement_hint {
-        // Okay, looks like we have a placeable piece -- let's draw where it could be.
-        for (dr, r) in game_state.piece_buffer.iter().enumerate() {
-            for dc in r.iter_ones() {
-                let t_row = l_row + dr as i32;
-                let t_col = l_col + dc as i32;
-                draw_rectangle(
-                    play_area_top_left.x + t_col as f32 * tile_size,
-                    play_area_top_left.y + t_row as f32 * tile_size,
+            // Rounded tiles already show where one ends and the next begins.
+            if with_borders && tile_style == TileStyle::Flat {
+                draw_rectangle_lines(
+                    tile as f32 * tile_size + at.x,
+                    r_ind as f32 * tile_size + at.y,
                     tile_size,
                     tile_size,
-                    game_state.current_player().color.highlight_color(),
+                    theme.grid_thickness,
+                    theme.grid,
                 );
             }
         }
     }
+}
 
-    // Board Border
-    draw_rectangle_lines(
-        board_top_left.x,
-        board_top_left.y,
-        screen_height() * BOARD_SIZE,
-        screen_height() * BOARD_SIZE,
-        4.,
-        BLACK,
-    );
+/// Marks the tile at `top_left` with `symbol`, so colors can be told apart by shape.
+fn draw_symbol(symbol: Symbol, top_left: Vec2, tile_size: f32) {
+    let center = top_left + Vec2::splat(0.5 * tile_size);
+    let radius = 0.25 * tile_size;
+    let thickness = (0.08 * tile_size).max(1.);
+    let ink = Color::new(0., 0., 0., 0.6);
+    match symbol {
+        Symbol::Circle => draw_circle_lines(center.x, center.y, radius, thickness, ink),
+        // Point up.
+        Symbol::Triangle => draw_poly_lines(center.x, center.y, 3, radius, -90., thickness, ink),
+        Symbol::Diamond => draw_poly_lines(center.x, center.y, 4, radius, 0., thickness, ink),
+        Symbol::Cross => {
+            let (a, b) = (center - Vec2::splat(radius), center + Vec2::splat(radius));
+            draw_line(a.x, a.y, b.x, b.y, thickness, ink);
+            draw_line(a.x, b.y, b.x, a.y, thickness, ink);
+        }
+    }
+}
 
-    // Play area border
-    draw_rectangle_lines(
-        play_area_top_left.x,
-        play_area_top_left.y,
-        screen_height() * 0.9 * BOARD_SIZE,
-        screen_height() * 0.9 * BOARD_SIZE,
-        4.,
-        BLACK,
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    // grid time
-    // vertical lines:
-    for i in 1..20 {
-        let line_x = play_area_top_left.x + i as f32 * tile_size;
-        draw_line(
-            line_x,
-            play_area_top_left.y,
-            line_x,
-            play_area_top_left.y + 20. * tile_size,
-            2.,
-            BLACK,
-        );
+    /// Where `piece_id` goes in `tray`, the same way clicking finds it.
+    fn tray_slot(tray: &TrayLayout, piece_id: logic::PieceID, pieces: &PieceSet) -> Rect {
+        tray.slots(pieces)
+            .find(|&(id, _)| id == piece_id)
+            .map(|(_, slot)| slot)
+            .expect("Every piece in the set has a slot.")
     }
 
-    // horizontal lines:
-    for i in 1..20 {
-        let line_y = play_area_top_left.y + i as f32 * tile_size;
-        draw_line(
-            play_area_top_left.x,
-            line_y,
-            play_area_top_left.x + 20. * tile_size,
-            line_y,
-            2.,
-            BLACK,
-        );
+    #[test]
+    fn number_keys_cycle_through_pieces() {
+        let mut game_state = GameState::new(2);
+        // "1" picks up the first piece, then every tenth one after it.
+        for expected in [0, 10, 20, 0] {
+            let piece_id = piece_for_digit(&game_state, 0);
+            assert_eq!(piece_id, Some(expected));
+            game_state.select_piece(piece_id);
+        }
+
+        // Keys count pieces the player still has, not piece IDs.
+        game_state.players[0].remaining_pieces.remove(0);
+        assert_eq!(piece_for_digit(&game_state, 0), Some(1));
+        // Only 20 left, so "0" has two choices and nothing past them.
+        assert_eq!(piece_for_digit(&game_state, 9), Some(10));
     }
 
-    let player = &game_state.players[game_state.current_player];
-    let piece_left = 0.05 * screen_width();
-    let piece_top = 0.35 * screen_height();
-    if game_state.selected_piece.is_some() {
-        // piece preview border
-        draw_rectangle(
-            piece_left - tile_size,
-            piece_top - tile_size,
-            7. * tile_size,
-            7. * tile_size,
-            GRAY,
+    #[test]
+    fn standings_sort_and_share_ranks() {
+        let mut game_state = GameState::new(4);
+        let only = |piece_id| std::iter::once(piece_id).collect();
+        // Blue finished with the dot, Red finished with something else, and Yellow and
+        // Green each got stuck holding the dot.
+        game_state.players[0].remaining_pieces.clear();
+        game_state.players[0].last_piece = Some(0);
+        game_state.players[1].remaining_pieces = only(0);
+        game_state.players[2].remaining_pieces.clear();
+        game_state.players[2].last_piece = Some(5);
+        game_state.players[3].remaining_pieces = only(0);
+
+        let standings = standings(&game_state);
+        let summary: Vec<_> = standings
+            .iter()
+            .map(|row| (row.rank, row.tied, row.color, row.score))
+            .collect();
+        assert_eq!(
+            summary,
+            [
+                (1, false, TileColor::Blue, 20),
+                (2, false, TileColor::Red, 15),
+                (3, true, TileColor::Yellow, -1),
+                (3, true, TileColor::Green, -1),
+            ]
         );
+        assert!(standings[0].all_pieces && standings[0].dot_last);
+        assert!(standings[1].all_pieces && !standings[1].dot_last);
+        assert!(!standings[2].all_pieces && !standings[2].dot_last);
+        assert_eq!(standings[3].pieces_left, 1);
+    }
 
-        draw_rectangle_lines(
-            piece_left - tile_size,
-            piece_top - tile_size,
-            7. * tile_size,
-            7. * tile_size,
-            4.,
-            BLACK,
+    #[test]
+    fn standings_follow_the_score_mid_game() {
+        let mut game_state = GameState::new(3);
+        // Blue opens with the dot, and Yellow and Red with something bigger each.
+        for tiles in [1, 5, 4] {
+            let mv = game_state
+                .enumerate_legal_moves()
+                .into_iter()
+                .find(|mv| mv.tile_count(&game_state.config.pieces) == tiles)
+                .unwrap();
+            game_state.apply_move(&mv);
+        }
+        game_state.forfeit(2);
+
+        let rows = standings(&game_state);
+        let summary: Vec<_> = rows
+            .iter()
+            .map(|row| (row.rank, row.color, row.done))
+            .collect();
+        // Red's still ahead of Blue after giving up, but it's not going anywhere.
+        assert_eq!(
+            summary,
+            [
+                (1, TileColor::Yellow, false),
+                (2, TileColor::Red, true),
+                (3, TileColor::Blue, false),
+            ]
         );
+        for row in &rows {
+            let idx = game_state
+                .players
+                .iter()
+                .position(|p| p.color == row.color)
+                .unwrap();
+            assert_eq!(row.score, game_state.score(idx));
+        }
+    }
 
-        // Piece preview
-        draw_piece(
-            game_state.piece_buffer,
-            player.color,
-            vec2(piece_left, piece_top),
-            tile_size,
-            true,
+    #[test]
+    fn tab_cycles_through_remaining_pieces() {
+        let mut game_state = GameState::new(2);
+        assert_eq!(cycle_piece(&game_state, true), Some(0));
+        assert_eq!(cycle_piece(&game_state, false), Some(20));
+
+        // Pieces already on the board get skipped, both ways.
+        let remaining = &mut game_state.players[0].remaining_pieces;
+        for used in [4, 5, 19, 20] {
+            remaining.remove(used);
+        }
+        game_state.select_piece(Some(3));
+        assert_eq!(cycle_piece(&game_state, true), Some(6));
+        game_state.select_piece(Some(6));
+        assert_eq!(cycle_piece(&game_state, false), Some(3));
+
+        // And it wraps around at the ends.
+        game_state.select_piece(Some(18));
+        assert_eq!(cycle_piece(&game_state, true), Some(0));
+        game_state.select_piece(Some(0));
+        assert_eq!(cycle_piece(&game_state, false), Some(18));
+    }
+
+    #[test]
+    fn placed_tiles_fade_in() {
+        let mut game_state = GameState::new(2);
+        let mut fades = TileFades::new(&game_state.board, true);
+        game_state.select_piece(Some(0));
+        assert!(game_state.try_advance_turn(19, 19));
+
+        fades.update(&game_state.board, 10.);
+        assert_eq!(fades.progress(20, 20, 10.), 0.);
+        assert!((fades.progress(20, 20, 10. + FADE_IN_SECS / 2.) - 0.5).abs() < 1e-6);
+        // Tiles nobody touched are already there.
+        assert_eq!(fades.progress(1, 1, 10.), 1.);
+
+        // Done fading, so there's nothing left to track.
+        fades.update(&game_state.board, 10. + FADE_IN_SECS);
+        assert_eq!(fades.progress(20, 20, 10. + FADE_IN_SECS), 1.);
+        assert!(fades.placed_at.is_empty());
+
+        // Switching to a board of another size doesn't trip over the old one.
+        let duo = GameState::with_players(Player::default_order(2), GameConfig::duo());
+        fades.update(&game_state.board, 20.);
+        fades.update(&duo.board, 20.);
+        assert!(fades.placed_at.is_empty());
+        assert_eq!(fades.last_board, duo.board);
+
+        let mut off = TileFades::new(&GameState::new(2).board, false);
+        off.update(&game_state.board, 10.);
+        assert_eq!(off.progress(20, 20, 10.), 1.);
+    }
+
+    #[test]
+    fn turn_banner_comes_and_goes() {
+        let mut banner = TurnBanner::default();
+        assert_eq!(banner.progress(0.), None);
+
+        banner.announce(1, 5.);
+        assert_eq!(banner.progress(5.), Some((1, 0.)));
+        assert_eq!(banner.progress(5. + TURN_BANNER_SECS / 2.), Some((1, 0.5)));
+        assert_eq!(banner.progress(5. + TURN_BANNER_SECS), None);
+
+        // A turn ending while it's still up starts it over for the new player.
+        banner.announce(0, 5.5);
+        assert_eq!(banner.progress(6.), Some((0, 0.5)));
+    }
+
+    #[test]
+    fn four_turns_make_a_full_circle() {
+        let mut game_state = GameState::new(2);
+        game_state.select_piece(Some(19));
+        let original = game_state.piece_buffer;
+        let mut placement_hint = None;
+        for _ in 0..4 {
+            apply_transform(&mut game_state, &mut placement_hint, |shape| {
+                piece::rotate(shape, piece::RotateDir::Left)
+            });
+        }
+        assert_eq!(game_state.piece_buffer, original);
+
+        // The hint catches up without waiting for the cursor to move.
+        assert_eq!(placement_hint, None);
+        game_state.select_piece(Some(0));
+        game_state.cursor = ivec2(19, 19);
+        apply_transform(&mut game_state, &mut placement_hint, |shape| {
+            piece::rotate(shape, piece::RotateDir::Right)
+        });
+        assert_eq!(placement_hint, Some(ivec2(17, 17)));
+    }
+
+    #[test]
+    fn turning_keeps_the_grabbed_tile_under_the_pointer() {
+        // Held by its foot, an upright L5's foot goes to the bottom of the grid once it's
+        // turned to the right:
+        // ..#..      .....
+        // ..#..      .....
+        // ..#..  ->  .####
+        // ..##.      .#...
+        let right = |shape| piece::rotate(shape, piece::RotateDir::Right);
+        let foot = ivec2(3, 3);
+        let turned = follow_cell(foot, right);
+        assert_eq!(turned, ivec2(1, 3));
+        let l5 = piece::SHAPES[10];
+        assert!(l5[foot.y as usize][foot.x as usize]);
+        assert!(right(l5)[turned.y as usize][turned.x as usize]);
+        // The middle never goes anywhere.
+        assert_eq!(follow_cell(IVec2::splat(2), right), IVec2::splat(2));
+
+        // Blue's opening L5, held by the foot in the corner.
+        let mut game_state = GameState::new(2);
+        game_state.select_piece(Some(10));
+        game_state.grab = foot;
+        game_state.cursor = ivec2(19, 19);
+        let mut placement_hint = None;
+        refresh_hint(&game_state, &mut placement_hint);
+        assert_eq!(placement_hint, Some(ivec2(16, 16)));
+
+        // Turned left, the foot points up off the corner, so the pointer holds on by the end
+        // of the long side instead, and the piece stays in the corner.
+        apply_transform(&mut game_state, &mut placement_hint, |shape| {
+            piece::rotate(shape, piece::RotateDir::Left)
+        });
+        assert_eq!(placement_hint, Some(ivec2(16, 17)));
+        assert_eq!(game_state.grab, ivec2(3, 2));
+    }
+
+    #[test]
+    fn dragged_pieces_show_whether_they_fit() {
+        let mut game_state = GameState::new(2);
+        game_state.select_piece(Some(0));
+        // Blue's corner takes the dot, and the middle of the board doesn't.
+        let palette = Palette::default();
+        assert_eq!(drag_tint(&game_state, ivec2(19, 19), palette), GREEN);
+        assert_eq!(drag_tint(&game_state, ivec2(10, 10), palette), RED);
+        // The colorblind palette swaps those for blue and orange.
+        let safe = Palette::DeuteranopiaSafe;
+        assert_ne!(drag_tint(&game_state, ivec2(19, 19), safe), GREEN);
+        assert_ne!(drag_tint(&game_state, ivec2(10, 10), safe), RED);
+        // Nor does anywhere, with nothing in hand.
+        game_state.select_piece(None);
+        assert_eq!(drag_tint(&game_state, ivec2(19, 19), palette), RED);
+    }
+
+    #[test]
+    fn spectators_never_see_a_hint() {
+        let mut game_state = GameState::new(4);
+        let hint = Some(ivec2(17, 17));
+        for _ in 0..4 {
+            assert_eq!(ViewMode::Spectator.placement_hint(&game_state, hint), None);
+            let seat = ViewMode::Player(game_state.current_player);
+            assert_eq!(seat.placement_hint(&game_state, hint), hint);
+            game_state.end_turn();
+        }
+
+        // Nor do players waiting on someone else.
+        assert_eq!(ViewMode::Player(1).placement_hint(&game_state, hint), None);
+    }
+
+    #[test]
+    fn one_rotation_per_wheel_notch() {
+        use piece::RotateDir;
+        let mut wheel = WheelNotches::default();
+        assert_eq!(wheel.turn(0., 1.), None);
+        assert_eq!(wheel.turn(1., 1.), Some(RotateDir::Left));
+        // The rest of the same notch trickling in.
+        assert_eq!(wheel.turn(1., 1.02), None);
+        assert_eq!(wheel.turn(-1., 1.2), Some(RotateDir::Right));
+    }
+
+    #[test]
+    fn screen_layout_at_known_size() {
+        let screen = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        assert_eq!(screen.board_top_left, vec2(550., 250.));
+        assert_eq!(screen.board_size, 500.);
+        assert_eq!(screen.play_area_top_left, vec2(575., 275.));
+        assert_eq!(screen.tile_size, 22.5);
+        assert_eq!(screen.play_area_rect(), Rect::new(575., 275., 450., 450.));
+        // The play area sits in the middle of the board.
+        let (board, play_area) = (screen.board_rect(), screen.play_area_rect());
+        assert_eq!(board.center(), play_area.center());
+
+        // A smaller board gets bigger tiles in the same space, but the tray stays put.
+        let duo = ScreenInfo::for_window(1600., 1000., &GameConfig::duo());
+        assert_eq!(duo.play_area_rect(), screen.play_area_rect());
+        assert!(duo.tile_size > screen.tile_size);
+        assert_eq!(duo.avail_pieces_rect(), screen.avail_pieces_rect());
+    }
+
+    #[test]
+    fn cells_snap_to_whole_pixels() {
+        for (origin, step) in [(50.4, 22.5), (0., 33.75), (17.9, 27.3), (3.5, 8.1)] {
+            let edges: Vec<f32> = (0..=20).map(|n| snap_to_pixel(origin, step, n)).collect();
+            for pair in edges.windows(2) {
+                let (left, right) = (pair[0], pair[1]);
+                assert_eq!(left.fract(), 0., "{origin} + {step}");
+                // Each cell is within a pixel of the size it should be, and the next one
+                // starts right where it ends.
+                assert!((right - left - step).abs() <= 1., "{origin} + {step}");
+            }
+            // Rounding never piles up, however far along the grid it gets.
+            assert!((edges[20] - (origin + 20. * step)).abs() <= 0.5);
+        }
+
+        // The play area's border is exactly the cells inside it.
+        let screen = ScreenInfo::for_window(1366., 768., &GameConfig::classic());
+        let play_area = screen.play_area_rect();
+        assert_eq!(play_area.point(), screen.cell_rect(IVec2::ZERO).point());
+        let last = screen.cell_rect(IVec2::splat(19));
+        assert_eq!(
+            (play_area.right(), play_area.bottom()),
+            (last.right(), last.bottom())
         );
     }
 
-    // making the "executive" decision not to use the ui library (at least not for this)
-    for piece_id in player.remaining_pieces.iter() {
-        for (r_ind, row) in piece::SHAPES[piece_id].iter().enumerate() {
-            for tile in row.iter_ones() {
-                let offset = 5. * ui_tile_size;
-                let row = piece_id / 11;
-                let col = piece_id % 11;
-                draw_rectangle(
-                    tile as f32 * ui_tile_size + avail_pieces_top_left.x + offset * col as f32,
-                    r_ind as f32 * ui_tile_size + avail_pieces_top_left.y + offset * row as f32,
-                    ui_tile_size,
-                    ui_tile_size,
-                    player.color.into(),
-                );
+    #[test]
+    fn drawing_and_clicking_agree_on_cells() {
+        for (width, height) in [
+            (1600., 1000.),
+            (800., 600.),
+            (500., 1000.),
+            (3000., 500.),
+            (200., 1000.),
+        ] {
+            let screen = ScreenInfo::for_window(width, height, &GameConfig::classic());
+            // Even squeezed, the board stays in the window.
+            let board = screen.board_rect();
+            assert!(board.x >= 0. && board.right() <= width, "{width}x{height}");
+            assert!(
+                board.y >= 0. && board.bottom() <= height,
+                "{width}x{height}"
+            );
 
-                draw_rectangle_lines(
-                    tile as f32 * ui_tile_size + avail_pieces_top_left.x + offset * col as f32,
-                    r_ind as f32 * ui_tile_size + avail_pieces_top_left.y + offset * row as f32,
-                    ui_tile_size,
-                    ui_tile_size,
-                    2.,
-                    BLACK,
-                );
+            for row in 0..20 {
+                for col in 0..20 {
+                    let cell = ivec2(col, row);
+                    let rect = screen.cell_rect(cell);
+                    assert!(screen.play_area_rect().contains(rect.center()));
+                    // Right on a line between cells could go either way.
+                    for pos in [
+                        rect.point() + 0.01 * rect.size(),
+                        rect.center(),
+                        rect.point() + 0.99 * rect.size(),
+                    ] {
+                        assert_eq!(screen.cell_at(pos), Some(cell), "{width}x{height}");
+                    }
+                }
             }
+            assert_eq!(screen.cell_at(board.point()), None);
+            assert_eq!(screen.cell_at(vec2(width, height)), None);
         }
     }
-}
 
-fn handle_input(
-    game_state: &mut GameState,
-    placement_hint: &mut Option<IVec2>,
-    play_area_top_left: Vec2,
-    avail_pieces_pt: Vec2,
-    tile_size: f32,
-    ui_tile_size: f32,
-) {
-    // click detection rects
-    let board_rect = Rect::new(
-        play_area_top_left.x,
-        play_area_top_left.y,
-        20. * tile_size,
-        20. * tile_size,
-    );
+    #[test]
+    fn bots_move_without_waiting_for_input() {
+        // Left alone, the bots keep going every frame.
+        let mut pace = BotPace::default();
+        assert_eq!(pace.turns_due(0., false), 1);
+        pace.speed = BotSpeed::Instant;
+        assert_eq!(pace.turns_due(0., false), BotPace::INSTANT_TURNS);
 
-    let piece_rect = Rect::new(
-        avail_pieces_pt.x,
-        avail_pieces_pt.y,
-        11. * 5. * ui_tile_size,
-        10. * ui_tile_size,
-    );
+        // With a delay, they wait it out, but still don't need anyone to click.
+        pace.speed = BotSpeed::Every(0.5);
+        pace.last_turn = 10.;
+        assert_eq!(pace.turns_due(10.2, false), 0);
+        assert_eq!(pace.turns_due(10.5, false), 1);
 
-    // Flip pieces
-    if [KeyCode::A, KeyCode::D, KeyCode::Left, KeyCode::Right]
-        .into_iter()
-        .any(is_key_pressed)
-    {
-        use piece::FlipDir;
-        game_state.piece_buffer = piece::flip(game_state.piece_buffer, FlipDir::Horizontal);
-        // Can't quite do `Option::map` since `update_suggestion` is T -> Option<U> not T -> U.
-        *placement_hint = match *placement_hint {
-            Some(proposed) => update_suggestion(&game_state, proposed),
-            None => None,
-        };
+        // Paused, they only go one step at a time.
+        pace.paused = true;
+        assert_eq!(pace.turns_due(100., false), 0);
+        assert_eq!(pace.turns_due(100., true), 1);
+
+        // The speed button comes back around.
+        let mut speed = BotSpeed::default();
+        for _ in 0..4 {
+            speed = speed.next();
+        }
+        assert_eq!(speed, BotSpeed::default());
     }
 
-    if [KeyCode::W, KeyCode::S, KeyCode::Up, KeyCode::Down]
-        .into_iter()
-        .any(is_key_pressed)
-    {
-        use piece::FlipDir;
-        game_state.piece_buffer = piece::flip(game_state.piece_buffer, FlipDir::Vertical);
-        *placement_hint = match *placement_hint {
-            Some(proposed) => update_suggestion(&game_state, proposed),
-            None => None,
+    #[test]
+    fn second_click_confirms_placement() {
+        let (here, there) = (ivec2(3, 4), ivec2(7, 7));
+        let held = |corner| {
+            Some(Placement {
+                corner,
+                confirmed: false,
+            })
+        };
+        let placed = |corner| {
+            Some(Placement {
+                corner,
+                confirmed: true,
+            })
         };
+
+        // One click is all it takes by default.
+        assert_eq!(Placing::OneClick.click(None, Some(here)), placed(here));
+        assert_eq!(Placing::OneClick.click(None, None), None);
+
+        let two = Placing::TwoClicks;
+        assert_eq!(two.click(None, Some(here)), held(here));
+        // Clicking the same spot again puts it down...
+        assert_eq!(two.click(held(here), Some(here)), placed(here));
+        // ...but clicking anywhere else, fitting or not, calls it off.
+        assert_eq!(two.click(held(here), Some(there)), None);
+        assert_eq!(two.click(held(here), None), None);
+        // So the click after that starts over.
+        assert_eq!(two.click(None, Some(there)), held(there));
     }
 
-    // Rotate pieces
-    if is_key_pressed(KeyCode::Q) || is_key_pressed(KeyCode::PageUp) {
-        use piece::RotateDir;
-        game_state.piece_buffer = piece::rotate(game_state.piece_buffer, RotateDir::Left);
-        *placement_hint = match *placement_hint {
-            Some(proposed) => update_suggestion(&game_state, proposed),
-            None => None,
+    #[test]
+    fn turned_boards_click_where_they_draw() {
+        let base = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        let screen = base.with_view_rotation(1);
+        // A quarter turn clockwise takes the left edge up to the top.
+        let cell = ivec2(2, 5);
+        assert_eq!(screen.turned(cell), ivec2(14, 2));
+        assert_eq!(screen.cell_rect(cell), base.cell_rect(ivec2(14, 2)));
+        assert_eq!(screen.cell_at(screen.cell_rect(cell).center()), Some(cell));
+        assert_eq!(screen.unturned(screen.turned(cell)), cell);
+
+        // Everyone gets their start corner at the bottom left.
+        let game_state = GameState::new(4);
+        for idx in 0..4 {
+            let (row, col) = game_state.start_corner(idx);
+            let screen = base.with_view_rotation(seat_rotation(&game_state, idx));
+            assert_eq!(screen.turned(ivec2(col as i32, row as i32)), ivec2(0, 19));
+        }
+    }
+
+    #[test]
+    fn previews_turn_with_the_board() {
+        // The L5 in the tray and the preview takes up the same cells as its ghost on the board.
+        let l5 = piece::SHAPES[10];
+        let cells = |shape: &piece::Shape| {
+            let mut cells: Vec<_> = piece::cells(shape)
+                .map(|(row, col)| ivec2(col as i32, row as i32))
+                .collect();
+            cells.sort_by_key(|cell| (cell.y, cell.x));
+            cells
         };
+        for quarter_turns in 0..4 {
+            let mut on_board: Vec<_> = cells(&l5)
+                .into_iter()
+                .map(|cell| turn_cell(cell, 5, quarter_turns))
+                .collect();
+            on_board.sort_by_key(|cell| (cell.y, cell.x));
+            assert_eq!(cells(&turned_shape(l5, quarter_turns)), on_board);
+        }
     }
 
-    if is_key_pressed(KeyCode::E) || is_key_pressed(KeyCode::PageDown) {
-        use piece::RotateDir;
-        game_state.piece_buffer = piece::rotate(game_state.piece_buffer, RotateDir::Right);
-        *placement_hint = match *placement_hint {
-            Some(proposed) => update_suggestion(&game_state, proposed),
-            None => None,
+    #[test]
+    fn board_faces_whoever_plays_here() {
+        let mut players = Player::default_order(4);
+        players[1].is_bot = true;
+        let mut game_state = GameState::with_players(players, GameConfig::classic());
+        // Red plays from somewhere else.
+        let remote = |idx: usize| idx == 2;
+        assert_eq!(facing_seat(&game_state, None, remote), Some(0));
+
+        // The bot's and Red's turns leave it facing whoever had it last.
+        game_state.current_player = 1;
+        assert_eq!(facing_seat(&game_state, Some(0), remote), Some(0));
+        game_state.current_player = 2;
+        assert_eq!(facing_seat(&game_state, Some(0), remote), Some(0));
+        game_state.current_player = 3;
+        assert_eq!(facing_seat(&game_state, Some(0), remote), Some(3));
+
+        // A game that starts on a bot's turn faces the first person here.
+        game_state.current_player = 1;
+        assert_eq!(facing_seat(&game_state, None, remote), Some(0));
+        assert_eq!(facing_seat(&game_state, None, |_| true), None);
+    }
+
+    #[test]
+    fn find_pieces_in_the_tray() {
+        let screen = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        let pieces = PieceSet::classic();
+        let slot = |row: f32, col: f32| {
+            screen.tray.top_left + 5. * screen.ui_tile_size * vec2(col + 0.5, row + 0.5)
         };
+        assert_eq!(screen.tray_piece_at(slot(0., 0.), &pieces), Some(0));
+        assert_eq!(screen.tray_piece_at(slot(1., 3.), &pieces), Some(14));
+        assert_eq!(screen.tray_piece_at(slot(1., 9.), &pieces), Some(20));
+        // There's one empty slot at the end of the second row.
+        assert_eq!(screen.tray_piece_at(slot(1., 10.), &pieces), None);
+        assert_eq!(
+            screen.tray_piece_at(screen.play_area_top_left, &pieces),
+            None
+        );
     }
 
-    let mouse_pos = Vec2::from(mouse_position());
-    // If this move is valid, mark it as such for the drawing logic.
-    if board_rect.contains(mouse_pos) {
-        let center = ivec2(
-            ((mouse_pos.x - board_rect.x) / tile_size) as i32,
-            ((mouse_pos.y - board_rect.y) / tile_size) as i32,
+    #[test]
+    fn zoomed_clicks_land_on_the_same_cell() {
+        let base = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        let mut view = BoardView::default();
+        let pointer = base.cell_rect(ivec2(12, 7)).center();
+        view.zoom_at(2.5, pointer, &base);
+        view.pan += vec2(-40., 25.);
+        view.clamp(&base);
+        let screen = base.viewed_through(view);
+        assert_eq!(screen.tile_size, 2.5 * base.tile_size);
+
+        // Every cell that's on screen is still found right where it's drawn.
+        let window = Rect::new(0., 0., 1600., 1000.);
+        for row in 0..20 {
+            for col in 0..20 {
+                let cell = ivec2(col, row);
+                let center = screen.cell_rect(cell).center();
+                if window.contains(center) {
+                    assert_eq!(screen.cell_at(center), Some(cell));
+                }
+            }
+        }
+
+        // Zooming keeps what's under the pointer under it.
+        let mut view = BoardView::default();
+        view.zoom_at(2., pointer, &base);
+        assert_eq!(
+            base.viewed_through(view).cell_at(pointer),
+            Some(ivec2(12, 7))
         );
 
-        *placement_hint = update_suggestion(&game_state, center);
+        // No dragging the board off into the distance, or zooming past the limits.
+        view.pan = vec2(1e6, -1e6);
+        view.clamp(&base);
+        let center = base.viewed_through(view).board_rect().center();
+        assert_eq!(center, vec2(1600., 0.));
+        view.zoom_at(100., pointer, &base);
+        assert_eq!(view.zoom, BoardView::MAX_ZOOM);
+        view.zoom_at(0.001, pointer, &base);
+        assert_eq!(view.zoom, 1.);
     }
 
-    if is_mouse_button_pressed(MouseButton::Left) {
-        if board_rect.contains(mouse_pos) {
-            // put a piece on the board -- we know where, since we already validated!
-            if let Some(corner) = *placement_hint {
-                game_state.place_piece(corner);
-                game_state.end_turn();
-                *placement_hint = None;
+    #[test]
+    fn preview_labels_name_and_orientation() {
+        let pieces = PieceSet::classic();
+        assert_eq!(
+            preview_labels(&pieces, 10, Orientation::new(true, 2)),
+            ("L5, 5 tiles".to_string(), "rot 2, flipped".to_string())
+        );
+        assert_eq!(
+            preview_labels(&pieces, 0, Orientation::default()),
+            ("DOT, 1 tile".to_string(), "rot 0".to_string())
+        );
+    }
+
+    #[test]
+    fn coordinate_labels() {
+        assert_eq!(
+            (column_label(0), row_label(0)),
+            ("A".to_string(), "1".to_string())
+        );
+        // The classic board runs A to T and 1 to 20.
+        assert_eq!(
+            (column_label(19), row_label(19)),
+            ("T".to_string(), "20".to_string())
+        );
+        assert_eq!(column_label(25), "Z");
+        // Past the alphabet, the letters double up.
+        assert_eq!(column_label(26), "AA");
+        assert_eq!(column_label(27), "AB");
+        assert_eq!(column_label(51), "AZ");
+        assert_eq!(column_label(52), "BA");
+        assert_eq!(column_label(701), "ZZ");
+        assert_eq!(column_label(702), "AAA");
+    }
+
+    #[test]
+    fn clicks_just_outside_miss() {
+        let screen = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        let nudge = 0.01 * screen.tile_size;
+
+        // Either side of the line between the first two cells.
+        let line = screen.cell_rect(ivec2(1, 0)).x;
+        let y = screen.cell_rect(IVec2::ZERO).center().y;
+        assert_eq!(screen.cell_at(vec2(line - nudge, y)), Some(ivec2(0, 0)));
+        assert_eq!(screen.cell_at(vec2(line + nudge, y)), Some(ivec2(1, 0)));
+
+        // Either side of the play area's edges.
+        let play_area = screen.play_area_rect();
+        let (left, right) = (play_area.x, play_area.right());
+        assert_eq!(screen.cell_at(vec2(left + nudge, y)), Some(ivec2(0, 0)));
+        assert_eq!(screen.cell_at(vec2(left - nudge, y)), None);
+        assert_eq!(screen.cell_at(vec2(right - nudge, y)), Some(ivec2(19, 0)));
+        assert_eq!(screen.cell_at(vec2(right + nudge, y)), None);
+        assert_eq!(
+            screen.cell_at(vec2(left + nudge, play_area.y - nudge)),
+            None
+        );
+
+        // Same for the tray: between the first two slots, and just past the first one.
+        let pieces = PieceSet::classic();
+        let first = tray_slot(&screen.tray, 0, &pieces);
+        let y = first.center().y;
+        let nudge = 0.01 * screen.ui_tile_size;
+        assert_eq!(
+            screen.tray_piece_at(vec2(first.right() - nudge, y), &pieces),
+            Some(0)
+        );
+        assert_eq!(
+            screen.tray_piece_at(vec2(first.right() + nudge, y), &pieces),
+            Some(1)
+        );
+        assert_eq!(
+            screen.tray_piece_at(vec2(first.x - nudge, y), &pieces),
+            None
+        );
+        assert_eq!(
+            screen.tray_piece_at(vec2(first.center().x, first.y - nudge), &pieces),
+            None
+        );
+    }
+
+    #[test]
+    fn tray_orders_cover_every_piece_once() {
+        let pieces = PieceSet::classic();
+        let base = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        for order in [TrayOrder::Id, TrayOrder::SizeAsc, TrayOrder::SizeDesc] {
+            let mut arrangement = order.arrangement(&pieces);
+            let sizes: Vec<_> = arrangement.iter().map(|&id| pieces.size(id)).collect();
+            match order {
+                TrayOrder::Id => assert!(arrangement.iter().copied().eq(0..pieces.len())),
+                TrayOrder::SizeAsc => assert!(sizes.is_sorted()),
+                TrayOrder::SizeDesc => assert!(sizes.iter().rev().is_sorted()),
             }
-        } else if piece_rect.contains(mouse_pos) {
-            // Change selected piece.
-            let piece_size = 5. * ui_tile_size;
-            let (col, row) = (
-                ((mouse_pos.x - piece_rect.x) / piece_size) as usize,
-                ((mouse_pos.y - piece_rect.y) / piece_size) as usize,
-            );
-            dbg!(row, col);
-
-            let piece_id = row * 11 + col;
-            if game_state
-                .current_player()
-                .remaining_pieces
-                .contains(piece_id)
-            {
-                game_state.select_piece(Some(piece_id));
+            // Clicking a piece's slot finds that piece, whatever order they're in.
+            let screen = base.with_tray_order(order);
+            for piece_id in 0..pieces.len() {
+                let slot = tray_slot(&screen.tray, piece_id, &pieces);
+                assert_eq!(screen.tray_piece_at(slot.center(), &pieces), Some(piece_id));
             }
-        } else {
-            game_state.select_piece(None);
+            arrangement.sort();
+            assert!(arrangement.into_iter().eq(0..pieces.len()));
         }
     }
-}
 
-/// Given the center position and size of a UI element, return the position
-/// of its topleft corner.
-fn centered_at(center: Vec2, dims: Vec2) -> Vec2 {
-    center - 0.5 * dims
-}
+    #[test]
+    fn opponent_trays_show_what_they_have_left() {
+        let mut game_state = GameState::new(4);
+        game_state.players[1].remaining_pieces.remove(20);
+        game_state.players[2].remaining_pieces.remove(0);
+        game_state.players[2].remaining_pieces.remove(7);
+        let opponents = opponent_trays(&game_state, 0, TrayOrder::SizeDesc);
+        assert_eq!(
+            opponents.iter().map(|&(idx, _)| idx).collect::<Vec<_>>(),
+            [1, 2, 3]
+        );
+        for (idx, mut left) in opponents {
+            left.sort();
+            let remaining: Vec<_> = game_state.players[idx].remaining_pieces.iter().collect();
+            assert_eq!(left, remaining);
+        }
 
-fn medium_ui_button_dims() -> Vec2 {
-    vec2(screen_height() / 4., screen_height() / 16.)
-}
+        // They all fit in the margin, clear of the board.
+        let screen = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        for nth in 0..3 {
+            let tray = screen.opponent_tray(nth, 3, 21).rect();
+            assert!(tray.x >= screen.board_rect().right() && tray.right() <= 1600.);
+            assert!(tray.bottom() <= screen.board_rect().bottom() + 0.01);
+        }
+    }
 
-fn medium_ui_button_padding() -> f32 {
-    1. / 64. * screen_height()
-}
+    #[test]
+    fn tray_fits_narrow_windows() {
+        let wide = ScreenInfo::for_window(1600., 1000., &GameConfig::classic());
+        let narrow = ScreenInfo::for_window(500., 1000., &GameConfig::classic());
+        assert_eq!(wide.tray.columns, 11);
+        assert!(narrow.tray.columns < 11);
 
-/// Updates the coordinates for the potential next move.
-fn update_suggestion(game_state: &GameState, proposed: IVec2) -> Option<IVec2> {
-    if let Some(corner) = game_state.check_bounds_and_recenter(proposed) {
-        // Why did I need to do "+1" here?
-        // I completely forgot what madness led me here.
-        if game_state.valid_move(corner + IVec2::ONE) {
-            Some(corner)
-        } else {
-            None
+        let pieces = PieceSet::classic();
+        for (screen, width) in [(wide, 1600.), (narrow, 500.)] {
+            // Everything stays on screen.
+            let tray = screen.avail_pieces_rect();
+            assert!(tray.x >= 0. && tray.right() <= width);
+            assert!(tray.bottom() <= 1000.);
+
+            for piece_id in 0..piece::SHAPES.len() {
+                let slot = tray_slot(&screen.tray, piece_id, &pieces);
+                assert!(tray.contains(slot.center()));
+                // Clicking anywhere in a piece's slot picks that piece.
+                for pos in [
+                    slot.point(),
+                    slot.center(),
+                    slot.point() + 0.99 * slot.size(),
+                ] {
+                    assert_eq!(screen.tray_piece_at(pos, &pieces), Some(piece_id));
+                }
+            }
         }
-    } else {
-        None
     }
-}
 
-fn draw_piece(shape: piece::Shape, color: TileColor, at: Vec2, tile_size: f32, with_borders: bool) {
-    // piece preview
-    for (r_ind, row) in shape.iter().enumerate() {
-        for tile in row.iter_ones() {
-            draw_rectangle(
-                tile as f32 * tile_size + at.x,
-                r_ind as f32 * tile_size + at.y,
-                tile_size,
-                tile_size,
-                color.into(),
-            );
+    #[test]
+    fn tray_makes_room_for_bigger_sets() {
+        let pieces = (0..3).fold(PieceSet::classic(), |pieces, i| {
+            pieces.with_piece(&format!("EXTRA{i}"), piece::SHAPES[i])
+        });
+        let config = GameConfig::classic().pieces(pieces.clone());
+        let screen = ScreenInfo::for_window(1600., 1000., &config);
+        // Still two rows, just longer ones.
+        assert_eq!(screen.tray.columns, 12);
 
-            if with_borders {
-                draw_rectangle_lines(
-                    tile as f32 * tile_size + at.x,
-                    r_ind as f32 * tile_size + at.y,
-                    tile_size,
-                    tile_size,
-                    2.,
-                    BLACK,
-                );
-            }
+        let tray = screen.avail_pieces_rect();
+        assert!(tray.x >= 0. && tray.right() <= 1600.);
+        for piece_id in 0..24 {
+            let slot = tray_slot(&screen.tray, piece_id, &pieces);
+            assert!(tray.contains(slot.center()));
+            assert_eq!(screen.tray_piece_at(slot.center(), &pieces), Some(piece_id));
         }
+        let past_the_end = screen.tray.slot_at(24);
+        assert_eq!(screen.tray_piece_at(past_the_end.center(), &pieces), None);
+    }
+
+    #[test]
+    fn pockets_stop_at_tiles() {
+        let mut game_state = GameState::new(2);
+        // Box in a 2x2 pocket in the corner with Red's tiles.
+        for (row, col) in [(0, 2), (1, 2), (2, 0), (2, 1), (2, 2)] {
+            game_state.board[row + 1][col + 1] = TileColor::Red;
+        }
+        let mut pocket = pocket_at(&game_state, ivec2(1, 0)).expect("Four cells is a pocket.");
+        pocket.sort();
+        assert_eq!(pocket, [(0, 0), (0, 1), (1, 0), (1, 1)]);
+
+        // Tiles aren't pockets, and neither is the rest of the board.
+        assert_eq!(pocket_at(&game_state, ivec2(2, 2)), None);
+        assert_eq!(pocket_at(&game_state, ivec2(10, 10)), None);
+    }
+
+    #[test]
+    fn placed_pieces_are_outlined_whole() {
+        let mut game_state = GameState::new(2);
+        let pieces = &game_state.config.pieces;
+        let l5 = (0..pieces.len())
+            .find(|&id| pieces.name(id) == "L5")
+            .unwrap();
+        let mv = game_state
+            .enumerate_legal_moves()
+            .into_iter()
+            .find(|mv| mv.piece_id == l5)
+            .unwrap();
+        game_state.apply_move(&mv);
+
+        let &[(0, placed)] = game_state.moves() else {
+            panic!("Expected just Blue's L5, got {:?}", game_state.moves());
+        };
+        let cells: Vec<_> = placed.board_cells(&game_state.config.pieces).collect();
+        // Five cells have twenty sides, and the four joins inside the piece hide two each.
+        assert_eq!(outline_edges(&cells).len(), 12);
+        // Cells off by themselves get all four.
+        assert_eq!(outline_edges(&[(0, 0), (1, 1)]).len(), 8);
     }
 }
@@ -1,42 +1,1628 @@
+// None of this is hooked up to the game loop yet.
+#![allow(dead_code)]
+
 use macroquad::prelude::*;
-use std::net::{Ipv4Addr, TcpStream};
+use std::{
+    collections::VecDeque,
+    io::{self, Read, Write},
+    net::Ipv4Addr,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    lobby,
+    logic::{GameEvent, GameState, Move, MoveError, Player, TileColor},
+    piece::Orientation,
+    session::{check_submission, Session, SubmitError, UndoHistory},
+};
+
+/// Longest chat message anyone gets to send, in characters. Anything longer is cut off.
+pub const MAX_CHAT_LEN: usize = 200;
+
+// Every message starts with one of these, so we know what the rest of the bytes mean.
+const MOVE_TAG: u8 = 0;
+const CHAT_TAG: u8 = 1;
+const PING_TAG: u8 = 2;
+const PONG_TAG: u8 = 3;
+const PASS_TAG: u8 = 4;
+const REJECT_TAG: u8 = 5;
+const SWAP_TAG: u8 = 6;
+const READY_TAG: u8 = 7;
+const HELLO_TAG: u8 = 8;
+const EVENT_TAG: u8 = 9;
+const RESYNC_TAG: u8 = 10;
+const STATE_TAG: u8 = 11;
+const FORFEIT_TAG: u8 = 12;
+const ROSTER_TAG: u8 = 13;
+const UNDO_REQUEST_TAG: u8 = 14;
+const UNDO_VOTE_TAG: u8 = 15;
+const UNDO_DECIDED_TAG: u8 = 16;
+
+// What kind of event an event message carries, right after its tag.
+const PLACED_EVENT: u8 = 0;
+const TURN_EVENT: u8 = 1;
+const PASSED_EVENT: u8 = 2;
+const GAME_OVER_EVENT: u8 = 3;
+const FORFEITED_EVENT: u8 = 4;
+/// Stands in for a seat that isn't there, like the winner of a tied game.
+const NO_SEAT: u8 = u8::MAX;
+
+/// How often the server pings everyone.
+pub const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(2);
+/// How long we wait to hear anything from someone before deciding they've left.
+pub const HEARTBEAT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Everything that goes over the wire during a game.
+///
+/// A move is five bytes: the tag, the piece ID, the orientation (flipped in bit 2, rotation
+/// in the low two bits), then the corner's x and y as signed bytes. A chat message is the tag,
+/// the sender's name prefixed by its length in one byte, then the text prefixed by its length
+/// in two big-endian bytes. A rejection is the tag and one byte saying why (see
+/// `RejectReason::code`). A ready toggle is the tag, the seat and a 0 or 1. A hello is the tag
+/// and the name, prefixed by its length in one byte like a chat sender's. An event is the tag,
+/// what kind of event it is, and then the seat it's about (`NO_SEAT` for a tied game's
+/// winner), followed by the move's four bytes if a piece was placed. A full state is the tag
+/// and the save text, prefixed by its length in two big-endian bytes like chat. A roster is
+/// the tag and how many entries follow, each one a name prefixed by its length in one byte,
+/// the color's place in `TileColor::all_playable` (`NO_SEAT` for spectators), and then a
+/// byte for their `Health`. An undo request is the tag and a seat, and an undo vote or
+/// decision is the tag and a 0 or 1. Passes, seat swaps, forfeits, resync requests, pings
+/// and pongs are just their tag.
+///
+/// On the wire, each message is wrapped in a frame (see `write_frame` and `FrameReader`), and
+/// frames go back and forth over a `Transport`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    Move(Move),
+    Chat {
+        from: String,
+        text: String,
+    },
+    /// Whoever's turn it is skips it.
+    Pass,
+    /// The second player takes the first player's opening (see `GameState::swap_seats`).
+    SwapSeats,
+    /// Whoever sent it gives up their seat (see `GameState::forfeit`).
+    Forfeit,
+    /// The host didn't accept your last move or pass.
+    Reject(RejectReason),
+    /// Someone in the lobby is (or isn't) ready to start. Clients can put anything in `seat`,
+    /// since the host knows who sent it; the host fills it in when passing it on.
+    Ready {
+        seat: u8,
+        ready: bool,
+    },
+    /// The first thing anyone joining sends: what to call them. This is also how the host
+    /// knows who's back to take a reserved seat.
+    Hello {
+        name: String,
+    },
+    /// Something that happened on the host's board. Clients only ever change their copy of
+    /// the game by playing these back, in order.
+    Event(GameEvent),
+    /// A client asking for the whole game, because its copy stopped agreeing with what the
+    /// host says happened.
+    Resync,
+    /// The whole game, written out by `GameState::to_save_string`, for a client to start
+    /// over from.
+    State(String),
+    /// Who's connected, from the host. Sent whenever someone comes or goes, and with every
+    /// round of heartbeats so everyone's `Health` stays current.
+    Roster(Vec<RosterEntry>),
+    /// From a client, asking to take back their last move or pass. From the host, asking
+    /// whether the player in `seat` can take theirs back. Clients can put anything in
+    /// `seat`, like with `Ready`.
+    UndoRequest {
+        seat: u8,
+    },
+    /// A client's answer to an `UndoRequest`.
+    UndoVote {
+        approve: bool,
+    },
+    /// How an undo vote went, from the host to everyone. If it went through, the whole
+    /// game follows as a `State`.
+    UndoDecided {
+        approved: bool,
+    },
+    /// The server checking that you're still there. Answer with a `Pong`.
+    Ping,
+    Pong,
+}
+
+impl Message {
+    /// A chat message, cleaned up with `sanitize_chat`.
+    pub fn chat(from: &str, text: &str) -> Self {
+        Self::Chat {
+            from: sanitize_chat(from),
+            text: sanitize_chat(text),
+        }
+    }
+
+    /// A hello, with the name cleaned up like chat.
+    pub fn hello(name: &str) -> Self {
+        Self::Hello {
+            name: sanitize_chat(name.trim()),
+        }
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        match self {
+            Self::Move(mv) => {
+                let mut bytes = vec![MOVE_TAG];
+                bytes.extend(encode_move(mv));
+                bytes
+            }
+            Self::Chat { from, text } => {
+                let from = truncate_bytes(from, u8::MAX as usize);
+                let text = truncate_bytes(text, u16::MAX as usize);
+                let mut bytes = vec![CHAT_TAG, from.len() as u8];
+                bytes.extend_from_slice(from.as_bytes());
+                bytes.extend_from_slice(&(text.len() as u16).to_be_bytes());
+                bytes.extend_from_slice(text.as_bytes());
+                bytes
+            }
+            Self::Pass => vec![PASS_TAG],
+            Self::SwapSeats => vec![SWAP_TAG],
+            Self::Forfeit => vec![FORFEIT_TAG],
+            Self::Reject(reason) => vec![REJECT_TAG, reason.code()],
+            Self::Ready { seat, ready } => vec![READY_TAG, *seat, *ready as u8],
+            Self::Hello { name } => {
+                let name = truncate_bytes(name, u8::MAX as usize);
+                let mut bytes = vec![HELLO_TAG, name.len() as u8];
+                bytes.extend_from_slice(name.as_bytes());
+                bytes
+            }
+            Self::Event(event) => {
+                let seat = |idx: usize| idx as u8;
+                match *event {
+                    GameEvent::PiecePlaced { player, mv } => {
+                        let mut bytes = vec![EVENT_TAG, PLACED_EVENT, seat(player)];
+                        bytes.extend(encode_move(&mv));
+                        bytes
+                    }
+                    GameEvent::TurnEnded { next } => vec![EVENT_TAG, TURN_EVENT, seat(next)],
+                    GameEvent::Passed { player } => vec![EVENT_TAG, PASSED_EVENT, seat(player)],
+                    GameEvent::Forfeited { player } => {
+                        vec![EVENT_TAG, FORFEITED_EVENT, seat(player)]
+                    }
+                    GameEvent::GameOver { winner } => {
+                        vec![EVENT_TAG, GAME_OVER_EVENT, winner.map_or(NO_SEAT, seat)]
+                    }
+                }
+            }
+            Self::Resync => vec![RESYNC_TAG],
+            Self::State(save) => {
+                let save = truncate_bytes(save, u16::MAX as usize);
+                let mut bytes = vec![STATE_TAG];
+                bytes.extend_from_slice(&(save.len() as u16).to_be_bytes());
+                bytes.extend_from_slice(save.as_bytes());
+                bytes
+            }
+            Self::Roster(entries) => {
+                let mut bytes = vec![ROSTER_TAG, entries.len().min(u8::MAX as usize) as u8];
+                for entry in entries.iter().take(u8::MAX as usize) {
+                    let name = truncate_bytes(&entry.name, u8::MAX as usize);
+                    bytes.push(name.len() as u8);
+                    bytes.extend_from_slice(name.as_bytes());
+                    let color = match entry.role {
+                        Role::Playing(color) => TileColor::all_playable()
+                            .iter()
+                            .position(|&c| c == color)
+                            .map_or(NO_SEAT, |idx| idx as u8),
+                        Role::Spectating => NO_SEAT,
+                    };
+                    bytes.extend([color, entry.health.code()]);
+                }
+                bytes
+            }
+            Self::UndoRequest { seat } => vec![UNDO_REQUEST_TAG, *seat],
+            Self::UndoVote { approve } => vec![UNDO_VOTE_TAG, *approve as u8],
+            Self::UndoDecided { approved } => vec![UNDO_DECIDED_TAG, *approved as u8],
+            Self::Ping => vec![PING_TAG],
+            Self::Pong => vec![PONG_TAG],
+        }
+    }
+
+    /// Reads exactly one message off of `reader`. Chat from the other end gets sanitized
+    /// again, since we can't trust them to have done it.
+    pub fn read_from(reader: &mut impl Read) -> io::Result<Self> {
+        let invalid = |what: &str| io::Error::new(io::ErrorKind::InvalidData, what.to_string());
+        let mut tag = [0u8; 1];
+        reader.read_exact(&mut tag)?;
+        match tag[0] {
+            MOVE_TAG => {
+                let mut payload = [0u8; 4];
+                reader.read_exact(&mut payload)?;
+                decode_move(payload)
+                    .map(Self::Move)
+                    .ok_or_else(|| invalid("bad move"))
+            }
+            CHAT_TAG => {
+                let mut from_len = [0u8; 1];
+                reader.read_exact(&mut from_len)?;
+                let mut from = vec![0u8; from_len[0] as usize];
+                reader.read_exact(&mut from)?;
+
+                let mut text_len = [0u8; 2];
+                reader.read_exact(&mut text_len)?;
+                let mut text = vec![0u8; u16::from_be_bytes(text_len) as usize];
+                reader.read_exact(&mut text)?;
+
+                let from = String::from_utf8(from).map_err(|_| invalid("bad name"))?;
+                let text = String::from_utf8(text).map_err(|_| invalid("bad chat"))?;
+                Ok(Self::chat(&from, &text))
+            }
+            PASS_TAG => Ok(Self::Pass),
+            SWAP_TAG => Ok(Self::SwapSeats),
+            FORFEIT_TAG => Ok(Self::Forfeit),
+            REJECT_TAG => {
+                let mut code = [0u8; 1];
+                reader.read_exact(&mut code)?;
+                RejectReason::from_code(code[0])
+                    .map(Self::Reject)
+                    .ok_or_else(|| invalid("unknown reject reason"))
+            }
+            READY_TAG => {
+                let mut payload = [0u8; 2];
+                reader.read_exact(&mut payload)?;
+                let [seat, ready] = payload;
+                if ready > 1 {
+                    return Err(invalid("bad ready flag"));
+                }
+                Ok(Self::Ready {
+                    seat,
+                    ready: ready == 1,
+                })
+            }
+            HELLO_TAG => {
+                let mut len = [0u8; 1];
+                reader.read_exact(&mut len)?;
+                let mut name = vec![0u8; len[0] as usize];
+                reader.read_exact(&mut name)?;
+                let name = String::from_utf8(name).map_err(|_| invalid("bad name"))?;
+                Ok(Self::hello(&name))
+            }
+            EVENT_TAG => {
+                let mut header = [0u8; 2];
+                reader.read_exact(&mut header)?;
+                let [kind, seat] = header;
+                let idx = seat as usize;
+                let event = match kind {
+                    PLACED_EVENT => {
+                        let mut payload = [0u8; 4];
+                        reader.read_exact(&mut payload)?;
+                        let mv = decode_move(payload).ok_or_else(|| invalid("bad move"))?;
+                        GameEvent::PiecePlaced { player: idx, mv }
+                    }
+                    TURN_EVENT => GameEvent::TurnEnded { next: idx },
+                    PASSED_EVENT => GameEvent::Passed { player: idx },
+                    FORFEITED_EVENT => GameEvent::Forfeited { player: idx },
+                    GAME_OVER_EVENT => GameEvent::GameOver {
+                        winner: (seat != NO_SEAT).then_some(idx),
+                    },
+                    _ => return Err(invalid("unknown event")),
+                };
+                Ok(Self::Event(event))
+            }
+            RESYNC_TAG => Ok(Self::Resync),
+            STATE_TAG => {
+                let mut len = [0u8; 2];
+                reader.read_exact(&mut len)?;
+                let mut save = vec![0u8; u16::from_be_bytes(len) as usize];
+                reader.read_exact(&mut save)?;
+                let save = String::from_utf8(save).map_err(|_| invalid("bad state"))?;
+                Ok(Self::State(save))
+            }
+            ROSTER_TAG => {
+                let mut count = [0u8; 1];
+                reader.read_exact(&mut count)?;
+                let mut entries = Vec::with_capacity(count[0] as usize);
+                for _ in 0..count[0] {
+                    let mut len = [0u8; 1];
+                    reader.read_exact(&mut len)?;
+                    let mut name = vec![0u8; len[0] as usize];
+                    reader.read_exact(&mut name)?;
+                    let name = String::from_utf8(name).map_err(|_| invalid("bad name"))?;
+                    let mut payload = [0u8; 2];
+                    reader.read_exact(&mut payload)?;
+                    let [color, health] = payload;
+                    let role = match color {
+                        NO_SEAT => Role::Spectating,
+                        idx => TileColor::all_playable()
+                            .get(idx as usize)
+                            .copied()
+                            .map(Role::Playing)
+                            .ok_or_else(|| invalid("bad color"))?,
+                    };
+                    let health = Health::from_code(health).ok_or_else(|| invalid("bad health"))?;
+                    entries.push(RosterEntry {
+                        name: sanitize_chat(&name),
+                        role,
+                        health,
+                    });
+                }
+                Ok(Self::Roster(entries))
+            }
+            UNDO_REQUEST_TAG => {
+                let mut seat = [0u8; 1];
+                reader.read_exact(&mut seat)?;
+                Ok(Self::UndoRequest { seat: seat[0] })
+            }
+            UNDO_VOTE_TAG | UNDO_DECIDED_TAG => {
+                let mut flag = [0u8; 1];
+                reader.read_exact(&mut flag)?;
+                let yes = match flag[0] {
+                    0 => false,
+                    1 => true,
+                    _ => return Err(invalid("bad undo flag")),
+                };
+                Ok(if tag[0] == UNDO_VOTE_TAG {
+                    Self::UndoVote { approve: yes }
+                } else {
+                    Self::UndoDecided { approved: yes }
+                })
+            }
+            PING_TAG => Ok(Self::Ping),
+            PONG_TAG => Ok(Self::Pong),
+            _ => Err(invalid("unknown message tag")),
+        }
+    }
+
+    /// Decodes a whole frame's worth of bytes. Leftovers mean the frame was bad.
+    pub fn decode(mut frame: &[u8]) -> io::Result<Self> {
+        let message = Self::read_from(&mut frame)?;
+        if !frame.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "trailing bytes after message",
+            ));
+        }
+        Ok(message)
+    }
+}
+
+/// A move's four bytes on the wire: the piece ID, the orientation, then the corner.
+fn encode_move(mv: &Move) -> [u8; 4] {
+    let orientation = (mv.orientation.flipped as u8) << 2 | mv.orientation.rotation;
+    let piece_id = u8::try_from(mv.piece_id).expect("Piece sets stop at PieceSet::MAX_LEN.");
+    [
+        piece_id,
+        orientation,
+        mv.corner.x as i8 as u8,
+        mv.corner.y as i8 as u8,
+    ]
+}
+
+/// Reads back `encode_move`, or `None` if the bytes can't be a move. Any piece ID will do
+/// here, since only the game knows which set it's played with; `GameState::check_move` turns
+/// down pieces nobody has.
+fn decode_move([piece_id, orientation, x, y]: [u8; 4]) -> Option<Move> {
+    if orientation > 0b111 {
+        return None;
+    }
+    Some(Move {
+        piece_id: piece_id as usize,
+        orientation: Orientation::new(orientation & 0b100 != 0, orientation & 0b11),
+        corner: ivec2(x as i8 as i32, y as i8 as i32),
+    })
+}
+
+/// Writes `message` as a frame: its length as a big-endian `u16`, then the message itself.
+pub fn write_frame(writer: &mut impl Write, message: &Message) -> io::Result<()> {
+    writer.write_all(&length_prefixed(&message.encode())?)
+}
+
+fn length_prefixed(payload: &[u8]) -> io::Result<Vec<u8>> {
+    let len = u16::try_from(payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too long for a frame"))?;
+    let mut frame = Vec::with_capacity(2 + payload.len());
+    frame.extend_from_slice(&len.to_be_bytes());
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+/// Whatever frames travel over between the host and everyone else. The host and clients
+/// don't care which, so browser players can sit at the same table as everyone else.
+pub trait Transport {
+    /// Sends one whole frame.
+    fn send_frame(&mut self, frame: &[u8]) -> io::Result<()>;
+
+    /// The next whole frame, if there's one yet. Never waits around: nothing to say gives
+    /// `Ok(None)`, and a connection that's closed gives `UnexpectedEof`.
+    fn recv_frame(&mut self) -> io::Result<Option<Vec<u8>>>;
+
+    fn send(&mut self, message: &Message) -> io::Result<()> {
+        self.send_frame(&message.encode())
+    }
+
+    fn recv(&mut self) -> io::Result<Option<Message>> {
+        self.recv_frame()?
+            .map(|frame| Message::decode(&frame))
+            .transpose()
+    }
+}
+
+/// Any byte stream carries frames the same way: length first. The stream has to be
+/// non-blocking, or `recv_frame` will wait for something to show up.
+impl<S: Read + Write> Transport for FrameReader<S> {
+    fn send_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        self.inner.write_all(&length_prefixed(frame)?)
+    }
+
+    fn recv_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        self.read_frame()
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+pub type TcpTransport = FrameReader<std::net::TcpStream>;
+
+/// Browsers can't open TCP sockets, so they talk WebSocket instead. The bytes inside are just
+/// what would have gone over TCP, so a WebSocket-to-TCP bridge (websockify, say) in front of
+/// the host lets them join. Connect with `FrameReader::new(WebSocket::connect(url))`.
+#[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+pub type WebSocketTransport = FrameReader<web_socket::WebSocket>;
+
+/// The page has to load `web/blorus_websocket.js` alongside miniquad's loader for these to
+/// exist.
+#[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+pub mod web_socket {
+    use std::io::{self, Read, Write};
+
+    extern "C" {
+        /// Starts connecting to `url` and returns a handle for the socket.
+        fn blorus_ws_connect(url: *const u8, url_len: usize) -> i32;
+        /// Sends the bytes once the socket's open, or drops them if it's closed.
+        fn blorus_ws_send(socket: i32, data: *const u8, len: usize);
+        /// Copies as many received bytes as fit into `buf` and returns how many that was,
+        /// or -1 if the socket's closed and there's nothing left.
+        fn blorus_ws_recv(socket: i32, buf: *mut u8, buf_len: usize) -> i32;
+    }
+
+    /// A WebSocket, read and written like a non-blocking TCP stream.
+    pub struct WebSocket {
+        handle: i32,
+    }
+
+    impl WebSocket {
+        /// Doesn't wait for the socket to open. Anything written before then goes out
+        /// once it does.
+        pub fn connect(url: &str) -> Self {
+            let handle = unsafe { blorus_ws_connect(url.as_ptr(), url.len()) };
+            Self { handle }
+        }
+    }
+
+    impl Read for WebSocket {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            match unsafe { blorus_ws_recv(self.handle, buf.as_mut_ptr(), buf.len()) } {
+                -1 => Ok(0),
+                0 => Err(io::ErrorKind::WouldBlock.into()),
+                n => Ok(n as usize),
+            }
+        }
+    }
+
+    impl Write for WebSocket {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            unsafe { blorus_ws_send(self.handle, buf.as_ptr(), buf.len()) };
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+}
+
+/// Pulls whole frames out of a stream.
+///
+/// TCP doesn't care where our messages start and end, so a single `read` can give us half a
+/// move, or a move and a half. This holds on to whatever's left over until the rest shows up.
+pub struct FrameReader<R> {
+    inner: R,
+    buffer: Vec<u8>,
+}
+
+impl<R: Read> FrameReader<R> {
+    pub fn new(inner: R) -> Self {
+        Self {
+            inner,
+            buffer: Vec::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    pub fn get_mut(&mut self) -> &mut R {
+        &mut self.inner
+    }
+
+    /// Does at most one read on the stream and hands back the next complete frame, if there
+    /// is one yet. A non-blocking stream with nothing to say gives `Ok(None)`, and a stream
+    /// that's closed gives `UnexpectedEof`.
+    pub fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        // A previous read may have brought in more than one frame.
+        if let Some(frame) = self.take_frame() {
+            return Ok(Some(frame));
+        }
+
+        let mut scratch = [0u8; 1024];
+        match self.inner.read(&mut scratch) {
+            Ok(0) => Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(n) => {
+                self.buffer.extend_from_slice(&scratch[..n]);
+                Ok(self.take_frame())
+            }
+            Err(e) if e.kind() == io::ErrorKind::WouldBlock => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Like `read_frame`, but decodes the frame too.
+    pub fn read_message(&mut self) -> io::Result<Option<Message>> {
+        self.read_frame()?
+            .map(|frame| Message::decode(&frame))
+            .transpose()
+    }
+
+    fn take_frame(&mut self) -> Option<Vec<u8>> {
+        let len = u16::from_be_bytes([*self.buffer.first()?, *self.buffer.get(1)?]) as usize;
+        if self.buffer.len() < 2 + len {
+            return None;
+        }
+        let frame = self.buffer[2..2 + len].to_vec();
+        self.buffer.drain(..2 + len);
+        Some(frame)
+    }
+}
+
+/// Why the host turned down a move or pass from a remote player.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RejectReason {
+    /// Someone else is up.
+    NotYourTurn,
+    /// Nobody gets to move once the game is over.
+    GameOver,
+    /// The move breaks the rules, including trying to play a piece twice.
+    Illegal(MoveError),
+}
+
+// The order here decides which byte each error goes over the wire as, so only add to the end.
+const MOVE_ERRORS: [MoveError; 8] = [
+    MoveError::Overlap,
+    MoveError::AdjacentSameColor,
+    MoveError::NoDiagonalContact,
+    MoveError::OutOfBounds,
+    MoveError::NotAllowedYet,
+    MoveError::AlreadyPlayed,
+    MoveError::NotDrawn,
+    MoveError::BiggerThanLast,
+];
 
-use crate::logic::Player;
+impl RejectReason {
+    /// The byte this goes over the wire as.
+    fn code(self) -> u8 {
+        match self {
+            Self::NotYourTurn => 0,
+            Self::GameOver => 1,
+            Self::Illegal(e) => {
+                2 + MOVE_ERRORS
+                    .iter()
+                    .position(|&known| known == e)
+                    .expect("Every MoveError has a code.") as u8
+            }
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Self::NotYourTurn),
+            1 => Some(Self::GameOver),
+            _ => MOVE_ERRORS
+                .get(code as usize - 2)
+                .map(|&e| Self::Illegal(e)),
+        }
+    }
+}
+
+impl From<SubmitError> for RejectReason {
+    fn from(e: SubmitError) -> Self {
+        match e {
+            SubmitError::GameOver => Self::GameOver,
+            SubmitError::Illegal(e) => Self::Illegal(e),
+            // Only clients wait on anyone, and only the host turns moves down.
+            SubmitError::AwaitingHost => Self::NotYourTurn,
+        }
+    }
+}
+
+/// Drops control characters (so nobody can mess with the chat panel using newlines and
+/// such) and cuts the message off at `MAX_CHAT_LEN` characters.
+pub fn sanitize_chat(text: &str) -> String {
+    text.chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_CHAT_LEN)
+        .collect()
+}
+
+/// The longest prefix of `s` that fits in `max` bytes without splitting a character.
+fn truncate_bytes(s: &str, max: usize) -> &str {
+    let mut end = s.len().min(max);
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// The most recent chat messages, oldest first.
+pub struct ChatLog {
+    lines: VecDeque<String>,
+    capacity: usize,
+}
+
+impl ChatLog {
+    /// A log that remembers the last `capacity` messages.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, from: &str, text: &str) {
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(format!("{from}: {text}"));
+    }
+
+    /// Draws the log inside `rect`, newest message at the bottom. Older messages scroll
+    /// off the top once the panel fills up.
+    pub fn draw(&self, rect: Rect, font_size: f32) {
+        draw_rectangle(rect.x, rect.y, rect.w, rect.h, Color::new(0., 0., 0., 0.2));
+        let line_height = 1.2 * font_size;
+        let fits = (rect.h / line_height) as usize;
+        for (i, line) in self.lines.iter().rev().take(fits).enumerate() {
+            draw_text(
+                line,
+                rect.x + 0.25 * font_size,
+                rect.bottom() - 0.25 * font_size - i as f32 * line_height,
+                font_size,
+                BLACK,
+            );
+        }
+    }
+}
 
-// Maybe define a trait for local game state and game server so you can use the
-// same game loop in local multiplayer and online multiplayer?
+/// What someone connected to the game is doing there.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    Playing(TileColor),
+    Spectating,
+}
+
+/// How well the host is hearing from someone, going by the heartbeat.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Health {
+    /// Heard from them within the last couple of heartbeats.
+    Good,
+    /// Missed a few heartbeats.
+    Lagging,
+    /// Missed enough that they're about to be dropped.
+    Lost,
+}
+
+impl Health {
+    /// How long since we heard from someone makes for this health.
+    fn after(silence: Duration) -> Self {
+        if silence > HEARTBEAT_TIMEOUT {
+            Self::Lost
+        } else if silence > 2 * HEARTBEAT_INTERVAL {
+            Self::Lagging
+        } else {
+            Self::Good
+        }
+    }
+
+    fn code(self) -> u8 {
+        match self {
+            Self::Good => 0,
+            Self::Lagging => 1,
+            Self::Lost => 2,
+        }
+    }
+
+    fn from_code(code: u8) -> Option<Self> {
+        [Self::Good, Self::Lagging, Self::Lost]
+            .into_iter()
+            .find(|health| health.code() == code)
+    }
+}
+
+/// One line of the list of who's connected. See `GameServer::roster`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RosterEntry {
+    pub name: String,
+    pub role: Role,
+    pub health: Health,
+}
 
 enum OnlinePlayer {
     Playing {
         name: String,
-        connection: TcpStream,
+        connection: Box<dyn Transport>,
+        last_seen: Instant,
         data: Player,
+        /// Whether they're happy for the game to start.
+        ready: bool,
     },
     Spectator {
         name: String,
-        connection: TcpStream,
+        connection: Box<dyn Transport>,
+        last_seen: Instant,
     },
 }
 
 impl OnlinePlayer {
-    pub fn disconnected(&self) -> bool {
-        let mut scratch = [0u8; 1];
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Playing { name, .. } | Self::Spectator { name, .. } => name,
+        }
+    }
+
+    /// Takes the name they said hello with. Anyone playing shows up by it in the game too.
+    fn introduce(&mut self, new_name: String) {
+        if let Self::Playing { data, .. } = self {
+            data.name = Some(new_name.clone());
+        }
+        match self {
+            Self::Playing { name, .. } | Self::Spectator { name, .. } => *name = new_name,
+        }
+    }
+
+    fn connection(&mut self) -> &mut dyn Transport {
         match self {
             Self::Playing { connection, .. } | Self::Spectator { connection, .. } => {
-                // we don't care about what packets we see, just that we can't get any more.
-                connection.peek(&mut scratch).is_err()
+                connection.as_mut()
             }
         }
     }
+
+    fn last_seen(&mut self) -> &mut Instant {
+        match self {
+            Self::Playing { last_seen, .. } | Self::Spectator { last_seen, .. } => last_seen,
+        }
+    }
+
+    /// Whether we've gone too long without hearing from them. Peeking at the socket can't
+    /// tell us this reliably, so we go by the heartbeat instead.
+    pub fn disconnected(&mut self, now: Instant) -> bool {
+        now.duration_since(*self.last_seen()) > HEARTBEAT_TIMEOUT
+    }
+
+    fn roster_entry(&self, now: Instant) -> RosterEntry {
+        let (role, last_seen) = match self {
+            Self::Playing {
+                data, last_seen, ..
+            } => (Role::Playing(data.color), last_seen),
+            Self::Spectator { last_seen, .. } => (Role::Spectating, last_seen),
+        };
+        RosterEntry {
+            name: self.name().to_string(),
+            role,
+            health: Health::after(now.saturating_duration_since(*last_seen)),
+        }
+    }
 }
 
 // TODO: Maybe move this struct? Or don't, if you can make it sufficiently involve the network.
+#[derive(Default)]
 pub struct GameServer {
     players: Vec<OnlinePlayer>,
+    /// Players who dropped mid-game, by name, so they can have their seat back if they rejoin.
+    reserved_seats: Vec<(String, Player)>,
+    last_ping: Option<Instant>,
 }
 
 impl GameServer {
     pub async fn signal_advance_turn(&self) {}
+
+    /// Pings everyone if it's been `HEARTBEAT_INTERVAL` since the last round. Call this
+    /// every frame.
+    pub fn send_heartbeats(&mut self) {
+        let now = Instant::now();
+        if self
+            .last_ping
+            .is_some_and(|last| now.duration_since(last) < HEARTBEAT_INTERVAL)
+        {
+            return;
+        }
+        self.last_ping = Some(now);
+        for player in &mut self.players {
+            // A failed write shows up as a missed heartbeat soon enough.
+            let _ = player.connection().send(&Message::Ping);
+        }
+        self.broadcast_roster();
+    }
+
+    /// Lets someone in under `name`, and returns where they ended up in the roster. Anyone
+    /// coming back to a seat held for them gets it back. Otherwise they get the first color
+    /// nobody has if they `want_seat` and there's one left, and watch if not. A new seat
+    /// comes with all `piece_count` pieces of the set being played. Everyone hears about it.
+    pub fn join(
+        &mut self,
+        connection: impl Transport + 'static,
+        name: &str,
+        want_seat: bool,
+        piece_count: usize,
+    ) -> usize {
+        let name = sanitize_chat(name.trim());
+        let connection: Box<dyn Transport> = Box::new(connection);
+        let last_seen = Instant::now();
+        let held = self
+            .reserved_seats
+            .iter()
+            .position(|(held, _)| *held == name);
+        let data = match held {
+            Some(idx) => Some(self.reserved_seats.remove(idx).1),
+            None if want_seat => {
+                let seated: Vec<Player> = self
+                    .players
+                    .iter()
+                    .filter_map(|player| match player {
+                        OnlinePlayer::Playing { data, .. } => Some(data.clone()),
+                        OnlinePlayer::Spectator { .. } => None,
+                    })
+                    .chain(self.reserved_seats.iter().map(|(_, data)| data.clone()))
+                    .collect();
+                lobby::first_unused_color(&seated).map(|color| Player {
+                    name: Some(name.clone()),
+                    ..Player::new(color, piece_count)
+                })
+            }
+            None => None,
+        };
+        self.players.push(match data {
+            Some(data) => OnlinePlayer::Playing {
+                name,
+                connection,
+                last_seen,
+                data,
+                ready: false,
+            },
+            None => OnlinePlayer::Spectator {
+                name,
+                connection,
+                last_seen,
+            },
+        });
+        self.broadcast_roster();
+        self.players.len() - 1
+    }
+
+    /// Everyone connected, in the order they joined, with what they're doing and how well
+    /// we're hearing from them.
+    pub fn roster(&self) -> Vec<RosterEntry> {
+        let now = Instant::now();
+        self.players
+            .iter()
+            .map(|player| player.roster_entry(now))
+            .collect()
+    }
+
+    /// Tells everyone who's connected.
+    fn broadcast_roster(&mut self) {
+        let roster = Message::Roster(self.roster());
+        self.broadcast(&roster);
+    }
+
+    /// Reads whatever everyone has sent us. Any frame at all counts as a sign of life.
+    /// Heartbeats, hellos and ready toggles are dealt with here, and everything else is handed back
+    /// along with the sender's index. Assumes the connections are non-blocking.
+    pub fn poll(&mut self) -> Vec<(usize, Message)> {
+        let mut received = Vec::new();
+        let mut toggles = Vec::new();
+        let mut renamed = false;
+        for (i, player) in self.players.iter_mut().enumerate() {
+            while let Ok(Some(message)) = player.connection().recv() {
+                *player.last_seen() = Instant::now();
+                match message {
+                    Message::Ping => {
+                        let _ = player.connection().send(&Message::Pong);
+                    }
+                    Message::Pong => {}
+                    Message::Ready { ready, .. } => toggles.push((i, ready)),
+                    Message::Hello { name } if !name.is_empty() => {
+                        player.introduce(name);
+                        renamed = true;
+                    }
+                    message => received.push((i, message)),
+                }
+            }
+        }
+        for (sender, ready) in toggles {
+            self.set_ready(sender, ready);
+        }
+        if renamed {
+            self.broadcast_roster();
+        }
+        received
+    }
+
+    /// Marks the player at `idx` as ready or not and lets everyone know. Spectators don't
+    /// get a say.
+    pub fn set_ready(&mut self, idx: usize, now_ready: bool) {
+        if let OnlinePlayer::Playing { ready, .. } = &mut self.players[idx] {
+            *ready = now_ready;
+            self.broadcast(&Message::Ready {
+                seat: idx as u8,
+                ready: now_ready,
+            });
+        }
+    }
+
+    /// Whether everyone with a seat is ready to start. The host's "Begin!" stays disabled
+    /// until they are.
+    pub fn all_ready(&self) -> bool {
+        self.players.iter().all(|player| match player {
+            OnlinePlayer::Playing { ready, .. } => *ready,
+            OnlinePlayer::Spectator { .. } => true,
+        })
+    }
+
+    /// Kicks out everyone who missed their heartbeat and returns their names. Anyone who was
+    /// playing gets their seat held in case they come back.
+    pub fn prune_disconnected(&mut self) -> Vec<String> {
+        let now = Instant::now();
+        let mut dropped = Vec::new();
+        let mut i = 0;
+        while i < self.players.len() {
+            if !self.players[i].disconnected(now) {
+                i += 1;
+                continue;
+            }
+            let player = self.players.remove(i);
+            dropped.push(player.name().to_string());
+            if let OnlinePlayer::Playing { name, data, .. } = player {
+                self.reserved_seats.push((name, data));
+            }
+        }
+        if !dropped.is_empty() {
+            self.broadcast_roster();
+        }
+        dropped
+    }
+
+    /// Sends `message` to everyone in the lobby, players and spectators alike.
+    pub fn broadcast(&mut self, message: &Message) {
+        for player in &mut self.players {
+            // If this fails, they're gone, and the heartbeat will notice soon enough.
+            let _ = player.connection().send(message);
+        }
+    }
+
+    /// Sends a chat message to everyone in the lobby.
+    pub fn broadcast_chat(&mut self, from: &str, text: &str) {
+        self.broadcast(&Message::chat(from, text));
+    }
+
+    /// The color the player at `idx` plays, or `None` if they're only watching.
+    fn color_of(&self, idx: usize) -> Option<TileColor> {
+        match &self.players[idx] {
+            OnlinePlayer::Playing { data, .. } => Some(data.color),
+            OnlinePlayer::Spectator { .. } => None,
+        }
+    }
+}
+
+/// A game hosted on this machine, with some of the players joining over the network.
+/// The host keeps the real game state and tells everyone else what happens, as the
+/// `GameEvent`s each move or pass set off. Clients play those back on their own copy (see
+/// `ClientSession`).
+pub struct NetSession {
+    server: GameServer,
+    game_state: GameState,
+    chat: ChatLog,
+    history: UndoHistory,
+    /// Someone wanting their last move back, while everyone else makes up their minds.
+    undo_vote: Option<UndoVote>,
+    /// How our own last undo request went, until the game loop asks.
+    undo_answer: Option<bool>,
+}
+
+/// An undo waiting on everyone else still playing to agree to it.
+struct UndoVote {
+    /// Whose move it'd take back.
+    mover: usize,
+    /// The colors that haven't said yes yet.
+    waiting: Vec<TileColor>,
+}
+
+impl NetSession {
+    pub fn new(server: GameServer, game_state: GameState) -> Self {
+        Self {
+            server,
+            game_state,
+            chat: ChatLog::new(8),
+            history: UndoHistory::default(),
+            undo_vote: None,
+            undo_answer: None,
+        }
+    }
+
+    pub fn chat(&self) -> &ChatLog {
+        &self.chat
+    }
+
+    /// Who's connected (see `GameServer::roster`).
+    pub fn roster(&self) -> Vec<RosterEntry> {
+        self.server.roster()
+    }
+
+    /// Plays a move someone sent us, after making sure it's theirs to make and legal.
+    /// We never take a client's word for it: if anything's off, they get told why and the
+    /// game carries on as if they never sent it.
+    pub fn receive_move(&mut self, sender: usize, mv: Move) -> Result<(), RejectReason> {
+        let result = self
+            .check_turn(sender)
+            .and_then(|()| self.submit_move(mv).map_err(RejectReason::from));
+        self.reply_if_rejected(sender, result)
+    }
+
+    /// Like `receive_move`, for passing.
+    pub fn receive_pass(&mut self, sender: usize) -> Result<(), RejectReason> {
+        let result = self.check_turn(sender).and_then(|()| {
+            if self.game_state.is_game_over() {
+                Err(RejectReason::GameOver)
+            } else if !self.game_state.may_pass() {
+                Err(RejectReason::Illegal(MoveError::NotAllowedYet))
+            } else {
+                self.pass_turn();
+                Ok(())
+            }
+        });
+        self.reply_if_rejected(sender, result)
+    }
+
+    /// Like `receive_move`, for taking the opening under the pie rule.
+    pub fn receive_swap(&mut self, sender: usize) -> Result<(), RejectReason> {
+        let result = self.check_turn(sender).and_then(|()| {
+            if self.game_state.pie_swap_available() {
+                self.swap_seats();
+                Ok(())
+            } else {
+                Err(RejectReason::Illegal(MoveError::NotAllowedYet))
+            }
+        });
+        self.reply_if_rejected(sender, result)
+    }
+
+    /// Someone over the network giving up. It doesn't have to be their turn, but the only
+    /// seat they can give up is their own.
+    pub fn receive_forfeit(&mut self, sender: usize) {
+        let Some(color) = self.server.color_of(sender) else {
+            return;
+        };
+        if let Some(idx) = self
+            .game_state
+            .players
+            .iter()
+            .position(|p| p.color == color)
+        {
+            self.forfeit(idx);
+        }
+    }
+
+    /// Someone over the network wanting their last move back. Only whoever made it gets to
+    /// ask, and only one undo gets voted on at a time.
+    pub fn receive_undo_request(&mut self, sender: usize) {
+        let mover = self.history.last_mover();
+        let theirs = mover.is_some_and(|mover| {
+            self.server.color_of(sender) == Some(self.game_state.players[mover].color)
+        });
+        match mover {
+            Some(mover) if theirs && self.undo_vote.is_none() => self.start_undo_vote(mover),
+            _ => {
+                let no = Message::UndoDecided { approved: false };
+                let _ = self.server.players[sender].connection().send(&no);
+            }
+        }
+    }
+
+    /// Someone over the network voting on the undo in progress.
+    pub fn receive_undo_vote(&mut self, sender: usize, approve: bool) {
+        if let Some(color) = self.server.color_of(sender) {
+            self.cast_undo_vote(&[color], approve);
+        }
+    }
+
+    /// Asks everyone still playing, other than `mover`, whether `mover` can take their last
+    /// move back. Bots on this end don't mind.
+    fn start_undo_vote(&mut self, mover: usize) {
+        let waiting: Vec<TileColor> = self
+            .game_state
+            .players
+            .iter()
+            .enumerate()
+            .filter(|&(idx, player)| {
+                idx != mover && player.still_playing() && !player.is_neutral && !player.is_bot
+            })
+            .map(|(_, player)| player.color)
+            .collect();
+        let ask = Message::UndoRequest { seat: mover as u8 };
+        for idx in 0..self.server.players.len() {
+            if self
+                .server
+                .color_of(idx)
+                .is_some_and(|c| waiting.contains(&c))
+            {
+                let _ = self.server.players[idx].connection().send(&ask);
+            }
+        }
+        self.undo_vote = Some(UndoVote { mover, waiting });
+        self.cast_undo_vote(&[], true);
+    }
+
+    /// Counts a vote from whoever plays `voters`. A single no settles it, and so does the
+    /// last yes.
+    fn cast_undo_vote(&mut self, voters: &[TileColor], approve: bool) {
+        let Some(vote) = &mut self.undo_vote else {
+            return;
+        };
+        if !approve && vote.waiting.iter().any(|c| voters.contains(c)) {
+            return self.settle_undo_vote(false);
+        }
+        vote.waiting.retain(|c| !voters.contains(c));
+        if vote.waiting.is_empty() {
+            self.settle_undo_vote(true);
+        }
+    }
+
+    /// Tells everyone how the undo vote went, and takes the move back if it passed.
+    fn settle_undo_vote(&mut self, approved: bool) {
+        let Some(vote) = self.undo_vote.take() else {
+            return;
+        };
+        if !self.is_remote(vote.mover) {
+            self.undo_answer = Some(approved);
+        }
+        self.server.broadcast(&Message::UndoDecided { approved });
+        if approved && self.history.undo_last(&mut self.game_state) {
+            let state = Message::State(self.game_state.to_save_string());
+            self.server.broadcast(&state);
+        }
+    }
+
+    /// The colors played on this end that are still waiting to vote.
+    fn local_voters(&self) -> Vec<TileColor> {
+        let Some(vote) = &self.undo_vote else {
+            return Vec::new();
+        };
+        (0..self.game_state.players.len())
+            .filter(|&idx| !self.is_remote(idx))
+            .map(|idx| self.game_state.players[idx].color)
+            .filter(|color| vote.waiting.contains(color))
+            .collect()
+    }
+
+    fn check_turn(&self, sender: usize) -> Result<(), RejectReason> {
+        if self.server.color_of(sender) == Some(self.game_state.current_player().color) {
+            Ok(())
+        } else {
+            Err(RejectReason::NotYourTurn)
+        }
+    }
+
+    /// Tells everyone about the events since the first `seen` pending ones. The game loop
+    /// drains them for itself later, so they have to stay put.
+    fn broadcast_events_since(&mut self, seen: usize) {
+        let events = self.game_state.pending_events()[seen..].to_vec();
+        for event in events {
+            self.server.broadcast(&Message::Event(event));
+        }
+    }
+
+    /// Sends the player at `idx` the whole game, for when their copy has gone wrong.
+    fn send_state(&mut self, idx: usize) {
+        let state = Message::State(self.game_state.to_save_string());
+        let _ = self.server.players[idx].connection().send(&state);
+    }
+
+    fn reply_if_rejected(
+        &mut self,
+        sender: usize,
+        result: Result<(), RejectReason>,
+    ) -> Result<(), RejectReason> {
+        if let Err(reason) = result {
+            let _ = self.server.players[sender]
+                .connection()
+                .send(&Message::Reject(reason));
+        }
+        result
+    }
+}
+
+impl Session for NetSession {
+    fn current_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    fn current_state_mut(&mut self) -> &mut GameState {
+        &mut self.game_state
+    }
+
+    /// Playing on means the move being voted on isn't the last one anymore, so that vote's off.
+    fn submit_move(&mut self, mv: Move) -> Result<(), SubmitError> {
+        check_submission(&self.game_state, &mv)?;
+        self.settle_undo_vote(false);
+        self.history.record(&self.game_state);
+        let seen = self.game_state.pending_events().len();
+        self.game_state.apply_move(&mv);
+        self.broadcast_events_since(seen);
+        Ok(())
+    }
+
+    fn pass_turn(&mut self) {
+        self.settle_undo_vote(false);
+        self.history.record(&self.game_state);
+        let seen = self.game_state.pending_events().len();
+        self.game_state.pass_turn();
+        self.broadcast_events_since(seen);
+    }
+
+    fn swap_seats(&mut self) {
+        self.settle_undo_vote(false);
+        self.history.clear();
+        let (first, second) = (
+            self.game_state.players[0].color,
+            self.game_state.players[1].color,
+        );
+        self.game_state.swap_seats();
+        // Whoever was playing one color over the network is now playing the other.
+        for player in &mut self.server.players {
+            if let OnlinePlayer::Playing { data, .. } = player {
+                if data.color == first {
+                    data.color = second;
+                } else if data.color == second {
+                    data.color = first;
+                }
+            }
+        }
+        self.server.broadcast(&Message::SwapSeats);
+    }
+
+    fn forfeit(&mut self, player_idx: usize) {
+        self.settle_undo_vote(false);
+        self.history.clear();
+        let seen = self.game_state.pending_events().len();
+        self.game_state.forfeit(player_idx);
+        self.broadcast_events_since(seen);
+    }
+
+    /// Everyone gets the whole new game, the same as when their copy goes wrong.
+    fn rematch(&mut self, rotate_seats: bool) -> bool {
+        self.settle_undo_vote(false);
+        self.history.clear();
+        self.game_state = self.game_state.rematch(rotate_seats);
+        let state = Message::State(self.game_state.to_save_string());
+        self.server.broadcast(&state);
+        true
+    }
+
+    /// Only if the last move was made here, by a person.
+    fn request_undo(&mut self) -> bool {
+        match self.history.last_mover() {
+            Some(mover)
+                if self.undo_vote.is_none()
+                    && !self.is_remote(mover)
+                    && !self.game_state.players[mover].is_bot =>
+            {
+                self.start_undo_vote(mover);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn undo_vote_pending(&self) -> Option<usize> {
+        let vote = self.undo_vote.as_ref()?;
+        (!self.local_voters().is_empty()).then_some(vote.mover)
+    }
+
+    /// Everyone playing on this end votes together.
+    fn vote_undo(&mut self, approve: bool) {
+        let voters = self.local_voters();
+        self.cast_undo_vote(&voters, approve);
+    }
+
+    fn take_undo_answer(&mut self) -> Option<bool> {
+        self.undo_answer.take()
+    }
+
+    fn poll_remote(&mut self) {
+        self.server.send_heartbeats();
+        for (sender, message) in self.server.poll() {
+            match message {
+                // They've already been told if these didn't go through.
+                Message::Move(mv) => {
+                    let _ = self.receive_move(sender, mv);
+                }
+                Message::Pass => {
+                    let _ = self.receive_pass(sender);
+                }
+                Message::SwapSeats => {
+                    let _ = self.receive_swap(sender);
+                }
+                Message::Forfeit => self.receive_forfeit(sender),
+                Message::Chat { from, text } => {
+                    self.chat.push(&from, &text);
+                    self.server.broadcast_chat(&from, &text);
+                }
+                Message::Resync => self.send_state(sender),
+                Message::UndoRequest { .. } => self.receive_undo_request(sender),
+                Message::UndoVote { approve } => self.receive_undo_vote(sender, approve),
+                _ => {}
+            }
+        }
+        self.server.prune_disconnected();
+    }
+
+    fn is_remote(&self, player_idx: usize) -> bool {
+        let color = self.game_state.players[player_idx].color;
+        (0..self.server.players.len()).any(|idx| self.server.color_of(idx) == Some(color))
+    }
+}
+
+/// Our end of a game someone else is hosting. We keep a copy of the game to draw and to aim
+/// pieces on, but it only ever changes by playing back what the host says happened. Moves
+/// and passes go off to the host, and nothing happens here until it tells us how they went.
+pub struct ClientSession {
+    host: Box<dyn Transport>,
+    /// Our copy of the host's game.
+    mirror: GameState,
+    /// The color we're playing.
+    color: TileColor,
+    /// Whether we've sent a move, pass or swap and haven't heard back yet.
+    awaiting_host: bool,
+    /// Whether we've asked for the whole game and are ignoring events until it shows up.
+    resyncing: bool,
+    /// Why the host turned down our last move or pass, if it did.
+    last_rejection: Option<RejectReason>,
+    chat: ChatLog,
+    /// Who the host last said was connected.
+    roster: Vec<RosterEntry>,
+    /// Whose undo the host wants our vote on.
+    undo_vote: Option<usize>,
+    /// Whether we've asked to undo and haven't heard how it went.
+    asked_undo: bool,
+    undo_answer: Option<bool>,
+}
+
+impl ClientSession {
+    /// Joins in on `game_state` as `color`, and tells the host to call us `name`.
+    pub fn new(
+        host: impl Transport + 'static,
+        game_state: GameState,
+        color: TileColor,
+        name: &str,
+    ) -> Self {
+        let mut session = Self {
+            host: Box::new(host),
+            mirror: game_state,
+            color,
+            awaiting_host: false,
+            resyncing: false,
+            last_rejection: None,
+            chat: ChatLog::new(8),
+            roster: Vec::new(),
+            undo_vote: None,
+            asked_undo: false,
+            undo_answer: None,
+        };
+        session.send(&Message::hello(name));
+        session
+    }
+
+    pub fn chat(&self) -> &ChatLog {
+        &self.chat
+    }
+
+    pub fn last_rejection(&self) -> Option<RejectReason> {
+        self.last_rejection
+    }
+
+    /// Who's connected, as of the host's last word on it.
+    pub fn roster(&self) -> &[RosterEntry] {
+        &self.roster
+    }
+
+    fn send(&mut self, message: &Message) {
+        // If this fails, the host is gone, and there's nothing to do about it from here.
+        let _ = self.host.send(message);
+    }
+
+    /// Whether we're free to ask the host for anything.
+    fn check_not_waiting(&self) -> Result<(), SubmitError> {
+        if self.awaiting_host || self.resyncing {
+            Err(SubmitError::AwaitingHost)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sends `message` to the host and holds off on anything else until it answers.
+    fn ask_host(&mut self, message: &Message) -> Result<(), SubmitError> {
+        self.check_not_waiting()?;
+        self.send(message);
+        self.awaiting_host = true;
+        self.last_rejection = None;
+        Ok(())
+    }
+
+    /// Plays `event` from the host on our copy of the game, and says whether our copy still
+    /// agrees with the host's.
+    fn play_back(&mut self, event: GameEvent) -> bool {
+        let mirror = &mut self.mirror;
+        match event {
+            GameEvent::PiecePlaced { player, mv } => {
+                if mirror.players.get(player).map(|p| p.color) == Some(self.color) {
+                    self.awaiting_host = false;
+                }
+                mirror.current_player == player && mirror.try_apply_move(&mv).is_ok()
+            }
+            GameEvent::Passed { player } => {
+                if mirror.players.get(player).map(|p| p.color) == Some(self.color) {
+                    self.awaiting_host = false;
+                }
+                let in_sync = mirror.current_player == player && !mirror.is_game_over();
+                if in_sync {
+                    mirror.pass_turn();
+                }
+                in_sync
+            }
+            GameEvent::Forfeited { player } => {
+                let in_sync = mirror.players.get(player).is_some_and(|p| !p.forfeited);
+                if in_sync {
+                    mirror.forfeit(player);
+                }
+                in_sync
+            }
+            GameEvent::TurnEnded { next } => {
+                // Someone stopping short of `pieces_per_turn` doesn't pass, so this is the
+                // only word we get of it.
+                if mirror.current_player != next && mirror.placements_this_turn > 0 {
+                    mirror.pass_turn();
+                }
+                mirror.current_player == next
+            }
+            GameEvent::GameOver { .. } => mirror.is_game_over(),
+        }
+    }
+}
+
+impl Session for ClientSession {
+    fn current_state(&self) -> &GameState {
+        &self.mirror
+    }
+
+    fn current_state_mut(&mut self) -> &mut GameState {
+        &mut self.mirror
+    }
+
+    /// Sends `mv` off to the host, once we've checked it's worth the trip. It doesn't go on
+    /// the board until the host says so.
+    fn submit_move(&mut self, mv: Move) -> Result<(), SubmitError> {
+        self.check_not_waiting()?;
+        check_submission(&self.mirror, &mv)?;
+        self.ask_host(&Message::Move(mv))
+    }
+
+    fn pass_turn(&mut self) {
+        let _ = self.ask_host(&Message::Pass);
+    }
+
+    fn swap_seats(&mut self) {
+        let _ = self.ask_host(&Message::SwapSeats);
+    }
+
+    /// Only our own seat is ours to give up. The host tells us once it's done.
+    fn forfeit(&mut self, player_idx: usize) {
+        if self.mirror.players[player_idx].color == self.color {
+            self.send(&Message::Forfeit);
+        }
+    }
+
+    /// That's up to the host. Its new game shows up the same way a resync does.
+    fn rematch(&mut self, _rotate_seats: bool) -> bool {
+        false
+    }
+
+    /// The host decides whether it was ours to take back, and asks everyone else.
+    fn request_undo(&mut self) -> bool {
+        if self.asked_undo {
+            return false;
+        }
+        self.send(&Message::UndoRequest { seat: 0 });
+        self.asked_undo = true;
+        true
+    }
+
+    fn undo_vote_pending(&self) -> Option<usize> {
+        self.undo_vote
+    }
+
+    fn vote_undo(&mut self, approve: bool) {
+        if self.undo_vote.take().is_some() {
+            self.send(&Message::UndoVote { approve });
+        }
+    }
+
+    fn take_undo_answer(&mut self) -> Option<bool> {
+        self.undo_answer.take()
+    }
+
+    fn poll_remote(&mut self) {
+        while let Ok(Some(message)) = self.host.recv() {
+            match message {
+                // Anything from before the full state shows up is already in it.
+                Message::Event(_) | Message::SwapSeats if self.resyncing => {}
+                Message::Event(event) => {
+                    let in_sync = self.play_back(event);
+                    if !in_sync {
+                        self.resyncing = true;
+                        self.send(&Message::Resync);
+                    }
+                }
+                Message::SwapSeats => {
+                    let (first, second) =
+                        (self.mirror.players[0].color, self.mirror.players[1].color);
+                    self.mirror.swap_seats();
+                    if self.color == first {
+                        self.color = second;
+                    } else if self.color == second {
+                        self.color = first;
+                    }
+                    self.awaiting_host = false;
+                }
+                Message::State(save) => match GameState::from_save_string(&save) {
+                    Ok(game_state) => {
+                        self.mirror = game_state;
+                        self.resyncing = false;
+                        self.awaiting_host = false;
+                    }
+                    // Mangled somehow, so try again.
+                    Err(_) => self.send(&Message::Resync),
+                },
+                Message::Reject(reason) => {
+                    self.last_rejection = Some(reason);
+                    self.awaiting_host = false;
+                }
+                Message::Chat { from, text } => self.chat.push(&from, &text),
+                Message::Roster(roster) => self.roster = roster,
+                Message::UndoRequest { seat } => self.undo_vote = Some(seat as usize),
+                // If it went through, the game it went back to is on its way.
+                Message::UndoDecided { approved } => {
+                    self.undo_vote = None;
+                    if std::mem::take(&mut self.asked_undo) {
+                        self.undo_answer = Some(approved);
+                    }
+                }
+                Message::Ping => self.send(&Message::Pong),
+                _ => {}
+            }
+        }
+    }
+
+    fn is_remote(&self, player_idx: usize) -> bool {
+        self.mirror.players[player_idx].color != self.color
+    }
+
+    fn keeps_time(&self) -> bool {
+        false
+    }
 }
 
 pub async fn create_lobby(_port: u32) -> GameServer {
@@ -51,3 +1637,576 @@ pub async fn join_lobby(_addr: Ipv4Addr, _port: u32) {
     // Then you can jump right back in w/o any trouble.
     // The game will skip your turn while you are away.
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{cell::RefCell, io::Cursor, net::TcpStream, rc::Rc};
+
+    #[test]
+    fn interleave_moves_and_chat() {
+        let first = Message::Move(Move {
+            piece_id: 20,
+            orientation: Orientation::new(true, 3),
+            corner: ivec2(-2, 17),
+        });
+        let chat = Message::chat("Blue", "nice\nmove\u{7}");
+        let second = Message::Move(Move {
+            piece_id: 0,
+            orientation: Orientation::default(),
+            corner: ivec2(0, 0),
+        });
+
+        let mut stream = Vec::new();
+        for message in [&first, &chat, &second] {
+            stream.extend(message.encode());
+        }
+        let mut reader = Cursor::new(stream);
+        assert_eq!(Message::read_from(&mut reader).unwrap(), first);
+        assert_eq!(
+            Message::read_from(&mut reader).unwrap(),
+            Message::Chat {
+                from: "Blue".to_string(),
+                text: "nicemove".to_string()
+            }
+        );
+        assert_eq!(Message::read_from(&mut reader).unwrap(), second);
+        assert!(Message::read_from(&mut reader).is_err());
+    }
+
+    #[test]
+    fn long_chat_gets_cut_off() {
+        let rant = "a".repeat(3 * MAX_CHAT_LEN);
+        assert_eq!(sanitize_chat(&rant).len(), MAX_CHAT_LEN);
+
+        let mut log = ChatLog::new(2);
+        for text in ["one", "two", "three"] {
+            log.push("Red", text);
+        }
+        assert_eq!(log.lines, ["Red: two", "Red: three"]);
+    }
+
+    /// Hands out one byte per `read`, like the stingiest possible network.
+    struct Trickle(Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let len = buf.len().min(1);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn frames_survive_fragmentation() {
+        let messages = [
+            Message::Move(Move {
+                piece_id: 7,
+                orientation: Orientation::new(false, 1),
+                corner: ivec2(3, 4),
+            }),
+            Message::chat("Green", "gg"),
+            Message::Move(Move {
+                piece_id: 0,
+                orientation: Orientation::default(),
+                corner: ivec2(19, 19),
+            }),
+        ];
+        let mut stream = Vec::new();
+        for message in &messages {
+            write_frame(&mut stream, message).unwrap();
+        }
+        let total_bytes = stream.len();
+
+        let mut reader = FrameReader::new(Trickle(Cursor::new(stream)));
+        let mut received = Vec::new();
+        let mut reads = 0;
+        while received.len() < messages.len() {
+            reads += 1;
+            if let Some(message) = reader.read_message().unwrap() {
+                received.push(message);
+            }
+        }
+        assert_eq!(received, messages);
+        assert_eq!(reads, total_bytes);
+        assert_eq!(
+            reader.read_frame().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+
+        // Everything arriving at once works just as well.
+        let mut stream = Vec::new();
+        for message in &messages {
+            write_frame(&mut stream, message).unwrap();
+        }
+        let mut reader = FrameReader::new(Cursor::new(stream));
+        for message in &messages {
+            assert_eq!(reader.read_message().unwrap().as_ref(), Some(message));
+        }
+    }
+
+    /// A server with a player for each of `seats`, all connected over loopback, along with
+    /// the client end of each connection.
+    fn loopback_server(
+        seats: &[(&str, TileColor)],
+        last_seen: Instant,
+    ) -> (GameServer, Vec<TcpStream>) {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut clients = Vec::new();
+        let mut players = Vec::new();
+        for &(name, color) in seats {
+            clients.push(TcpStream::connect(addr).unwrap());
+            let (server_side, _) = listener.accept().unwrap();
+            server_side.set_nonblocking(true).unwrap();
+            players.push(OnlinePlayer::Playing {
+                name: name.to_string(),
+                connection: Box::new(FrameReader::new(server_side)),
+                last_seen,
+                data: Player::new(color, crate::piece::SHAPES.len()),
+                ready: false,
+            });
+        }
+        let server = GameServer {
+            players,
+            reserved_seats: Vec::new(),
+            last_ping: None,
+        };
+        (server, clients)
+    }
+
+    /// Reads what the server sent `client` until it turns something down.
+    fn next_rejection(client: &TcpStream) -> RejectReason {
+        let mut reader = FrameReader::new(client);
+        loop {
+            if let Some(Message::Reject(reason)) = reader.read_message().unwrap() {
+                return reason;
+            }
+        }
+    }
+
+    #[test]
+    fn missed_heartbeat_gets_pruned() {
+        // Both of them were last heard from a long time ago...
+        let long_ago = Instant::now() - 2 * HEARTBEAT_TIMEOUT;
+        let seats = [("Alive", TileColor::Blue), ("Asleep", TileColor::Yellow)];
+        let (mut server, mut clients) = loopback_server(&seats, long_ago);
+
+        // ...but only one answers the ping.
+        server.send_heartbeats();
+        write_frame(&mut clients[0], &Message::Pong).unwrap();
+        for _ in 0..100 {
+            server.poll();
+            if !server.players[0].disconnected(Instant::now()) {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        assert_eq!(server.prune_disconnected(), ["Asleep"]);
+        assert_eq!(server.players.len(), 1);
+        assert_eq!(server.players[0].name(), "Alive");
+        assert_eq!(server.reserved_seats.len(), 1);
+        assert_eq!(server.reserved_seats[0].1.color, TileColor::Yellow);
+        assert!(server.prune_disconnected().is_empty());
+    }
+
+    #[test]
+    fn begin_waits_for_everyone() {
+        let seats = [("Blue", TileColor::Blue), ("Yellow", TileColor::Yellow)];
+        let (mut server, mut clients) = loopback_server(&seats, Instant::now());
+        assert!(!server.all_ready());
+
+        // Whatever seat the client claims, the host knows who really sent it.
+        let ready_up = |client: &mut TcpStream| {
+            let toggle = Message::Ready {
+                seat: 3,
+                ready: true,
+            };
+            write_frame(client, &toggle).unwrap();
+        };
+        let poll_until = |server: &mut GameServer, idx: usize| {
+            for _ in 0..100 {
+                server.poll();
+                if matches!(
+                    server.players[idx],
+                    OnlinePlayer::Playing { ready: true, .. }
+                ) {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            panic!("Never heard from seat {idx}.");
+        };
+
+        ready_up(&mut clients[1]);
+        poll_until(&mut server, 1);
+        assert!(!server.all_ready());
+
+        ready_up(&mut clients[0]);
+        poll_until(&mut server, 0);
+        assert!(server.all_ready());
+
+        // Everyone hears about both, with the right seats filled in.
+        let mut reader = FrameReader::new(&clients[1]);
+        let mut heard = Vec::new();
+        while heard.len() < 2 {
+            if let Some(Message::Ready { seat, ready }) = reader.read_message().unwrap() {
+                heard.push((seat, ready));
+            }
+        }
+        assert_eq!(heard, [(1, true), (0, true)]);
+
+        // Changing your mind holds everyone up again.
+        server.set_ready(0, false);
+        assert!(!server.all_ready());
+    }
+
+    #[test]
+    fn hello_names_the_seat() {
+        let hello = Message::hello("  Ada\n");
+        assert_eq!(Message::decode(&hello.encode()).unwrap(), hello);
+
+        let seats = [("Blue", TileColor::Blue)];
+        let (mut server, mut clients) = loopback_server(&seats, Instant::now());
+        write_frame(&mut clients[0], &hello).unwrap();
+        for _ in 0..100 {
+            server.poll();
+            if server.players[0].name() == "Ada" {
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(server.players[0].name(), "Ada");
+        let OnlinePlayer::Playing { data, .. } = &server.players[0] else {
+            panic!("Still has a seat.");
+        };
+        assert_eq!(data.display_name(), "Ada");
+    }
+
+    #[test]
+    fn joining_says_hello() {
+        let mut server = GameServer::default();
+        let (host_side, client_side) = memory_pair();
+        server.join(host_side, "Guest", true, crate::piece::SHAPES.len());
+        let mut client = ClientSession::new(client_side, GameState::new(2), TileColor::Blue, "Ada");
+
+        // The host goes by the name the client gave, and tells everyone, the client included.
+        server.poll();
+        assert_eq!(server.players[0].name(), "Ada");
+        client.poll_remote();
+        assert_eq!(client.roster()[0].name, "Ada");
+        assert_eq!(client.roster()[0].role, Role::Playing(TileColor::Blue));
+    }
+
+    #[test]
+    fn reject_out_of_turn_moves() {
+        let seats = [("Blue", TileColor::Blue), ("Yellow", TileColor::Yellow)];
+        let (server, clients) = loopback_server(&seats, Instant::now());
+        let mut session = NetSession::new(server, GameState::new(2));
+
+        // Blue's opener is perfectly legal, it just isn't Yellow's to make.
+        let opener = session.current_state().enumerate_legal_moves()[0];
+        assert_eq!(
+            session.receive_move(1, opener),
+            Err(RejectReason::NotYourTurn)
+        );
+        assert_eq!(session.receive_pass(1), Err(RejectReason::NotYourTurn));
+        assert_eq!(next_rejection(&clients[1]), RejectReason::NotYourTurn);
+        assert_eq!(session.current_state().current_player, 0);
+        assert_eq!(session.current_state().players[0].pieces_placed, 0);
+
+        assert_eq!(session.receive_move(0, opener), Ok(()));
+        assert_eq!(session.current_state().current_player, 1);
+    }
+
+    #[test]
+    fn reject_pieces_already_played() {
+        let seats = [("Blue", TileColor::Blue), ("Yellow", TileColor::Yellow)];
+        let (server, clients) = loopback_server(&seats, Instant::now());
+        let mut session = NetSession::new(server, GameState::new(2));
+
+        for sender in 0..2 {
+            let mv = crate::ai::choose_move(session.current_state()).unwrap();
+            assert_eq!(session.receive_move(sender, mv), Ok(()));
+        }
+
+        // Blue tries to put their first piece down again somewhere it would otherwise fit.
+        let used = session.current_state().players[0].last_piece.unwrap();
+        let mut cheat = session.current_state().enumerate_legal_moves()[0];
+        cheat.piece_id = used;
+        let board = session.current_state().board.clone();
+        assert_eq!(
+            session.receive_move(0, cheat),
+            Err(RejectReason::Illegal(MoveError::AlreadyPlayed))
+        );
+        assert_eq!(
+            next_rejection(&clients[0]),
+            RejectReason::Illegal(MoveError::AlreadyPlayed)
+        );
+        assert_eq!(session.current_state().board, board);
+        assert_eq!(session.current_state().current_player, 0);
+
+        // Pieces past the end of the set make it over the wire fine, since some sets have
+        // them, but nobody here has one to play.
+        cheat.piece_id = crate::piece::SHAPES.len();
+        let sent = Message::Move(cheat);
+        assert_eq!(Message::decode(&sent.encode()).unwrap(), sent);
+        assert_eq!(
+            session.receive_move(0, cheat),
+            Err(RejectReason::Illegal(MoveError::AlreadyPlayed))
+        );
+    }
+
+    #[test]
+    fn client_mirrors_follow_the_host() {
+        let seats = [("Blue", TileColor::Blue), ("Yellow", TileColor::Yellow)];
+        let (server, streams) = loopback_server(&seats, Instant::now());
+        let mut host = NetSession::new(server, GameState::new(2));
+        let mut clients: Vec<_> = streams
+            .into_iter()
+            .zip(seats)
+            .map(|(stream, (name, color))| {
+                stream.set_nonblocking(true).unwrap();
+                ClientSession::new(FrameReader::new(stream), GameState::new(2), color, name)
+            })
+            .collect();
+
+        // Polls everyone until every copy of the game matches the host's again.
+        let settle = |host: &mut NetSession, clients: &mut [ClientSession]| {
+            for _ in 0..100 {
+                host.poll_remote();
+                for client in clients.iter_mut() {
+                    client.poll_remote();
+                }
+                let truth = host.current_state().to_save_string();
+                if clients.iter().all(|client| {
+                    !client.awaiting_host
+                        && !client.resyncing
+                        && client.current_state().to_save_string() == truth
+                }) {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            panic!("The mirrors never caught up.");
+        };
+
+        for turn in 0..3 {
+            let seat = turn % 2;
+            let mv = crate::ai::choose_move(clients[seat].current_state()).unwrap();
+            assert_eq!(clients[seat].submit_move(mv), Ok(()));
+            // Nothing happens until the host says so, and there's no squeezing in another
+            // move while we wait.
+            assert_eq!(clients[seat].current_state().current_player, seat);
+            assert_eq!(
+                clients[seat].submit_move(mv),
+                Err(SubmitError::AwaitingHost)
+            );
+            settle(&mut host, &mut clients);
+        }
+        assert_eq!(host.current_state().players[0].pieces_placed, 2);
+        assert_eq!(host.current_state().players[1].pieces_placed, 1);
+        // The mirrors fire the same events the host's game did, for the sounds and such.
+        let placed = clients[1]
+            .current_state_mut()
+            .drain_events()
+            .into_iter()
+            .filter(|event| matches!(event, GameEvent::PiecePlaced { .. }))
+            .count();
+        assert_eq!(placed, 3);
+
+        // Yellow's copy skips ahead on its own, so the next move doesn't add up and it
+        // asks for the whole game instead.
+        clients[1].mirror.pass_turn();
+        let mv = crate::ai::choose_move(host.current_state()).unwrap();
+        assert_eq!(host.submit_move(mv), Ok(()));
+        settle(&mut host, &mut clients);
+        assert_eq!(clients[1].current_state().current_player, 0);
+    }
+
+    /// One end of a connection that never leaves the process.
+    struct MemoryTransport {
+        inbox: Rc<RefCell<VecDeque<Vec<u8>>>>,
+        outbox: Rc<RefCell<VecDeque<Vec<u8>>>>,
+    }
+
+    impl Transport for MemoryTransport {
+        fn send_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+            self.outbox.borrow_mut().push_back(frame.to_vec());
+            Ok(())
+        }
+
+        fn recv_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+            Ok(self.inbox.borrow_mut().pop_front())
+        }
+    }
+
+    /// Two ends of the same connection: whatever goes in one comes out the other.
+    fn memory_pair() -> (MemoryTransport, MemoryTransport) {
+        let (there, back) = Default::default();
+        let a = MemoryTransport {
+            inbox: Rc::clone(&back),
+            outbox: Rc::clone(&there),
+        };
+        let b = MemoryTransport {
+            inbox: there,
+            outbox: back,
+        };
+        (a, b)
+    }
+
+    #[test]
+    fn sessions_run_over_any_transport() {
+        let (host_side, client_side) = memory_pair();
+        let server = GameServer {
+            players: vec![OnlinePlayer::Playing {
+                name: "Yellow".to_string(),
+                connection: Box::new(host_side),
+                last_seen: Instant::now(),
+                data: Player::new(TileColor::Yellow, crate::piece::SHAPES.len()),
+                ready: false,
+            }],
+            reserved_seats: Vec::new(),
+            last_ping: None,
+        };
+        let mut host = NetSession::new(server, GameState::new(2));
+        let mut client =
+            ClientSession::new(client_side, GameState::new(2), TileColor::Yellow, "Yellow");
+        assert!(host.is_remote(1) && client.is_remote(0));
+
+        // No sockets, so nothing to wait on: one poll each way gets everything across.
+        for seat in [0, 1, 0] {
+            let mv = crate::ai::choose_move(host.current_state()).unwrap();
+            if seat == 0 {
+                assert_eq!(host.submit_move(mv), Ok(()));
+            } else {
+                assert_eq!(client.submit_move(mv), Ok(()));
+                host.poll_remote();
+            }
+            client.poll_remote();
+            assert!(!client.awaiting_host);
+            assert_eq!(
+                client.current_state().to_save_string(),
+                host.current_state().to_save_string()
+            );
+        }
+
+        client.send(&Message::chat("Yellow", "gg"));
+        host.poll_remote();
+        client.poll_remote();
+        assert_eq!(host.chat().lines.back().unwrap(), "Yellow: gg");
+        assert_eq!(client.chat().lines.back().unwrap(), "Yellow: gg");
+    }
+
+    #[test]
+    fn roster_lists_everyone_in_join_order() {
+        let mut server = GameServer::default();
+        let (watcher_host, watcher) = memory_pair();
+        let (player_host, _player) = memory_pair();
+        // Ana's in for a game with a piece more than usual, and gets that many.
+        let piece_count = crate::piece::SHAPES.len() + 1;
+        assert_eq!(server.join(watcher_host, "Watcher", false, piece_count), 0);
+        assert_eq!(server.join(player_host, " Ana ", true, piece_count), 1);
+        let OnlinePlayer::Playing { data, .. } = &server.players[1] else {
+            panic!("Ana wanted a seat.");
+        };
+        assert_eq!(data.remaining_pieces.len(), piece_count);
+
+        let roster = server.roster();
+        assert_eq!(
+            roster,
+            [
+                RosterEntry {
+                    name: "Watcher".to_string(),
+                    role: Role::Spectating,
+                    health: Health::Good,
+                },
+                RosterEntry {
+                    name: "Ana".to_string(),
+                    role: Role::Playing(TileColor::Blue),
+                    health: Health::Good,
+                },
+            ]
+        );
+
+        // The spectator heard about both joins, and the list survives the trip.
+        let mut client =
+            ClientSession::new(watcher, GameState::new(2), TileColor::Yellow, "Watcher");
+        client.poll_remote();
+        assert_eq!(client.roster(), roster);
+        assert_eq!(Health::after(HEARTBEAT_TIMEOUT), Health::Lagging);
+        assert_eq!(Health::after(2 * HEARTBEAT_TIMEOUT), Health::Lost);
+    }
+
+    #[test]
+    fn one_no_keeps_the_move() {
+        let (yellow_host, yellow) = memory_pair();
+        let (red_host, red) = memory_pair();
+        // The host plays Blue from here, not over a connection.
+        let seat = |connection: MemoryTransport, color: TileColor| OnlinePlayer::Playing {
+            name: format!("{color:?}"),
+            connection: Box::new(connection),
+            last_seen: Instant::now(),
+            data: Player::new(color, crate::piece::SHAPES.len()),
+            ready: true,
+        };
+        let server = GameServer {
+            players: vec![
+                seat(yellow_host, TileColor::Yellow),
+                seat(red_host, TileColor::Red),
+            ],
+            ..GameServer::default()
+        };
+        let mut host = NetSession::new(server, GameState::new(3));
+        let mut clients = [
+            ClientSession::new(yellow, GameState::new(3), TileColor::Yellow, "Yellow"),
+            ClientSession::new(red, GameState::new(3), TileColor::Red, "Red"),
+        ];
+        let mv = crate::ai::choose_move(host.current_state()).unwrap();
+        assert_eq!(host.submit_move(mv), Ok(()));
+        let before = host.current_state().to_save_string();
+
+        // Yellow's fine with it, but Red isn't, and that's the end of it.
+        assert!(host.request_undo());
+        assert_eq!(host.undo_vote_pending(), None);
+        for client in &mut clients {
+            client.poll_remote();
+            assert_eq!(client.undo_vote_pending(), Some(0));
+        }
+        clients[0].vote_undo(true);
+        host.poll_remote();
+        assert!(host.undo_vote.is_some());
+        clients[1].vote_undo(false);
+        host.poll_remote();
+        assert!(host.undo_vote.is_none());
+        assert_eq!(host.take_undo_answer(), Some(false));
+        assert_eq!(host.current_state().to_save_string(), before);
+
+        // Asking again works, and with everyone on board the move comes off for them too.
+        assert!(host.request_undo());
+        for client in &mut clients {
+            client.poll_remote();
+            client.vote_undo(true);
+        }
+        host.poll_remote();
+        assert_eq!(host.take_undo_answer(), Some(true));
+        assert_eq!(host.current_state().players[0].pieces_placed, 0);
+        clients[1].poll_remote();
+        assert_eq!(
+            clients[1].current_state().to_save_string(),
+            host.current_state().to_save_string()
+        );
+    }
+
+    #[test]
+    fn reject_reasons_round_trip() {
+        let reasons = [RejectReason::NotYourTurn, RejectReason::GameOver]
+            .into_iter()
+            .chain(MOVE_ERRORS.map(RejectReason::Illegal));
+        for reason in reasons {
+            let bytes = Message::Reject(reason).encode();
+            assert_eq!(Message::decode(&bytes).unwrap(), Message::Reject(reason));
+        }
+        assert!(Message::decode(&[REJECT_TAG, 200]).is_err());
+    }
+}
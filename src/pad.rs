@@ -0,0 +1,158 @@
+//! Controllers, for couch games where passing the mouse around gets old.
+//!
+//! Each pad works one seat: the first pad anyone presses a button on gets the first person
+//! at the table, the second gets the second, and so on, skipping bots. A pad only does
+//! anything on its own seat's turn. Reading pads is only built with the `gamepad` feature;
+//! without it nothing ever gets pressed.
+//!
+//! | Button            | Does                  |
+//! |-------------------|-----------------------|
+//! | D-pad             | moves the cursor      |
+//! | A (south)         | puts the piece down   |
+//! | B (east)          | lets go of the piece  |
+//! | X (west)          | turns it right        |
+//! | Y (north)         | flips it side to side |
+//! | L2 / R2           | turns it left / flips it upside down |
+//! | L1 / R1           | previous / next piece |
+
+use macroquad::prelude::IVec2;
+use smallvec::SmallVec;
+
+use crate::keys::Action;
+use crate::logic::GameState;
+#[cfg(feature = "gamepad")]
+use crate::logic::Player;
+
+/// Whatever one seat's pad did this frame.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PadInput {
+    actions: SmallVec<[Action; 4]>,
+    /// How far the d-pad moved the cursor, in screen directions.
+    pub nudge: IVec2,
+}
+
+impl PadInput {
+    /// Whether a button for `action` was pressed, like `KeyBindings::fired` for keys.
+    pub fn fired(&self, action: Action) -> bool {
+        self.actions.contains(&action)
+    }
+}
+
+/// Which seat the `slot`th pad to turn up works: the `slot`th player who isn't a bot or the
+/// shared color.
+#[cfg(feature = "gamepad")]
+fn pad_seat(players: &[Player], slot: usize) -> Option<usize> {
+    (0..players.len())
+        .filter(|&idx| !players[idx].is_bot && !players[idx].is_neutral)
+        .nth(slot)
+}
+
+/// What pressing `button` does.
+#[cfg(feature = "gamepad")]
+fn press(button: gilrs::Button, input: &mut PadInput) {
+    use gilrs::Button;
+    use macroquad::prelude::ivec2;
+    input.nudge += match button {
+        Button::DPadLeft => ivec2(-1, 0),
+        Button::DPadRight => ivec2(1, 0),
+        Button::DPadUp => ivec2(0, -1),
+        Button::DPadDown => ivec2(0, 1),
+        _ => IVec2::ZERO,
+    };
+    let action = match button {
+        Button::South => Action::Commit,
+        Button::East => Action::Cancel,
+        Button::West => Action::RotateRight,
+        Button::North => Action::FlipHorizontal,
+        Button::LeftTrigger2 => Action::RotateLeft,
+        Button::RightTrigger2 => Action::FlipVertical,
+        Button::LeftTrigger => Action::PrevPiece,
+        Button::RightTrigger => Action::NextPiece,
+        _ => return,
+    };
+    input.actions.push(action);
+}
+
+/// Every pad plugged in, and which seat each one belongs to.
+#[cfg(feature = "gamepad")]
+pub struct Pads {
+    /// `None` if there's no way to read pads here, which just means nobody uses one.
+    gilrs: Option<gilrs::Gilrs>,
+    /// In the order they first pressed something, which is the order they get seats in.
+    slots: Vec<gilrs::GamepadId>,
+}
+
+#[cfg(feature = "gamepad")]
+impl Pads {
+    pub fn new() -> Self {
+        let gilrs = gilrs::Gilrs::new()
+            .map_err(|e| eprintln!("Couldn't read controllers -- {e}"))
+            .ok();
+        Self {
+            gilrs,
+            slots: Vec::new(),
+        }
+    }
+
+    /// Whatever the current player's pad did since last time. Everyone else's buttons are
+    /// used up too, so they don't pile up until it's their turn.
+    pub fn poll(&mut self, game_state: &GameState) -> PadInput {
+        let mut input = PadInput::default();
+        let Some(gilrs) = &mut self.gilrs else {
+            return input;
+        };
+        while let Some(gilrs::Event { id, event, .. }) = gilrs.next_event() {
+            let gilrs::EventType::ButtonPressed(button, _) = event else {
+                continue;
+            };
+            let slot = self.slots.iter().position(|&pad| pad == id);
+            let slot = slot.unwrap_or_else(|| {
+                self.slots.push(id);
+                self.slots.len() - 1
+            });
+            if pad_seat(&game_state.players, slot) == Some(game_state.current_player) {
+                press(button, &mut input);
+            }
+        }
+        input
+    }
+}
+
+/// Stands in for the real thing when the game's built without controllers.
+#[cfg(not(feature = "gamepad"))]
+pub struct Pads;
+
+#[cfg(not(feature = "gamepad"))]
+impl Pads {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn poll(&mut self, _game_state: &GameState) -> PadInput {
+        PadInput::default()
+    }
+}
+
+#[cfg(all(test, feature = "gamepad"))]
+mod tests {
+    use super::*;
+    use macroquad::prelude::ivec2;
+
+    #[test]
+    fn pads_skip_bot_seats() {
+        let mut players = Player::default_order(4);
+        players[1].is_bot = true;
+        assert_eq!(pad_seat(&players, 0), Some(0));
+        assert_eq!(pad_seat(&players, 1), Some(2));
+        assert_eq!(pad_seat(&players, 2), Some(3));
+        // A fourth pad has nobody left to play for.
+        assert_eq!(pad_seat(&players, 3), None);
+
+        let mut input = PadInput::default();
+        press(gilrs::Button::DPadUp, &mut input);
+        press(gilrs::Button::RightTrigger, &mut input);
+        assert_eq!(input.nudge, ivec2(0, -1));
+        assert!(input.fired(Action::NextPiece));
+        assert!(!input.fired(Action::PrevPiece));
+    }
+}
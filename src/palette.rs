@@ -0,0 +1,136 @@
+//! Which colors the players' tiles actually show up as on screen.
+
+use macroquad::prelude::*;
+
+use crate::logic::TileColor;
+
+/// Maps each `TileColor` to what gets drawn for it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Palette {
+    /// The colors on the box.
+    #[default]
+    Classic,
+    /// Colors that stay apart for players who mix up red and green, with a symbol on
+    /// every tile just in case.
+    DeuteranopiaSafe,
+}
+
+/// Marks a tile's color by shape as well as hue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Symbol {
+    Circle,
+    Triangle,
+    Cross,
+    Diamond,
+}
+
+impl Palette {
+    pub fn color(self, tile: TileColor) -> Color {
+        match (self, tile) {
+            (_, TileColor::Empty | TileColor::Wall) => BLANK,
+            (Self::Classic, TileColor::Red) => RED,
+            (Self::Classic, TileColor::Yellow) => YELLOW,
+            (Self::Classic, TileColor::Green) => GREEN,
+            (Self::Classic, TileColor::Blue) => BLUE,
+            // From Okabe and Ito's palette, which was picked to survive color blindness.
+            (Self::DeuteranopiaSafe, TileColor::Red) => color_u8!(0xd5, 0x5e, 0x00, 0xff),
+            (Self::DeuteranopiaSafe, TileColor::Yellow) => color_u8!(0xf0, 0xe4, 0x42, 0xff),
+            (Self::DeuteranopiaSafe, TileColor::Green) => color_u8!(0x00, 0x9e, 0x73, 0xff),
+            (Self::DeuteranopiaSafe, TileColor::Blue) => color_u8!(0x00, 0x72, 0xb2, 0xff),
+        }
+    }
+
+    /// A lighter version of `color`, for showing where a piece would go.
+    pub fn highlight(self, tile: TileColor) -> Color {
+        match (self, tile) {
+            (_, TileColor::Empty | TileColor::Wall) => BLANK,
+            (Self::Classic, TileColor::Red) => color_u8!(0xff, 0x70, 0x70, 0xff),
+            (Self::Classic, TileColor::Yellow) => color_u8!(0xff, 0xee, 0x75, 0xff),
+            (Self::Classic, TileColor::Green) => color_u8!(0x8d, 0xff, 0x6b, 0xff),
+            (Self::Classic, TileColor::Blue) => color_u8!(0x28, 0xa0, 0xff, 0xff),
+            (Self::DeuteranopiaSafe, _) => {
+                let Color { r, g, b, .. } = self.color(tile);
+                // Halfway to white.
+                Color::new(0.5 + 0.5 * r, 0.5 + 0.5 * g, 0.5 + 0.5 * b, 1.)
+            }
+        }
+    }
+
+    /// What a dragged piece is tinted with: green if it `fits` where it is and red if not,
+    /// or blue and orange for anyone who can't tell those two apart.
+    pub fn fit_tint(self, fits: bool) -> Color {
+        match (self, fits) {
+            (Self::Classic, true) => GREEN,
+            (Self::Classic, false) => RED,
+            (Self::DeuteranopiaSafe, true) => color_u8!(0x00, 0x72, 0xb2, 0xff),
+            (Self::DeuteranopiaSafe, false) => color_u8!(0xe6, 0x9f, 0x00, 0xff),
+        }
+    }
+
+    /// The symbol to draw on top of `tile`, if this palette uses them.
+    pub fn symbol(self, tile: TileColor) -> Option<Symbol> {
+        match (self, tile) {
+            (Self::Classic, _) | (_, TileColor::Empty | TileColor::Wall) => None,
+            (Self::DeuteranopiaSafe, TileColor::Blue) => Some(Symbol::Circle),
+            (Self::DeuteranopiaSafe, TileColor::Yellow) => Some(Symbol::Triangle),
+            (Self::DeuteranopiaSafe, TileColor::Red) => Some(Symbol::Cross),
+            (Self::DeuteranopiaSafe, TileColor::Green) => Some(Symbol::Diamond),
+        }
+    }
+
+    /// The other palette, for the toggle on the setup screen.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Classic => Self::DeuteranopiaSafe,
+            Self::DeuteranopiaSafe => Self::Classic,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hue in degrees, `0..360`.
+    fn hue(color: Color) -> f32 {
+        let Color { r, g, b, .. } = color;
+        let max = r.max(g).max(b);
+        let min = r.min(g).min(b);
+        let chroma = max - min;
+        let sector = if chroma == 0. {
+            0.
+        } else if max == r {
+            ((g - b) / chroma).rem_euclid(6.)
+        } else if max == g {
+            (b - r) / chroma + 2.
+        } else {
+            (r - g) / chroma + 4.
+        };
+        60. * sector
+    }
+
+    #[test]
+    fn safe_palette_hues_stay_apart() {
+        let hues = TileColor::all_playable().map(|tile| hue(Palette::DeuteranopiaSafe.color(tile)));
+        for (i, a) in hues.iter().enumerate() {
+            for b in &hues[i + 1..] {
+                let diff = (a - b).abs();
+                // Hue wraps around, so red at 350 is right next to red at 10.
+                assert!(diff.min(360. - diff) > 25., "{hues:?}");
+            }
+        }
+
+        // Same for the tints on a dragged piece.
+        let [fits, doesnt_fit] =
+            [true, false].map(|fits| hue(Palette::DeuteranopiaSafe.fit_tint(fits)));
+        let diff = (fits - doesnt_fit).abs();
+        assert!(diff.min(360. - diff) > 25.);
+
+        // Every color gets its own symbol too, since hue alone isn't everything.
+        let symbols = TileColor::all_playable().map(|tile| Palette::DeuteranopiaSafe.symbol(tile));
+        for (i, a) in symbols.iter().enumerate() {
+            assert!(a.is_some());
+            assert!(!symbols[i + 1..].contains(a));
+        }
+    }
+}
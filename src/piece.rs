@@ -1,4 +1,9 @@
 use bitvec::prelude::*;
+use smallvec::SmallVec;
+use std::{
+    hash::{Hash, Hasher},
+    sync::{Mutex, OnceLock},
+};
 
 const ROW_LEN: usize = 5;
 
@@ -10,6 +15,37 @@ pub type Shape = [BitArr!(for ROW_LEN, in u8); 5];
 
 pub const EMPTY_SHAPE: Shape = [bitarr![u8, Lsb0; 0; ROW_LEN]; 5];
 
+/// How many tiles each piece in `SHAPES` covers.
+pub const PIECE_SIZES: [usize; 21] = [
+    1, 2, 3, 3, 4, 4, 4, 4, 4, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5, 5,
+];
+
+/// What each piece in `SHAPES` is called, for writing moves down. No spaces, so a move stays
+/// one word on a command line.
+pub const PIECE_NAMES: [&str; 21] = [
+    "DOT",
+    "LINE2",
+    "LINE3",
+    "L3",
+    "LINE4",
+    "L4",
+    "ZIG-ZAG",
+    "SQUARE",
+    "TEE",
+    "LINE5",
+    "L5",
+    "EXT-ZIG",
+    "EXT-TEE",
+    "U",
+    "NOTCH-SQUARE",
+    "BIG-TEE",
+    "BIG-L5",
+    "STAIRS",
+    "WIDE-ZIG",
+    "CHAIR",
+    "PLUS",
+];
+
 pub const SHAPES: [Shape; 21] = [
     // DOT - 0
     [
@@ -181,19 +217,316 @@ pub const SHAPES: [Shape; 21] = [
     ],
 ];
 
+/// The pieces a game is played with. Everyone starts with one of each, and a piece's ID is
+/// where it sits in the set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PieceSet {
+    shapes: Vec<Shape>,
+    names: Vec<String>,
+    sizes: Vec<usize>,
+}
+
+impl PieceSet {
+    /// The most pieces a set can have, so a piece's ID fits in the one byte the network
+    /// gives it.
+    pub const MAX_LEN: usize = 256;
+
+    /// The usual 21: `SHAPES`, going by `PIECE_NAMES`.
+    pub fn classic() -> Self {
+        Self {
+            shapes: SHAPES.to_vec(),
+            names: PIECE_NAMES.iter().map(|name| name.to_string()).collect(),
+            sizes: PIECE_SIZES.to_vec(),
+        }
+    }
+
+    /// This set with one more piece on the end, builder-style. Moves get written down by
+    /// piece name, so `name` can't be taken already, and the set can't be full.
+    // Nothing in the game offers another set yet. This is for variants to build on.
+    #[allow(dead_code)]
+    pub fn with_piece(mut self, name: &str, shape: Shape) -> Self {
+        assert!(
+            self.find(name).is_none(),
+            "There's already a piece called {name}."
+        );
+        assert!(
+            self.len() < Self::MAX_LEN,
+            "There's no room for {name}, the set is full."
+        );
+        self.sizes.push(cells(&shape).count());
+        self.shapes.push(shape);
+        self.names.push(name.to_string());
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    #[allow(dead_code)]
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    pub fn shape(&self, piece_id: usize) -> Shape {
+        self.shapes[piece_id]
+    }
+
+    pub fn name(&self, piece_id: usize) -> &str {
+        &self.names[piece_id]
+    }
+
+    /// How many tiles `piece_id` covers.
+    pub fn size(&self, piece_id: usize) -> usize {
+        self.sizes[piece_id]
+    }
+
+    /// The ID of the piece called `name`, if there is one.
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|n| n == name)
+    }
+}
+
+impl Default for PieceSet {
+    fn default() -> Self {
+        Self::classic()
+    }
+}
+
 // Maybe a little overkill but it's explicit.
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RotateDir {
     Right,
     Left,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum FlipDir {
     Horizontal,
     Vertical,
 }
 
+/// One of the eight ways a piece can be turned over and around.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Orientation {
+    /// Whether the piece is flipped vertically before rotating.
+    pub flipped: bool,
+    /// Number of quarter turns to the right. `0 <= rotation <= 3`
+    pub rotation: u8,
+}
+
+impl Orientation {
+    pub const ALL: [Self; 8] = [
+        Self::new(false, 0),
+        Self::new(false, 1),
+        Self::new(false, 2),
+        Self::new(false, 3),
+        Self::new(true, 0),
+        Self::new(true, 1),
+        Self::new(true, 2),
+        Self::new(true, 3),
+    ];
+
+    pub const fn new(flipped: bool, rotation: u8) -> Self {
+        Self { flipped, rotation }
+    }
+
+    /// Turns `shape` into this orientation.
+    pub fn apply(self, shape: Shape) -> Shape {
+        let mut shape = if self.flipped {
+            flip(shape, FlipDir::Vertical)
+        } else {
+            shape
+        };
+        for _ in 0..self.rotation {
+            shape = rotate(shape, RotateDir::Right);
+        }
+        shape
+    }
+}
+
+/// Every distinct orientation of `shape`. Symmetric pieces look the same in several
+/// orientations, so we only keep the first orientation that produces each look.
+/// Rotating moves the piece around inside its 5x5 grid, so two orientations count
+/// as the same look if one is just a shifted copy of the other.
+pub fn orientations(shape: Shape) -> SmallVec<[(Orientation, Shape); 8]> {
+    let mut distinct = SmallVec::<[(Orientation, Shape); 8]>::new();
+    let mut looks = SmallVec::<[CanonicalShape; 8]>::new();
+    for orientation in Orientation::ALL {
+        let oriented = orientation.apply(shape);
+        let look = CanonicalShape::new(oriented);
+        if !looks.contains(&look) {
+            looks.push(look);
+            distinct.push((orientation, oriented));
+        }
+    }
+    distinct
+}
+
+/// Slides `shape` up and to the left until it touches the top and left edges of its grid.
+pub fn normalize(shape: Shape) -> Shape {
+    let top = cells(&shape).map(|(r, _)| r).min().unwrap_or(0);
+    let left = cells(&shape).map(|(_, c)| c).min().unwrap_or(0);
+    let mut new_shape = EMPTY_SHAPE;
+    for (r, c) in cells(&shape) {
+        new_shape[r - top].set(c - left, true);
+    }
+    new_shape
+}
+
+/// A shape slid into the top-left of its grid (see `normalize`), for keying maps and sets by
+/// how a piece looks. Each row's storage has a few bits past its five columns, so two
+/// `Shape`s can look the same without being `==`. These only go by the five columns.
+#[derive(Debug, Clone, Copy)]
+pub struct CanonicalShape(Shape);
+
+impl CanonicalShape {
+    pub fn new(shape: Shape) -> Self {
+        Self(normalize(shape))
+    }
+}
+
+impl PartialEq for CanonicalShape {
+    fn eq(&self, other: &Self) -> bool {
+        pack(&self.0) == pack(&other.0)
+    }
+}
+
+impl Eq for CanonicalShape {}
+
+impl Hash for CanonicalShape {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        pack(&self.0).hash(state);
+    }
+}
+
+/// A piece's tiles and the cells around them, as (row, col) offsets from the top-left of its
+/// normalized shape. Checking a placement only needs to look at these.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Footprint {
+    /// The piece's own tiles.
+    pub cells: SmallVec<[(i32, i32); 5]>,
+    /// Cells outside the piece that share an edge with it.
+    pub edges: SmallVec<[(i32, i32); 12]>,
+    /// Cells that touch the piece only at a corner.
+    pub corners: SmallVec<[(i32, i32); 12]>,
+    /// `cells` as a mask per row, top row first, with bit `c` standing for column `c`.
+    pub rows: SmallVec<[u8; 5]>,
+    /// `edges` as a mask per row, from the row above the piece down to the row below it.
+    /// Bit `c + 1` stands for column `c`, so the column left of the piece is bit 0.
+    pub edge_rows: SmallVec<[u8; 7]>,
+}
+
+impl Footprint {
+    fn new(shape: &Shape) -> Self {
+        let cells: SmallVec<[(i32, i32); 5]> = cells(&normalize(*shape))
+            .map(|(r, c)| (r as i32, c as i32))
+            .collect();
+        let around = |steps: [(i32, i32); 4], skip: &[(i32, i32)]| {
+            let mut found = SmallVec::new();
+            for &(r, c) in &cells {
+                for (dr, dc) in steps {
+                    let cell = (r + dr, c + dc);
+                    if !cells.contains(&cell) && !skip.contains(&cell) && !found.contains(&cell) {
+                        found.push(cell);
+                    }
+                }
+            }
+            found
+        };
+        let edges: SmallVec<[(i32, i32); 12]> = around([(-1, 0), (0, -1), (1, 0), (0, 1)], &[]);
+        let corners = around([(-1, -1), (1, -1), (-1, 1), (1, 1)], &edges);
+
+        let height = cells.iter().map(|&(r, _)| r + 1).max().unwrap_or(0);
+        let mut rows: SmallVec<[u8; 5]> = smallvec::smallvec![0; height as usize];
+        for &(r, c) in &cells {
+            rows[r as usize] |= 1 << c;
+        }
+        let mut edge_rows: SmallVec<[u8; 7]> = smallvec::smallvec![0; height as usize + 2];
+        for &(r, c) in &edges {
+            edge_rows[(r + 1) as usize] |= 1 << (c + 1);
+        }
+        Self {
+            cells,
+            edges,
+            corners,
+            rows,
+            edge_rows,
+        }
+    }
+}
+
+/// `shape`'s footprint, along with the (row, col) its normalized top-left sits at in `shape`'s
+/// grid. Footprints for every orientation of every piece get worked out once, the first time
+/// anyone asks, and filed under their normalized shape. Pieces from outside `SHAPES` get
+/// theirs worked out the first time they show up instead.
+pub fn footprint(shape: &Shape) -> (&'static Footprint, (i32, i32)) {
+    static FOOTPRINTS: OnceLock<Vec<(u32, Footprint)>> = OnceLock::new();
+    let footprints = FOOTPRINTS.get_or_init(|| {
+        let mut footprints: Vec<_> = SHAPES
+            .iter()
+            .flat_map(|&shape| orientations(shape))
+            .map(|(_, oriented)| oriented)
+            .chain([EMPTY_SHAPE])
+            .map(|shape| (pack(&normalize(shape)), Footprint::new(&shape)))
+            .collect();
+        footprints.sort_by_key(|&(key, _)| key);
+        footprints
+    });
+
+    // This gets called for every candidate move, so normalize with bit twiddling instead of
+    // `normalize`. Every row's empty columns on the left are zeros, so shifting the whole thing
+    // right slides each row over without spilling into the next.
+    let bits = pack(shape);
+    let (top, left) = if bits == 0 {
+        (0, 0)
+    } else {
+        let columns =
+            (0..ROW_LEN).fold(0, |columns, r| columns | (bits >> (r * ROW_LEN)) & 0b11111);
+        (
+            bits.trailing_zeros() / ROW_LEN as u32,
+            columns.trailing_zeros(),
+        )
+    };
+    let normalized = bits >> (top * ROW_LEN as u32 + left);
+    let footprint = match footprints.binary_search_by_key(&normalized, |&(key, _)| key) {
+        Ok(idx) => &footprints[idx].1,
+        Err(_) => custom_footprint(shape),
+    };
+    (footprint, (top as i32, left as i32))
+}
+
+/// The footprint of a shape from a custom `PieceSet`, filed under how it looks.
+/// There are only ever a handful of these, so they're leaked rather than tracked.
+fn custom_footprint(shape: &Shape) -> &'static Footprint {
+    static CUSTOM: Mutex<Vec<(CanonicalShape, &'static Footprint)>> = Mutex::new(Vec::new());
+    let key = CanonicalShape::new(*shape);
+    let mut custom = CUSTOM.lock().expect("Nothing panics while holding this.");
+    if let Some(&(_, footprint)) = custom.iter().find(|&&(k, _)| k == key) {
+        return footprint;
+    }
+    let footprint: &'static Footprint = Box::leak(Box::new(Footprint::new(shape)));
+    custom.push((key, footprint));
+    footprint
+}
+
+/// `shape` squeezed into the low 25 bits of a `u32`, five bits per row with the top row lowest.
+/// Anything past a row's five columns is left out.
+fn pack(shape: &Shape) -> u32 {
+    shape.iter().rev().fold(0, |bits, row| {
+        bits << ROW_LEN | (row.into_inner()[0] & 0b11111) as u32
+    })
+}
+
+/// The (row, col) of every filled tile in `shape`. Stray bits past a row's five columns
+/// aren't tiles.
+pub fn cells(shape: &Shape) -> impl Iterator<Item = (usize, usize)> + '_ {
+    shape
+        .iter()
+        .enumerate()
+        .flat_map(|(r, row)| row[..ROW_LEN].iter_ones().map(move |c| (r, c)))
+}
+
 pub fn rotate(shape: Shape, dir: RotateDir) -> Shape {
     match dir {
         // Rotate right TAU/4 := Transpose . Flip Vert
@@ -215,10 +548,9 @@ pub fn flip(shape: Shape, dir: FlipDir) -> Shape {
             }
         }
         FlipDir::Horizontal => {
-            for row in 0..ROW_LEN {
+            for (new_row, row) in new_shape.iter_mut().zip(shape) {
                 for col in 0..ROW_LEN {
-                    *new_shape[row].get_mut(col).expect("In bounds.") =
-                        shape[row][ROW_LEN - col - 1];
+                    *new_row.get_mut(col).expect("In bounds.") = row[ROW_LEN - col - 1];
                 }
             }
         }
@@ -231,12 +563,12 @@ pub fn flip(shape: Shape, dir: FlipDir) -> Shape {
 // Do I really see any performance wins with this after all? Who knows at this point lmfao
 fn transpose(shape: Shape) -> Shape {
     let mut new_shape = EMPTY_SHAPE;
-    for row in 0..ROW_LEN {
+    for (row, old_row) in shape.iter().enumerate() {
         for col in 0..ROW_LEN {
             let mut pt = new_shape[col]
                 .get_mut(row)
                 .expect("Should be in bounds, no?");
-            *pt = shape[row][col];
+            *pt = old_row[col];
         }
     }
 
@@ -247,6 +579,22 @@ fn transpose(shape: Shape) -> Shape {
 mod tests {
     use super::*;
 
+    #[test]
+    fn piece_sizes_match_shapes() {
+        for (shape, size) in SHAPES.iter().zip(PIECE_SIZES) {
+            assert_eq!(cells(shape).count(), size);
+        }
+        assert_eq!(PIECE_SIZES.iter().sum::<usize>(), 89);
+    }
+
+    #[test]
+    #[should_panic(expected = "the set is full")]
+    fn sets_stop_at_a_byte() {
+        (PieceSet::classic().len()..=PieceSet::MAX_LEN).fold(PieceSet::classic(), |pieces, i| {
+            pieces.with_piece(&format!("EXTRA{i}"), SHAPES[0])
+        });
+    }
+
     #[test]
     fn tranpose_ok() {
         let chair = SHAPES[19];
@@ -293,4 +641,50 @@ mod tests {
         assert_eq!(flip(chair, FlipDir::Vertical), chair_fv);
         assert_eq!(flip(chair, FlipDir::Horizontal), chair_fh);
     }
+
+    #[test]
+    fn canonical_shapes_ignore_position_and_padding() {
+        use std::collections::hash_map::DefaultHasher;
+        let hash = |shape: CanonicalShape| {
+            let mut hasher = DefaultHasher::new();
+            shape.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        // The L, slid into the top-left corner, with junk past the end of every row.
+        let l3 = SHAPES[3];
+        let mut moved = EMPTY_SHAPE;
+        for (r, c) in cells(&l3) {
+            moved[r - 1].set(c - 2, true);
+        }
+        for row in &mut moved {
+            row.set(6, true);
+        }
+        assert_ne!(moved, l3);
+        assert_eq!(cells(&moved).count(), 3);
+        assert_eq!(CanonicalShape::new(moved), CanonicalShape::new(l3));
+        assert_eq!(
+            hash(CanonicalShape::new(moved)),
+            hash(CanonicalShape::new(l3))
+        );
+        // Same look, same footprint.
+        assert_eq!(footprint(&moved).0, footprint(&l3).0);
+
+        // A different look is a different key.
+        assert_ne!(CanonicalShape::new(SHAPES[2]), CanonicalShape::new(l3));
+        let turned = rotate(l3, RotateDir::Right);
+        assert_ne!(CanonicalShape::new(turned), CanonicalShape::new(l3));
+    }
+
+    #[test]
+    fn orientations_skip_duplicates() {
+        // DOT and PLUS look the same no matter how you turn them.
+        assert_eq!(orientations(SHAPES[0]).len(), 1);
+        assert_eq!(orientations(SHAPES[20]).len(), 1);
+        // LINE5 only has two looks, SQUARE just the one.
+        assert_eq!(orientations(SHAPES[9]).len(), 2);
+        assert_eq!(orientations(SHAPES[7]).len(), 1);
+        // The chair is lopsided in every direction.
+        assert_eq!(orientations(SHAPES[19]).len(), 8);
+    }
 }
@@ -0,0 +1,145 @@
+//! A game's placements, kept so it can be stepped back through once it's over.
+
+use crate::logic::{GameEvent, GameState, Move, TileColor};
+
+/// Rebuilds a game one placement at a time, from wherever it was when recording started.
+#[derive(Debug, Clone)]
+pub struct Replay {
+    /// The game before the first recorded placement.
+    start: GameState,
+    /// Which color each move was, in order. Seats can trade colors partway through (the pie
+    /// rule), so the color's what stays put. Passes leave the board alone, so they aren't kept.
+    placements: Vec<(TileColor, Move)>,
+}
+
+impl Replay {
+    /// Starts recording from `start`.
+    pub fn new(start: &GameState) -> Self {
+        Self {
+            start: start.clone(),
+            placements: Vec::new(),
+        }
+    }
+
+    /// Keeps track of `event` if it changed the board. Feed this everything from
+    /// `GameState::drain_events`, along with the game they came out of.
+    pub fn record(&mut self, event: &GameEvent, game_state: &GameState) {
+        if let GameEvent::PiecePlaced { player, mv } = *event {
+            self.placements.push((game_state.players[player].color, mv));
+        }
+    }
+
+    /// Where `color` sits in the game being replayed, which might not be where it ended up.
+    fn seat_of(&self, color: TileColor) -> usize {
+        self.start
+            .players
+            .iter()
+            .position(|p| p.color == color)
+            .expect("Only colors in the game make moves.")
+    }
+
+    /// How many placements there are to step through.
+    pub fn len(&self) -> usize {
+        self.placements.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.placements.is_empty()
+    }
+
+    /// Forgets any placements `game_state` doesn't have anymore, since they were taken
+    /// back. Says whether there were any.
+    pub fn rewind_to(&mut self, game_state: &GameState) -> bool {
+        let placed = |game_state: &GameState| -> usize {
+            game_state.players.iter().map(|p| p.pieces_placed).sum()
+        };
+        let kept = placed(game_state).saturating_sub(placed(&self.start));
+        let rewound = kept < self.len();
+        self.placements.truncate(kept);
+        rewound
+    }
+
+    /// The game after its first `n` placements: step 0 is how it started, and step `len` is
+    /// how it ended.
+    pub fn step(&self, n: usize) -> GameState {
+        let mut game_state = self.start.clone();
+        for &(color, mv) in &self.placements[..n.min(self.len())] {
+            // Seats can change hands mid-game (the pie rule, the neutral color), so don't
+            // count on turn order to put the right player up.
+            game_state.current_player = self.seat_of(color);
+            game_state.apply_move(&mv);
+        }
+        game_state
+    }
+
+    /// The placement step `n` ended on, along with who made it, by their seat in `step`'s
+    /// games. Nothing for step 0.
+    pub fn placed_at(&self, n: usize) -> Option<(usize, Move)> {
+        n.checked_sub(1)
+            .and_then(|idx| self.placements.get(idx))
+            .map(|&(color, mv)| (self.seat_of(color), mv))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ai, config::GameConfig, logic::Player};
+
+    #[test]
+    fn steps_run_from_start_to_finish() {
+        let mut game_state = GameState::new(4);
+        let mut replay = Replay::new(&game_state);
+        for _ in 0..12 {
+            match ai::choose_move(&game_state) {
+                Some(mv) => game_state.apply_move(&mv),
+                None => game_state.pass_turn(),
+            }
+            for event in game_state.drain_events() {
+                replay.record(&event, &game_state);
+            }
+        }
+
+        assert_eq!(replay.len(), 12);
+        assert_eq!(replay.step(0).board, GameState::new(4).board);
+        assert_eq!(replay.placed_at(0), None);
+        assert_eq!(replay.step(replay.len()).board, game_state.board);
+        // Asking past the end just gets the final board.
+        assert_eq!(replay.step(replay.len() + 5).board, game_state.board);
+
+        // Each step puts down exactly the piece it says it does.
+        for n in 1..=replay.len() {
+            let (player, mv) = replay.placed_at(n).unwrap();
+            let (before, after) = (replay.step(n - 1), replay.step(n));
+            let color = before.players[player].color;
+            for (r, c) in mv.board_cells(&before.config.pieces) {
+                assert_eq!(before.board[r][c], TileColor::Empty);
+                assert_eq!(after.board[r][c], color);
+            }
+        }
+    }
+
+    #[test]
+    fn swapped_seats_keep_their_colors() {
+        let config = GameConfig::classic().pie_rule(true);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        let mut replay = Replay::new(&game_state);
+        let play = |game_state: &mut GameState, replay: &mut Replay| {
+            let mv = ai::choose_move(game_state).expect("Early game has room.");
+            game_state.apply_move(&mv);
+            for event in game_state.drain_events() {
+                replay.record(&event, game_state);
+            }
+        };
+        play(&mut game_state, &mut replay);
+        // Yellow takes Blue's opening, and Blue's old seat plays Yellow from here on.
+        game_state.swap_seats();
+        for _ in 0..3 {
+            play(&mut game_state, &mut replay);
+        }
+
+        assert_eq!(replay.step(replay.len()).board, game_state.board);
+        let (player, _) = replay.placed_at(2).unwrap();
+        assert_eq!(replay.step(2).players[player].color, TileColor::Yellow);
+    }
+}
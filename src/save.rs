@@ -0,0 +1,732 @@
+//! Turning a game into a string and back again, for saving and for bug reports.
+//!
+//! A save looks something like this, one section per line:
+//!
+//! ```text
+//! blorus 3
+//! config 20 1 1 0 0 - - 0 0 1 0 0 19,19 19,0 0,0 0,19
+//! turn 1 0 0 0
+//! player B human 10 0 1 2 3 4 5 6 7 8 9 11 12 13 14 15 16 17 18 19 20
+//! name Ada
+//! player Y hard - 0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20
+//! move 0 10 0 1 16,16
+//! board
+//! ######################
+//! #....................#
+//! ...
+//! ```
+//!
+//! The header has the version of the format the save's in. Saves from before there were versions
+//! count as version 1, and anything they don't have gets its usual value.
+//!
+//! `config` is the board size, whether the first move must cover a corner, whether the dot may go
+//! first, whether players may pass whenever they like, whether the pie rule is on, the turn time
+//! limit in seconds (`-` if there isn't one), the seed players draw pieces with (`-` if they pick
+//! from all of them), whether a three-player game has a neutral color, whether it's a game of
+//! solitaire, how many pieces a turn allows, whether play goes counter-clockwise, whether pieces
+//! have to get smaller as the game goes (new in version 2), then the start corners. `turn` is the
+//! current player, the pass counter, how many turns the neutral color has had and how many pieces
+//! the current player has put down this turn. Each `player` line is a color, who's playing it
+//! (`human`, a bot difficulty, or `neutral` for the neutral color), the last piece they put down
+//! (`-` if they haven't yet), then the pieces they have left. A player who picked a name gets a
+//! `name` line right after theirs, with the name taking up the rest of it, and a player who
+//! forfeited gets a `forfeited` line after that. Each `move` line is a piece put down so far,
+//! oldest first: the seat that put it down, the piece, whether it was flipped, how many quarter
+//! turns right it was turned, then the column and row its 5x5 grid went at. They're new in version
+//! 3, and only tell pieces apart, so older saves just go without. The board is drawn with the same
+//! characters `TileColor` displays as, walls and all.
+//!
+//! Saves go in files under `SAVES_DIR`, or the browser's local storage on the web. Key
+//! bindings live there too, in the `KEYS_SLOT` slot.
+
+use macroquad::prelude::ivec2;
+use smallvec::SmallVec;
+use std::{
+    fmt::{self, Write},
+    io,
+};
+
+use crate::{
+    ai::Difficulty,
+    config::{GameConfig, Seating},
+    keys::KeyBindings,
+    logic::{GameState, Move, Player, SetupError, TileColor},
+    piece::{self, Orientation},
+};
+
+const HEADER: &str = "blorus";
+
+/// The version of the format `to_save_string` writes. Bump it whenever a line gets another
+/// field, and give saves from before that the usual value for it.
+const VERSION: u32 = 3;
+
+/// Where saved games go, relative to wherever the game was started from.
+pub const SAVES_DIR: &str = "saves";
+
+/// The save slot behind the "Save game" and "Load game" buttons.
+pub const QUICK_SAVE: &str = "quicksave";
+
+/// Where the key bindings are kept, next to the saves.
+pub const KEYS_SLOT: &str = "keys";
+
+/// Where the dump key writes the game, for attaching to a bug report.
+pub const DUMP_SLOT: &str = "dump";
+
+/// Where the dump key writes the game as JSON, for outside tools.
+#[cfg(feature = "serde")]
+pub const DUMP_JSON_SLOT: &str = "dump-json";
+
+/// What went wrong reading a save string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    /// The string ended before we found this part of the save.
+    Missing(&'static str),
+    /// This line didn't look like what we expected.
+    Malformed(String),
+    /// This character isn't a tile.
+    UnknownTile(char),
+    /// The players and board in the save can't play together.
+    Setup(SetupError),
+    /// The save's from a newer version of the game, which wrote it in this version of the
+    /// format.
+    TooNew(u32),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Missing(what) => write!(f, "save is missing its {what}"),
+            Self::Malformed(line) => write!(f, "couldn't make sense of \"{line}\""),
+            Self::UnknownTile(c) => write!(f, "'{c}' isn't a tile"),
+            Self::Setup(e) => write!(f, "{e}"),
+            Self::TooNew(version) => {
+                write!(
+                    f,
+                    "save is in version {version} of the format, which is too new"
+                )
+            }
+        }
+    }
+}
+
+impl GameState {
+    /// Writes out everything needed to pick this game back up later.
+    /// The piece a player is holding doesn't count -- they'll have to pick it up again.
+    pub fn to_save_string(&self) -> String {
+        let mut save = String::new();
+        // Writing to a String can't fail, so the `unwrap`s are fine.
+        writeln!(save, "{HEADER} {VERSION}").unwrap();
+
+        let config = &self.config;
+        write!(
+            save,
+            "config {} {} {} {} {}",
+            config.board_dim,
+            config.first_move_must_cover_corner as u8,
+            config.allow_monominoes_first as u8,
+            config.allow_voluntary_pass as u8,
+            config.pie_rule as u8
+        )
+        .unwrap();
+        match config.turn_seconds {
+            Some(seconds) => write!(save, " {seconds}").unwrap(),
+            None => write!(save, " -").unwrap(),
+        }
+        if config.draft_mode {
+            write!(save, " {}", config.draft_seed).unwrap();
+        } else {
+            write!(save, " -").unwrap();
+        }
+        write!(save, " {}", config.three_player_neutral as u8).unwrap();
+        write!(save, " {}", config.solitaire as u8).unwrap();
+        write!(save, " {}", config.pieces_per_turn).unwrap();
+        let counter_clockwise = config.seating == Seating::CounterClockwise;
+        write!(save, " {}", counter_clockwise as u8).unwrap();
+        write!(save, " {}", config.descending_size as u8).unwrap();
+        for (row, col) in &config.start_corners {
+            write!(save, " {row},{col}").unwrap();
+        }
+        writeln!(save).unwrap();
+
+        writeln!(
+            save,
+            "turn {} {} {} {}",
+            self.current_player, self.pass_counter, self.neutral_turns, self.placements_this_turn
+        )
+        .unwrap();
+
+        for p in &self.players {
+            let controller = match (p.is_bot, p.difficulty) {
+                _ if p.is_neutral => "neutral",
+                (false, _) => "human",
+                (true, Difficulty::Easy) => "easy",
+                (true, Difficulty::Medium) => "medium",
+                (true, Difficulty::Hard) => "hard",
+            };
+            write!(save, "player {} {controller}", p.color).unwrap();
+            match p.last_piece {
+                Some(piece_id) => write!(save, " {piece_id}").unwrap(),
+                None => write!(save, " -").unwrap(),
+            }
+            for piece_id in p.remaining_pieces.iter() {
+                write!(save, " {piece_id}").unwrap();
+            }
+            writeln!(save).unwrap();
+            if let Some(name) = &p.name {
+                writeln!(save, "name {name}").unwrap();
+            }
+            if p.forfeited {
+                writeln!(save, "forfeited").unwrap();
+            }
+        }
+
+        for (seat, mv) in self.moves() {
+            let Move {
+                piece_id,
+                orientation,
+                corner,
+            } = mv;
+            writeln!(
+                save,
+                "move {seat} {piece_id} {} {} {},{}",
+                orientation.flipped as u8, orientation.rotation, corner.x, corner.y
+            )
+            .unwrap();
+        }
+
+        writeln!(save, "board").unwrap();
+        for row in &self.board {
+            for tile in row {
+                write!(save, "{tile}").unwrap();
+            }
+            writeln!(save).unwrap();
+        }
+
+        save
+    }
+
+    /// Reads back a game written by `to_save_string`.
+    pub fn from_save_string(save: &str) -> Result<GameState, ParseError> {
+        let mut lines = save.lines().map(str::trim).filter(|l| !l.is_empty());
+
+        let header = lines.next().unwrap_or_default();
+        let version = match header.strip_prefix(HEADER).map(str::trim) {
+            None => return Err(ParseError::Missing("header")),
+            Some("") => 1,
+            Some(version) => version
+                .parse()
+                .map_err(|_| ParseError::Malformed(header.to_string()))?,
+        };
+        if version > VERSION {
+            return Err(ParseError::TooNew(version));
+        }
+
+        let config_line = lines.next().ok_or(ParseError::Missing("config"))?;
+        let config = parse_config(config_line, version)?;
+
+        let turn_line = lines.next().ok_or(ParseError::Missing("turn"))?;
+        let malformed_turn = || ParseError::Malformed(turn_line.to_string());
+        let mut turn = turn_line
+            .strip_prefix("turn ")
+            .ok_or_else(malformed_turn)?
+            .split_whitespace()
+            .map(|n| n.parse::<usize>().map_err(|_| malformed_turn()));
+        let current_player = turn.next().ok_or_else(malformed_turn)??;
+        let pass_counter = turn.next().ok_or_else(malformed_turn)??;
+        let neutral_turns = turn.next().ok_or_else(malformed_turn)??;
+        let placements_this_turn = turn.next().ok_or_else(malformed_turn)??;
+
+        let mut players = SmallVec::<[Player; 4]>::new();
+        let mut line = lines.next().ok_or(ParseError::Missing("board"))?;
+        while let Some(rest) = line.strip_prefix("player ") {
+            let mut player = parse_player(line, rest, &config)?;
+            line = lines.next().ok_or(ParseError::Missing("board"))?;
+            if let Some(name) = line.strip_prefix("name ") {
+                player.name = Some(name.to_string());
+                line = lines.next().ok_or(ParseError::Missing("board"))?;
+            }
+            if line == "forfeited" {
+                player.forfeited = true;
+                line = lines.next().ok_or(ParseError::Missing("board"))?;
+            }
+            players.push(player);
+        }
+        let mut moves = Vec::new();
+        while let Some(rest) = line.strip_prefix("move ") {
+            let mv = parse_move(rest, players.len(), &config)
+                .ok_or_else(|| ParseError::Malformed(line.to_string()))?;
+            moves.push(mv);
+            line = lines.next().ok_or(ParseError::Missing("board"))?;
+        }
+        if line != "board" {
+            return Err(ParseError::Malformed(line.to_string()));
+        }
+
+        // Nobody passes once everyone has, since that ends the game, and a turn's over once
+        // its last piece is down.
+        if current_player >= players.len()
+            || pass_counter > players.len()
+            || placements_this_turn >= config.pieces_per_turn
+        {
+            return Err(malformed_turn());
+        }
+
+        let board = lines
+            .map(|row| {
+                row.chars()
+                    .map(|c| TileColor::from_char(c).ok_or(ParseError::UnknownTile(c)))
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let width = config.board_dim + 2;
+        if board.len() != width || board.iter().any(|row| row.len() != width) {
+            return Err(ParseError::Malformed("board".to_string()));
+        }
+
+        let mut game_state =
+            GameState::try_with_players(players, config).map_err(ParseError::Setup)?;
+        game_state.board = board;
+        game_state.current_player = current_player;
+        game_state.pass_counter = pass_counter;
+        game_state.neutral_turns = neutral_turns;
+        game_state.placements_this_turn = placements_this_turn;
+        game_state.restore_moves(moves);
+        game_state.refresh_frontiers();
+        Ok(game_state)
+    }
+}
+
+fn parse_config(line: &str, version: u32) -> Result<GameConfig, ParseError> {
+    let malformed = || ParseError::Malformed(line.to_string());
+    let mut fields = line
+        .strip_prefix("config ")
+        .ok_or_else(malformed)?
+        .split_whitespace();
+    let flag = |field: Option<&str>| match field {
+        Some("0") => Ok(false),
+        Some("1") => Ok(true),
+        _ => Err(malformed()),
+    };
+
+    let board_dim = fields
+        .next()
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(malformed)?;
+    let must_cover = flag(fields.next())?;
+    let monominoes_first = flag(fields.next())?;
+    let voluntary_pass = flag(fields.next())?;
+    let pie_rule = flag(fields.next())?;
+    let turn_seconds = match fields.next().ok_or_else(malformed)? {
+        "-" => None,
+        seconds => Some(seconds.parse().map_err(|_| malformed())?),
+    };
+    let draft_seed = match fields.next().ok_or_else(malformed)? {
+        "-" => None,
+        seed => Some(seed.parse().map_err(|_| malformed())?),
+    };
+    let three_player_neutral = flag(fields.next())?;
+    let solitaire = flag(fields.next())?;
+    let pieces_per_turn = fields
+        .next()
+        .and_then(|n| n.parse().ok())
+        .filter(|&n| n > 0)
+        .ok_or_else(malformed)?;
+    let seating = if flag(fields.next())? {
+        Seating::CounterClockwise
+    } else {
+        Seating::Clockwise
+    };
+    let descending_size = version >= 2 && flag(fields.next())?;
+    let start_corners = fields
+        .map(|corner| {
+            let (row, col) = corner.split_once(',')?;
+            let (row, col) = (row.parse().ok()?, col.parse().ok()?);
+            (row < board_dim && col < board_dim).then_some((row, col))
+        })
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(malformed)?;
+
+    Ok(GameConfig::classic()
+        .board_dim(board_dim)
+        .start_corners(start_corners)
+        .first_move_must_cover_corner(must_cover)
+        .allow_monominoes_first(monominoes_first)
+        .allow_voluntary_pass(voluntary_pass)
+        .pie_rule(pie_rule)
+        .draft_mode(draft_seed.is_some())
+        .draft_seed(draft_seed.unwrap_or_default())
+        .three_player_neutral(three_player_neutral)
+        .solitaire(solitaire)
+        .pieces_per_turn(pieces_per_turn)
+        .seating(seating)
+        .descending_size(descending_size)
+        .turn_seconds(turn_seconds))
+}
+
+fn parse_player(line: &str, rest: &str, config: &GameConfig) -> Result<Player, ParseError> {
+    let malformed = || ParseError::Malformed(line.to_string());
+    let mut fields = rest.split_whitespace();
+    let color = fields
+        .next()
+        .and_then(|c| c.chars().next())
+        .and_then(TileColor::from_char)
+        .filter(|c| !matches!(c, TileColor::Empty | TileColor::Wall))
+        .ok_or_else(malformed)?;
+    let parse_piece = |field: &str| match field.parse::<usize>() {
+        Ok(piece_id) if piece_id < config.pieces.len() => Ok(piece_id),
+        _ => Err(malformed()),
+    };
+
+    let mut player = Player::new(color, config.pieces.len());
+    (player.is_bot, player.difficulty) = match fields.next().ok_or_else(malformed)? {
+        "human" => (false, Difficulty::default()),
+        "easy" => (true, Difficulty::Easy),
+        "medium" => (true, Difficulty::Medium),
+        "hard" => (true, Difficulty::Hard),
+        "neutral" => {
+            player.is_neutral = true;
+            (false, Difficulty::default())
+        }
+        _ => return Err(malformed()),
+    };
+    player.last_piece = match fields.next().ok_or_else(malformed)? {
+        "-" => None,
+        field => Some(parse_piece(field)?),
+    };
+    player.remaining_pieces.clear();
+    for piece_id in fields {
+        player.remaining_pieces.insert(parse_piece(piece_id)?);
+    }
+    player.pieces_placed = config.pieces.len() - player.remaining_pieces.len();
+    Ok(player)
+}
+
+/// Reads the rest of a `move` line, or `None` if it's not a move anyone at the table could
+/// have made.
+fn parse_move(rest: &str, seats: usize, config: &GameConfig) -> Option<(usize, Move)> {
+    let mut fields = rest.split_whitespace();
+    let mut number = || fields.next()?.parse::<usize>().ok();
+    let seat = number().filter(|&seat| seat < seats)?;
+    let piece_id = number().filter(|&id| id < config.pieces.len())?;
+    let flipped = match number()? {
+        0 => false,
+        1 => true,
+        _ => return None,
+    };
+    let rotation = number().filter(|&turns| turns < 4)? as u8;
+    let (col, row) = fields.next()?.split_once(',')?;
+    let corner = ivec2(col.parse().ok()?, row.parse().ok()?);
+    if fields.next().is_some() {
+        return None;
+    }
+    let mv = Move {
+        piece_id,
+        orientation: Orientation::new(flipped, rotation),
+        corner,
+    };
+    // Off the edge would mean outlines drawn into the wall or past it.
+    let dim = config.board_dim as i32;
+    let on_board = piece::cells(&mv.shape(&config.pieces)).all(|(dr, dc)| {
+        (0..dim).contains(&(corner.y + dr as i32)) && (0..dim).contains(&(corner.x + dc as i32))
+    });
+    on_board.then_some((seat, mv))
+}
+
+/// What went wrong loading a saved game.
+#[derive(Debug)]
+pub enum LoadError {
+    /// Couldn't read the save at all.
+    Io(io::Error),
+    /// Read it, but it's not a game.
+    Parse(ParseError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) if e.kind() == io::ErrorKind::NotFound => f.write_str("no saved game"),
+            Self::Io(e) => write!(f, "couldn't read the save: {e}"),
+            Self::Parse(e) => write!(f, "the save is broken: {e}"),
+        }
+    }
+}
+
+/// Saves `game_state` in `slot`, replacing whatever was there.
+pub fn save_game(game_state: &GameState, slot: &str) -> io::Result<()> {
+    storage::write(slot, &game_state.to_save_string())
+}
+
+/// Writes `game_state` to `DUMP_SLOT`, and as JSON to `DUMP_JSON_SLOT` with serde.
+pub fn dump_game(game_state: &GameState) -> io::Result<()> {
+    save_game(game_state, DUMP_SLOT)?;
+    #[cfg(feature = "serde")]
+    storage::write(DUMP_JSON_SLOT, &game_state.to_json())?;
+    Ok(())
+}
+
+/// Picks up the game saved in `slot`.
+pub fn load_game(slot: &str) -> Result<GameState, LoadError> {
+    let save = storage::read(slot).map_err(LoadError::Io)?;
+    GameState::from_save_string(&save).map_err(LoadError::Parse)
+}
+
+/// The player's key bindings. The first time, that's the usual keys, which get written out
+/// so there's a file to edit. A file that can't be read gets the usual keys too.
+pub fn load_bindings() -> KeyBindings {
+    match storage::read(KEYS_SLOT) {
+        Ok(text) => KeyBindings::from_bindings_string(&text).unwrap_or_else(|e| {
+            eprintln!("Ignoring the key bindings: {e}");
+            KeyBindings::new()
+        }),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            let bindings = KeyBindings::new();
+            if let Err(e) = storage::write(KEYS_SLOT, &bindings.to_bindings_string()) {
+                eprintln!("Couldn't write out the key bindings: {e}");
+            }
+            bindings
+        }
+        Err(e) => {
+            eprintln!("Couldn't read the key bindings: {e}");
+            KeyBindings::new()
+        }
+    }
+}
+
+#[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+mod storage {
+    use std::{
+        fs, io,
+        path::{Path, PathBuf},
+    };
+
+    pub fn write(slot: &str, contents: &str) -> io::Result<()> {
+        write_in(Path::new(super::SAVES_DIR), slot, contents)
+    }
+
+    pub fn read(slot: &str) -> io::Result<String> {
+        read_in(Path::new(super::SAVES_DIR), slot)
+    }
+
+    fn path_in(dir: &Path, slot: &str) -> PathBuf {
+        dir.join(slot).with_extension("blorus")
+    }
+
+    pub(super) fn write_in(dir: &Path, slot: &str, contents: &str) -> io::Result<()> {
+        fs::create_dir_all(dir)?;
+        fs::write(path_in(dir, slot), contents)
+    }
+
+    pub(super) fn read_in(dir: &Path, slot: &str) -> io::Result<String> {
+        fs::read_to_string(path_in(dir, slot))
+    }
+}
+
+/// There's no filesystem in the browser, so saves go in local storage instead. The page has
+/// to load `web/blorus_storage.js` alongside miniquad's loader for these to exist.
+#[cfg(any(target_arch = "wasm32", target_arch = "wasm64"))]
+mod storage {
+    use std::io;
+
+    extern "C" {
+        fn blorus_storage_set(key: *const u8, key_len: usize, val: *const u8, val_len: usize);
+        /// Copies as much of the value as fits into `buf` and returns its full length,
+        /// or -1 if there's nothing under `key`.
+        fn blorus_storage_get(key: *const u8, key_len: usize, buf: *mut u8, buf_len: usize) -> i32;
+    }
+
+    fn key(slot: &str) -> String {
+        format!("{}/{slot}", super::SAVES_DIR)
+    }
+
+    pub fn write(slot: &str, contents: &str) -> io::Result<()> {
+        let key = key(slot);
+        unsafe { blorus_storage_set(key.as_ptr(), key.len(), contents.as_ptr(), contents.len()) };
+        Ok(())
+    }
+
+    pub fn read(slot: &str) -> io::Result<String> {
+        let key = key(slot);
+        // Ask how big it is first, then go back for the whole thing.
+        let len = unsafe { blorus_storage_get(key.as_ptr(), key.len(), std::ptr::null_mut(), 0) };
+        if len < 0 {
+            return Err(io::ErrorKind::NotFound.into());
+        }
+        let mut buf = vec![0u8; len as usize];
+        unsafe { blorus_storage_get(key.as_ptr(), key.len(), buf.as_mut_ptr(), buf.len()) };
+        String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai;
+    use macroquad::prelude::*;
+
+    #[test]
+    fn save_round_trip() {
+        let mut game_state = GameState::new(3);
+        // Let the bot get the game going so there's something on the board.
+        for _ in 0..5 {
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            game_state.select_piece(Some(mv.piece_id));
+            game_state.piece_buffer = mv.shape(&game_state.config.pieces);
+            game_state.place_piece(mv.corner).unwrap();
+            game_state.end_turn();
+        }
+        game_state.pass_counter = 1;
+        game_state.config.turn_seconds = Some(45);
+        game_state.config.pie_rule = true;
+        game_state.config.seating = Seating::CounterClockwise;
+        game_state.config.descending_size = true;
+        game_state.players[1].name = Some("Grace H.".to_string());
+        game_state.players[0].forfeited = true;
+
+        let save = game_state.to_save_string();
+        let mut loaded = GameState::from_save_string(&save).expect("We just wrote this.");
+        assert_eq!(loaded.board, game_state.board);
+        assert_eq!(loaded.current_player, game_state.current_player);
+        assert_eq!(loaded.pass_counter, 1);
+        assert_eq!(loaded.config, game_state.config);
+        for (a, b) in loaded.players.iter().zip(&game_state.players) {
+            assert_eq!(a.color, b.color);
+            assert_eq!(a.remaining_pieces, b.remaining_pieces);
+            assert_eq!(a.last_piece, b.last_piece);
+            assert_eq!(a.pieces_placed, b.pieces_placed);
+            assert_eq!(a.name, b.name);
+            assert_eq!(a.forfeited, b.forfeited);
+        }
+        assert_eq!(loaded.to_save_string(), save);
+        // Who put down which piece comes back too, so outlines survive a reload.
+        assert_eq!(loaded.moves().len(), 5);
+        assert_eq!(loaded.moves(), game_state.moves());
+        let cheat = save.replacen("move 0 ", "move 9 ", 1);
+        assert!(matches!(
+            GameState::from_save_string(&cheat),
+            Err(ParseError::Malformed(line)) if line.starts_with("move 9 ")
+        ));
+        let first_move = save.lines().find(|line| line.starts_with("move ")).unwrap();
+        let (fields, _) = first_move.rsplit_once(' ').unwrap();
+        let off_board = save.replacen(first_move, &format!("{fields} -5,-5"), 1);
+        assert!(matches!(
+            GameState::from_save_string(&off_board),
+            Err(ParseError::Malformed(line)) if line.ends_with(" -5,-5")
+        ));
+
+        // Both games should agree on what's legal.
+        for piece_id in [1, 5, 12, 20] {
+            game_state.select_piece(Some(piece_id));
+            loaded.select_piece(Some(piece_id));
+            for row in 0..20 {
+                for col in 0..20 {
+                    let corner = ivec2(col, row);
+                    let expected = game_state
+                        .check_bounds_and_recenter(corner)
+                        .map(|c| game_state.valid_move(c + IVec2::ONE));
+                    let actual = loaded
+                        .check_bounds_and_recenter(corner)
+                        .map(|c| loaded.valid_move(c + IVec2::ONE));
+                    assert_eq!(expected, actual);
+                }
+            }
+        }
+
+        // Drawn pieces come back in the same order, since they're shuffled from the seed.
+        let config = GameConfig::classic().draft_mode(true).draft_seed(1234);
+        let drafted = GameState::with_players(Player::default_order(2), config);
+        let loaded = GameState::from_save_string(&drafted.to_save_string()).unwrap();
+        assert_eq!(loaded.config, drafted.config);
+        assert_eq!(loaded.players[1].draw_order, drafted.players[1].draw_order);
+
+        // The neutral color comes back as itself, not as a fourth player.
+        let config = GameConfig::classic().three_player_neutral(true);
+        let mut shared = GameState::with_players(Player::default_order(3), config);
+        shared.neutral_turns = 2;
+        let loaded = GameState::from_save_string(&shared.to_save_string()).unwrap();
+        assert_eq!(loaded.players.len(), 4);
+        assert!(loaded.players[3].is_neutral);
+        assert_eq!(loaded.neutral_turns, 2);
+    }
+
+    #[cfg(not(any(target_arch = "wasm32", target_arch = "wasm64")))]
+    #[test]
+    fn save_file_round_trip() {
+        let mut players = Player::default_order(3);
+        players[1].is_bot = true;
+        players[2].is_bot = true;
+        players[2].difficulty = Difficulty::Hard;
+        let mut game_state = GameState::with_players(players, GameConfig::classic());
+        for _ in 0..7 {
+            let mv = ai::choose_move(&game_state).expect("Early game has room.");
+            game_state.apply_move(&mv);
+        }
+
+        let dir = std::env::temp_dir().join(format!("blorus-saves-{}", std::process::id()));
+        storage::write_in(&dir, "mid-game", &game_state.to_save_string()).unwrap();
+        let save = storage::read_in(&dir, "mid-game").unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let loaded = GameState::from_save_string(&save).unwrap();
+        assert_eq!(loaded.to_save_string(), game_state.to_save_string());
+        assert_eq!(loaded.board, game_state.board);
+        assert_eq!(loaded.current_player, 1);
+        for (a, b) in loaded.players.iter().zip(&game_state.players) {
+            assert_eq!((a.is_bot, a.difficulty), (b.is_bot, b.difficulty));
+        }
+
+        let missing = storage::read_in(&dir, "mid-game").unwrap_err();
+        assert_eq!(missing.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn reject_garbage() {
+        assert_eq!(
+            GameState::from_save_string("").unwrap_err(),
+            ParseError::Missing("header")
+        );
+
+        let save = GameState::new(2).to_save_string();
+        let broken = save.replacen("#.", "#?", 1);
+        assert_eq!(
+            GameState::from_save_string(&broken).unwrap_err(),
+            ParseError::UnknownTile('?')
+        );
+
+        let short = save.lines().take(8).collect::<Vec<_>>().join("\n");
+        assert!(GameState::from_save_string(&short).is_err());
+
+        // Too big to play on is an error, not a crash.
+        let (head, _) = save.split_once("board\n").unwrap();
+        let mut huge = head.replacen("config 20 ", "config 58 ", 1) + "board\n";
+        for row in 0..60 {
+            let inside = if row == 0 || row == 59 { "#" } else { "." };
+            huge += &format!("#{}#\n", inside.repeat(58));
+        }
+        assert_eq!(
+            GameState::from_save_string(&huge).unwrap_err(),
+            ParseError::Setup(SetupError::BoardTooBig(58))
+        );
+
+        let newer = save.replacen(&format!("{HEADER} {VERSION}"), "blorus 99", 1);
+        assert_eq!(
+            GameState::from_save_string(&newer).unwrap_err(),
+            ParseError::TooNew(99)
+        );
+    }
+
+    #[test]
+    fn old_saves_still_load() {
+        let game_state = GameState::new(2);
+        let save = game_state.to_save_string();
+        // Before versions, there was no header number and no `descending_size` flag just
+        // ahead of the corners.
+        let config_line = save.lines().nth(1).unwrap();
+        assert!(config_line.contains(" 0 19,19"));
+        let old = save
+            .replacen(&format!("{HEADER} {VERSION}"), HEADER, 1)
+            .replacen(" 0 19,19", " 19,19", 1);
+        let loaded = GameState::from_save_string(&old).unwrap();
+        assert_eq!(loaded.config, game_state.config);
+        assert_eq!(loaded.to_save_string(), save);
+    }
+}
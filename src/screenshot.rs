@@ -0,0 +1,106 @@
+//! Pictures of the board to show off after the game, drawn without the window so they come
+//! out the same no matter how big it is.
+
+use macroquad::prelude::*;
+use std::io;
+
+use crate::logic::{GameState, TileColor};
+
+/// How many pixels wide each cell is in `save_board_png`.
+pub const DEFAULT_CELL_SIZE: u16 = 24;
+
+const GRID_LINE_COLOR: Color = DARKGRAY;
+
+/// Draws the play area into an image, `cell_size` pixels per cell, with thin lines
+/// between the cells.
+pub fn render_board_to_image(game_state: &GameState, cell_size: u16) -> Image {
+    let board_dim = game_state.play_area_dim();
+    let wall = game_state.wall_thickness();
+    let side = board_dim as u16 * cell_size;
+    let mut image = Image::gen_image_color(side, side, GRAY);
+    let cell_size = cell_size as u32;
+
+    for row in 0..board_dim {
+        for col in 0..board_dim {
+            let tile = game_state.board[row + wall][col + wall];
+            // Empty cells show the board underneath, just like on screen.
+            let fill = match tile {
+                TileColor::Empty | TileColor::Wall => GRAY,
+                _ => tile.into(),
+            };
+            let (left, top) = (col as u32 * cell_size, row as u32 * cell_size);
+            for y in top..top + cell_size {
+                for x in left..left + cell_size {
+                    let on_grid_line = x == left || y == top;
+                    image.set_pixel(x, y, if on_grid_line { GRID_LINE_COLOR } else { fill });
+                }
+            }
+        }
+    }
+
+    // Close off the right and bottom edges, which no cell's top or left line covers.
+    let last = side as u32 - 1;
+    for i in 0..side as u32 {
+        image.set_pixel(last, i, GRID_LINE_COLOR);
+        image.set_pixel(i, last, GRID_LINE_COLOR);
+    }
+
+    image
+}
+
+/// Saves the board to `path` as a PNG. Macroquad's own `export_png` saves it upside down and
+/// panics if it can't write, so this goes through `image` instead.
+pub fn save_board_png(game_state: &GameState, path: &str) -> io::Result<()> {
+    let board = render_board_to_image(game_state, DEFAULT_CELL_SIZE);
+    let (width, height) = (board.width() as u32, board.height() as u32);
+    image::save_buffer(path, &board.bytes, width, height, image::ColorType::Rgba8).map_err(|e| {
+        match e {
+            image::ImageError::IoError(e) => e,
+            e => io::Error::other(e),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn picture_of_the_board() {
+        let mut game_state = GameState::new(2);
+        // The monomino in Blue's corner, bottom right.
+        game_state.select_piece(Some(0));
+        assert!(game_state.try_advance_turn(19, 19));
+
+        let image = render_board_to_image(&game_state, 10);
+        assert_eq!((image.width(), image.height()), (200, 200));
+
+        // Pixels only hold bytes, so compare colors as bytes too.
+        let bytes = |color: Color| -> [u8; 4] { color.into() };
+        let pixel = |x: u32, y: u32| bytes(image.get_pixel(x, y));
+        let center_of = |row: u32, col: u32| pixel(col * 10 + 5, row * 10 + 5);
+        assert_eq!(center_of(19, 19), bytes(BLUE));
+        assert_eq!(center_of(0, 0), bytes(GRAY));
+        assert_eq!(pixel(50, 55), bytes(GRID_LINE_COLOR));
+        assert_eq!(pixel(199, 0), bytes(GRID_LINE_COLOR));
+
+        // The file comes out the right way up, with Blue's corner at the bottom right.
+        let path = std::env::temp_dir().join(format!("blorus-{}.png", std::process::id()));
+        save_board_png(&game_state, path.to_str().unwrap()).unwrap();
+        let saved = image::open(&path).unwrap().to_rgba8();
+        std::fs::remove_file(&path).unwrap();
+        let size = DEFAULT_CELL_SIZE as u32;
+        assert_eq!(saved.dimensions(), (20 * size, 20 * size));
+        let saved_center_of = |row: u32, col: u32| {
+            saved
+                .get_pixel(col * size + size / 2, row * size + size / 2)
+                .0
+        };
+        assert_eq!(saved_center_of(19, 19), bytes(BLUE));
+        assert_eq!(saved_center_of(0, 0), bytes(GRAY));
+
+        // Somewhere that can't be written to is an error, not a crash.
+        let nowhere = std::env::temp_dir().join("no-such-dir").join("blorus.png");
+        assert!(save_board_png(&game_state, nowhere.to_str().unwrap()).is_err());
+    }
+}
@@ -0,0 +1,280 @@
+//! The game loop doesn't care whether everyone's at one keyboard or spread across the
+//! internet -- it just talks to a `Session`, which decides who gets to change the game.
+
+use std::fmt;
+
+use crate::logic::{GameState, Move, MoveError};
+
+/// Why a move didn't make it onto the board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubmitError {
+    /// Nobody gets to move once the game is over.
+    GameOver,
+    /// The move breaks the rules.
+    Illegal(MoveError),
+    /// We're still waiting to hear back from the host about the last move.
+    AwaitingHost,
+}
+
+impl fmt::Display for SubmitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::GameOver => f.write_str("The game is already over."),
+            Self::Illegal(e) => write!(f, "That piece {e}."),
+            Self::AwaitingHost => f.write_str("Still waiting on the host."),
+        }
+    }
+}
+
+/// A game in progress, wherever its players are.
+pub trait Session {
+    fn current_state(&self) -> &GameState;
+
+    /// For picking up, turning and aiming pieces. Anything that changes whose turn it is
+    /// goes through `submit_move` or `pass_turn` instead.
+    fn current_state_mut(&mut self) -> &mut GameState;
+
+    /// Plays `mv` for the current player, if it's legal.
+    fn submit_move(&mut self, mv: Move) -> Result<(), SubmitError>;
+
+    /// Skips the current player's turn.
+    fn pass_turn(&mut self);
+
+    /// Lets the second player take the first player's opening, under the pie rule.
+    /// Only call this when `GameState::pie_swap_available` says so.
+    fn swap_seats(&mut self);
+
+    /// Takes `player_idx` out of the game for good (see `GameState::forfeit`).
+    fn forfeit(&mut self, player_idx: usize);
+
+    /// Starts over with the same players (see `GameState::rematch`), and says whether it did.
+    /// Only whoever holds the real game gets to.
+    fn rematch(&mut self, rotate_seats: bool) -> bool;
+
+    /// Takes back the last move or pass we made, and says whether that went anywhere. Over
+    /// the network, everyone else still playing has to agree first, so this only asks.
+    fn request_undo(&mut self) -> bool;
+
+    /// Whose move someone wants to take back, if we get a say in it and haven't had it yet.
+    fn undo_vote_pending(&self) -> Option<usize> {
+        None
+    }
+
+    /// Lets the undo in `undo_vote_pending` through or not. One "no" is enough to stop it.
+    fn vote_undo(&mut self, _approve: bool) {}
+
+    /// Whether our last `request_undo` went through, once everyone's had their say. Only
+    /// hands the answer over once.
+    fn take_undo_answer(&mut self) -> Option<bool> {
+        None
+    }
+
+    /// Catches up on anything the other end has sent. Call this every frame.
+    fn poll_remote(&mut self);
+
+    /// Whether `player_idx` is playing from somewhere else, so we shouldn't play for them.
+    fn is_remote(&self, _player_idx: usize) -> bool {
+        false
+    }
+
+    /// Whether our clock is the one that counts, so we pass for whoever runs out of time.
+    /// Clients wait for the host to do it.
+    fn keeps_time(&self) -> bool {
+        true
+    }
+}
+
+/// Checks `mv` against `game_state` the same way for every kind of session.
+pub fn check_submission(game_state: &GameState, mv: &Move) -> Result<(), SubmitError> {
+    if game_state.is_game_over() {
+        return Err(SubmitError::GameOver);
+    }
+    game_state.check_move(mv).map_err(SubmitError::Illegal)
+}
+
+/// The game from before each move and pass, for taking them back.
+#[derive(Debug, Clone, Default)]
+pub struct UndoHistory {
+    /// Who was up, and the game just before they moved. Newest last.
+    before: Vec<(usize, GameState)>,
+}
+
+impl UndoHistory {
+    /// Call this right before the current player moves or passes.
+    pub fn record(&mut self, game_state: &GameState) {
+        self.before
+            .push((game_state.current_player, game_state.clone()));
+    }
+
+    /// Who made the last move or pass there is to take back.
+    pub fn last_mover(&self) -> Option<usize> {
+        self.before.last().map(|&(mover, _)| mover)
+    }
+
+    /// Puts `game_state` back how it was before the last move or pass, and says whether
+    /// there was one.
+    pub fn undo_last(&mut self, game_state: &mut GameState) -> bool {
+        let Some((_, mut before)) = self.before.pop() else {
+            return false;
+        };
+        // Anything that was waiting to be drained back then has been dealt with already.
+        before.drain_events();
+        // Their time was already running out back then. They get a whole turn again.
+        before.restart_turn_clock();
+        *game_state = before;
+        true
+    }
+
+    /// Forgets everything. Seat swaps and forfeits can't be taken back, so nothing from
+    /// before them can either.
+    pub fn clear(&mut self) {
+        self.before.clear();
+    }
+}
+
+/// Everyone shares one screen.
+pub struct LocalSession {
+    game_state: GameState,
+    history: UndoHistory,
+}
+
+impl LocalSession {
+    pub fn new(game_state: GameState) -> Self {
+        Self {
+            game_state,
+            history: UndoHistory::default(),
+        }
+    }
+}
+
+impl Session for LocalSession {
+    fn current_state(&self) -> &GameState {
+        &self.game_state
+    }
+
+    fn current_state_mut(&mut self) -> &mut GameState {
+        &mut self.game_state
+    }
+
+    fn submit_move(&mut self, mv: Move) -> Result<(), SubmitError> {
+        check_submission(&self.game_state, &mv)?;
+        self.history.record(&self.game_state);
+        self.game_state.apply_move(&mv);
+        Ok(())
+    }
+
+    fn pass_turn(&mut self) {
+        self.history.record(&self.game_state);
+        self.game_state.pass_turn();
+    }
+
+    fn swap_seats(&mut self) {
+        self.history.clear();
+        self.game_state.swap_seats();
+    }
+
+    fn forfeit(&mut self, player_idx: usize) {
+        self.history.clear();
+        self.game_state.forfeit(player_idx);
+    }
+
+    fn rematch(&mut self, rotate_seats: bool) -> bool {
+        self.history.clear();
+        self.game_state = self.game_state.rematch(rotate_seats);
+        true
+    }
+
+    /// Nobody to ask, so it just happens. Against the computer, its replies get taken back
+    /// too, or it'd only play the same thing again. The same goes for passes nobody had a
+    /// choice about.
+    fn request_undo(&mut self) -> bool {
+        let mut undone = false;
+        while let Some(mover) = self.history.last_mover() {
+            let bot = self.game_state.players[mover].is_bot;
+            undone |= self.history.undo_last(&mut self.game_state);
+            if !bot && self.game_state.can_make_move() {
+                break;
+            }
+        }
+        undone
+    }
+
+    // Nobody else to hear from.
+    fn poll_remote(&mut self) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::piece::Orientation;
+    use macroquad::prelude::*;
+
+    #[test]
+    fn local_session_checks_moves() {
+        let mut session = LocalSession::new(GameState::new(2));
+        // The square has to cover Blue's corner, bottom right.
+        let off_corner = Move {
+            piece_id: 7,
+            orientation: Orientation::default(),
+            corner: ivec2(10, 10),
+        };
+        assert_eq!(
+            session.submit_move(off_corner),
+            Err(SubmitError::Illegal(MoveError::NoDiagonalContact))
+        );
+        assert_eq!(session.current_state().current_player, 0);
+
+        let mv = session
+            .current_state()
+            .enumerate_legal_moves()
+            .into_iter()
+            .find(|mv| mv.piece_id == 7)
+            .expect("The square fits in the corner.");
+        assert_eq!(session.submit_move(mv), Ok(()));
+        assert_eq!(session.current_state().current_player, 1);
+
+        // Blue doesn't get to put the same square down twice.
+        session.pass_turn();
+        let again = session
+            .current_state()
+            .enumerate_legal_moves()
+            .into_iter()
+            .next()
+            .expect("Blue still has room.");
+        assert_eq!(
+            session.submit_move(Move {
+                piece_id: 7,
+                ..again
+            }),
+            Err(SubmitError::Illegal(MoveError::AlreadyPlayed))
+        );
+
+        // Nor does anyone get to move after the game is over.
+        let mut session = LocalSession::new(GameState::new(2));
+        let opener = session.current_state().enumerate_legal_moves()[0];
+        session.pass_turn();
+        session.pass_turn();
+        assert_eq!(session.submit_move(opener), Err(SubmitError::GameOver));
+    }
+
+    #[test]
+    fn undone_turns_get_a_fresh_clock() {
+        let config = crate::config::GameConfig::classic().turn_seconds(Some(30));
+        let game_state = GameState::with_players(crate::logic::Player::default_order(2), config);
+        let mut session = LocalSession::new(game_state);
+
+        // Blue thinks for 20 seconds before moving, then takes it back.
+        assert!(!session.current_state_mut().turn_expired(100.));
+        let opener = session.current_state().enumerate_legal_moves()[0];
+        assert_eq!(session.submit_move(opener), Ok(()));
+        assert!(!session.current_state_mut().turn_expired(120.));
+        assert!(session.request_undo());
+        assert_eq!(session.current_state().current_player, 0);
+
+        // The clock starts over from the undo, not from when Blue first sat down.
+        assert_eq!(session.current_state().turn_time_left(140.), None);
+        assert!(!session.current_state_mut().turn_expired(140.));
+        assert!(!session.current_state_mut().turn_expired(165.));
+        assert!(session.current_state_mut().turn_expired(170.));
+    }
+}
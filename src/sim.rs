@@ -0,0 +1,115 @@
+//! Plays whole games without a window, for figuring out which seats or variants are unfair.
+//!
+//! Something like `blorus sim 1000` runs a thousand games of bots against each other and
+//! prints how each seat did on average. `blorus sim 1 --moves` also writes down every move,
+//! ready for `blorus replay`.
+
+use crate::{
+    config::GameConfig,
+    logic::{GameState, Move, Player},
+};
+
+/// Decides what the current player does. `None` means they pass.
+pub type Strategy = fn(&GameState) -> Option<Move>;
+
+/// Plays a full game on `config` with one seat per strategy, in turn order, and returns each
+/// seat's final score as `(seat, score)`. With `log_moves`, the game's moves go to stderr
+/// too, out of the way of anything printed about the scores.
+pub fn play_out(
+    config: GameConfig,
+    strategies: Vec<Strategy>,
+    log_moves: bool,
+) -> Vec<(usize, i32)> {
+    let game_state = simulate(config, &strategies);
+    if log_moves {
+        let notation: Vec<_> = game_state
+            .moves()
+            .iter()
+            .map(|(seat, mv)| {
+                mv.to_notation(game_state.players[*seat].color, &game_state.config.pieces)
+            })
+            .collect();
+        eprintln!("{}", notation.join(" "));
+    }
+    (0..strategies.len())
+        .map(|seat| (seat, game_state.score(seat)))
+        .collect()
+}
+
+/// Like `play_out`, but hands back the finished game.
+pub fn simulate(config: GameConfig, strategies: &[Strategy]) -> GameState {
+    let players = Player::default_order(strategies.len());
+    let mut game_state = GameState::with_players(players, config);
+    while !game_state.is_game_over() {
+        match strategies[game_state.controller()](&game_state) {
+            Some(mv) => game_state.apply_move(&mv),
+            None => game_state.pass_turn(),
+        }
+    }
+    game_state
+}
+
+/// Plays `games` four-player games of greedy bots and prints each seat's average score.
+pub fn report(games: usize, log_moves: bool) {
+    let mut totals = [0i64; 4];
+    for _ in 0..games {
+        let strategies: Vec<Strategy> = vec![crate::ai::choose_move; 4];
+        for (seat, score) in play_out(GameConfig::classic(), strategies, log_moves) {
+            totals[seat] += score as i64;
+        }
+    }
+
+    for (seat, total) in totals.iter().enumerate() {
+        let average = *total as f64 / games.max(1) as f64;
+        println!("Seat {}: {average:.2}", seat + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ai;
+
+    #[test]
+    fn greedy_bots_finish_a_game() {
+        let strategies: Vec<Strategy> = vec![ai::choose_move; 4];
+        let game_state = simulate(GameConfig::classic(), &strategies);
+        assert!(game_state.is_game_over());
+
+        for (seat, player) in game_state.players.iter().enumerate() {
+            // Everything that isn't in hand is on the board.
+            let on_board = game_state
+                .board
+                .iter()
+                .flatten()
+                .filter(|&&tile| tile == player.color)
+                .count();
+            let pieces = &game_state.config.pieces;
+            let in_hand: usize = player
+                .remaining_pieces
+                .iter()
+                .map(|piece_id| pieces.size(piece_id))
+                .sum();
+            let whole_set: usize = (0..pieces.len())
+                .map(|piece_id| pieces.size(piece_id))
+                .sum();
+            assert_eq!(on_board + in_hand, whole_set);
+
+            let score = game_state.score(seat);
+            if in_hand > 0 {
+                assert_eq!(score, -(in_hand as i32));
+            } else {
+                assert!(score >= 15);
+            }
+        }
+    }
+
+    #[test]
+    fn everyone_passing_still_ends() {
+        let strategies: Vec<Strategy> = vec![|_| None; 2];
+        assert_eq!(
+            play_out(GameConfig::classic(), strategies, false),
+            [(0, -89), (1, -89)]
+        );
+    }
+}
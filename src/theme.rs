@@ -0,0 +1,95 @@
+//! The colors and line widths the board and everything around it are drawn with.
+
+use macroquad::prelude::*;
+
+/// Everything about the game screen that isn't a player's color.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Theme {
+    /// Behind everything.
+    pub background: Color,
+    /// The board itself, which shows through wherever there's no tile.
+    pub board_fill: Color,
+    /// Grid lines, borders and outlines.
+    pub grid: Color,
+    /// Anything written on the screen.
+    pub text: Color,
+    /// How wide the lines between cells are, in pixels.
+    pub grid_thickness: f32,
+    /// How wide the borders around the board and play area are, in pixels.
+    pub border_thickness: f32,
+}
+
+impl Theme {
+    /// The look the game has always had.
+    pub fn light() -> Self {
+        Self {
+            background: BEIGE,
+            board_fill: GRAY,
+            grid: BLACK,
+            text: BLACK,
+            grid_thickness: 2.,
+            border_thickness: 4.,
+        }
+    }
+
+    /// Easier on the eyes in a dark room.
+    pub fn dark() -> Self {
+        Self {
+            background: color_u8!(0x1e, 0x1e, 0x24, 0xff),
+            board_fill: color_u8!(0x3a, 0x3a, 0x44, 0xff),
+            grid: color_u8!(0x0e, 0x0e, 0x12, 0xff),
+            text: color_u8!(0xe8, 0xe8, 0xe8, 0xff),
+            ..Self::light()
+        }
+    }
+
+    pub fn is_dark(&self) -> bool {
+        *self == Self::dark()
+    }
+
+    /// The other theme, for the toggle on the setup screen.
+    pub fn next(self) -> Self {
+        if self.is_dark() {
+            Self::light()
+        } else {
+            Self::dark()
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::light()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Roughly how bright `color` looks, from 0 to 1.
+    fn brightness(color: Color) -> f32 {
+        0.3 * color.r + 0.59 * color.g + 0.11 * color.b
+    }
+
+    #[test]
+    fn themes_keep_text_readable() {
+        // Nobody should notice the default.
+        let light = Theme::default();
+        assert_eq!(light.background, BEIGE);
+        assert_eq!(light.board_fill, GRAY);
+        assert_eq!(light.grid, BLACK);
+        assert_eq!(light.text, BLACK);
+        assert_eq!(light.grid_thickness, 2.);
+        assert_eq!(light.border_thickness, 4.);
+
+        let dark = Theme::dark();
+        assert!(dark.is_dark() && !light.is_dark());
+        assert_eq!(light.next(), dark);
+        assert_eq!(dark.next(), light);
+        assert!(brightness(dark.background) < brightness(light.background));
+        for theme in [light, dark] {
+            assert!((brightness(theme.text) - brightness(theme.background)).abs() > 0.5);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+//! How a single tile gets drawn, on the board and in the tray alike.
+
+use macroquad::prelude::*;
+use smallvec::SmallVec;
+
+/// The shape every tile is drawn in.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TileStyle {
+    /// Plain squares. One rectangle a tile, so it's the cheapest to draw.
+    #[default]
+    Flat,
+    /// Squares with their corners cut off and a little bevel, so the seams between
+    /// pieces of the same color are easier to see.
+    Rounded,
+}
+
+impl TileStyle {
+    /// The other style, for the toggle on the setup screen.
+    pub fn next(self) -> Self {
+        match self {
+            Self::Flat => Self::Rounded,
+            Self::Rounded => Self::Flat,
+        }
+    }
+}
+
+/// One shape for macroquad to draw.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Primitive {
+    Rect(Rect, Color),
+    Triangle(Vec2, Vec2, Vec2, Color),
+    /// From, to, thickness.
+    Line(Vec2, Vec2, f32, Color),
+}
+
+impl Primitive {
+    fn draw(self) {
+        match self {
+            Self::Rect(Rect { x, y, w, h }, color) => draw_rectangle(x, y, w, h, color),
+            Self::Triangle(a, b, c, color) => draw_triangle(a, b, c, color),
+            Self::Line(a, b, thickness, color) => draw_line(a.x, a.y, b.x, b.y, thickness, color),
+        }
+    }
+}
+
+/// The shapes that make up a tile filling `rect`. Macroquad has no rounded rectangles, so
+/// `Rounded` tiles are a cross of rectangles with a triangle filling in each corner.
+pub fn tile_primitives(style: TileStyle, rect: Rect, color: Color) -> SmallVec<[Primitive; 9]> {
+    let Rect { x, y, w, h } = rect;
+    match style {
+        TileStyle::Flat => smallvec::smallvec![Primitive::Rect(rect, color)],
+        TileStyle::Rounded => {
+            let r = 0.2 * w.min(h);
+            let (right, bottom) = (x + w, y + h);
+            let bevel = (0.08 * w.min(h)).max(1.);
+            smallvec::smallvec![
+                Primitive::Rect(Rect::new(x + r, y, w - 2. * r, h), color),
+                Primitive::Rect(Rect::new(x, y + r, r, h - 2. * r), color),
+                Primitive::Rect(Rect::new(right - r, y + r, r, h - 2. * r), color),
+                Primitive::Triangle(vec2(x + r, y), vec2(x, y + r), vec2(x + r, y + r), color),
+                Primitive::Triangle(
+                    vec2(right - r, y),
+                    vec2(right, y + r),
+                    vec2(right - r, y + r),
+                    color
+                ),
+                Primitive::Triangle(
+                    vec2(x, bottom - r),
+                    vec2(x + r, bottom),
+                    vec2(x + r, bottom - r),
+                    color
+                ),
+                Primitive::Triangle(
+                    vec2(right, bottom - r),
+                    vec2(right - r, bottom),
+                    vec2(right - r, bottom - r),
+                    color
+                ),
+                // Lit from above.
+                Primitive::Line(
+                    vec2(x + r, y + 0.5 * bevel),
+                    vec2(right - r, y + 0.5 * bevel),
+                    bevel,
+                    Color::new(1., 1., 1., 0.35)
+                ),
+                Primitive::Line(
+                    vec2(x + r, bottom - 0.5 * bevel),
+                    vec2(right - r, bottom - 0.5 * bevel),
+                    bevel,
+                    Color::new(0., 0., 0., 0.25)
+                ),
+            ]
+        }
+    }
+}
+
+/// Draws a tile of `color` filling `rect`.
+pub fn draw_tile(style: TileStyle, rect: Rect, color: Color) {
+    for primitive in tile_primitives(style, rect, color) {
+        primitive.draw();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn primitives_per_style() {
+        let rect = Rect::new(10., 20., 30., 30.);
+        assert_eq!(
+            tile_primitives(TileStyle::Flat, rect, RED).as_slice(),
+            [Primitive::Rect(rect, RED)]
+        );
+
+        // Three rectangles, four corners and the bevel's two edges.
+        let rounded = tile_primitives(TileStyle::Rounded, rect, RED);
+        assert_eq!(rounded.len(), 9);
+        let count = |pred: fn(&Primitive) -> bool| rounded.iter().filter(|p| pred(p)).count();
+        assert_eq!(count(|p| matches!(p, Primitive::Rect(..))), 3);
+        assert_eq!(count(|p| matches!(p, Primitive::Triangle(..))), 4);
+        assert_eq!(count(|p| matches!(p, Primitive::Line(..))), 2);
+
+        // Nothing spills out of the tile.
+        for primitive in rounded {
+            if let Primitive::Rect(inner, _) = primitive {
+                assert!(rect.contains(inner.point()));
+                assert!(inner.right() <= rect.right() && inner.bottom() <= rect.bottom());
+            }
+        }
+    }
+}
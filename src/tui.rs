@@ -0,0 +1,158 @@
+//! The game in a terminal, for playing (and testing) without a window.
+//!
+//! `blorus --tui 2` starts a two-player game. Each turn, type a move the way the game loop
+//! prints them (`B:L5@r15c15/rot0`), `bot` to let the computer move for you, or `pass`.
+
+use std::{
+    fmt::Write as _,
+    io::{self, BufRead, Write as _},
+};
+
+use crate::{
+    ai,
+    config::GameConfig,
+    logic::{GameEvent, GameState, Move, Player},
+};
+
+/// Writes the game out as text: whose turn it is, how many pieces everyone has left, the
+/// last piece put down, then the board.
+#[derive(Debug, Default, Clone)]
+pub struct TextRenderer {
+    /// The last placement, and who made it.
+    last_move: Option<(usize, Move)>,
+}
+
+impl TextRenderer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keeps track of the last placement. Feed this everything from `GameState::drain_events`.
+    pub fn watch(&mut self, event: &GameEvent) {
+        if let GameEvent::PiecePlaced { player, mv } = *event {
+            self.last_move = Some((player, mv));
+        }
+    }
+
+    pub fn render(&self, game_state: &GameState) -> String {
+        let mut text = String::new();
+        if game_state.is_game_over() {
+            writeln!(text, "Game over").unwrap();
+        } else {
+            let name = game_state.current_player().display_name();
+            writeln!(text, "{name} to move").unwrap();
+        }
+        for (idx, player) in game_state.players.iter().enumerate() {
+            let marker = if idx == game_state.current_player {
+                '>'
+            } else {
+                ' '
+            };
+            writeln!(
+                text,
+                "{marker} {}: {} pieces left",
+                player.display_name(),
+                player.remaining_pieces.len()
+            )
+            .unwrap();
+        }
+        match self.last_move {
+            Some((player, mv)) => {
+                let color = game_state.players[player].color;
+                let notation = mv.to_notation(color, &game_state.config.pieces);
+                writeln!(text, "Last move: {notation}").unwrap();
+            }
+            None => writeln!(text, "Last move: -").unwrap(),
+        }
+        for row in &game_state.board {
+            for tile in row {
+                write!(text, "{tile}").unwrap();
+            }
+            writeln!(text).unwrap();
+        }
+        text
+    }
+}
+
+/// Plays a classic game for `player_count` people, one line of stdin per turn, until it's
+/// over or stdin runs out.
+pub fn play(player_count: usize) {
+    let mut game_state =
+        GameState::with_players(Player::default_order(player_count), GameConfig::classic());
+    let mut renderer = TextRenderer::new();
+    let mut lines = io::stdin().lock().lines();
+    while !game_state.is_game_over() {
+        print!("{}> ", renderer.render(&game_state));
+        io::stdout().flush().unwrap();
+        let Some(Ok(line)) = lines.next() else {
+            return;
+        };
+        match line.trim() {
+            "pass" if game_state.may_pass() => game_state.pass_turn(),
+            "pass" => println!("You can still move!"),
+            "bot" => match ai::choose_move(&game_state) {
+                Some(mv) => game_state.apply_move(&mv),
+                None => game_state.pass_turn(),
+            },
+            notation => match Move::from_notation(notation, &game_state) {
+                Ok((color, _)) if color != game_state.current_player().color => {
+                    println!("It's {:?}'s turn!", game_state.current_player().color);
+                }
+                Ok((_, mv)) => {
+                    if let Err(e) = game_state.try_apply_move(&mv) {
+                        println!("That piece {e}.");
+                    }
+                }
+                Err(e) => println!("{e}"),
+            },
+        }
+        for event in game_state.drain_events() {
+            renderer.watch(&event);
+        }
+    }
+    print!("{}", renderer.render(&game_state));
+    for idx in 0..game_state.players.len() {
+        let player = &game_state.players[idx];
+        println!(
+            "{}: {} points",
+            player.display_name(),
+            game_state.score(idx)
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_a_known_game() {
+        let config = GameConfig::classic()
+            .board_dim(5)
+            .start_corners(vec![(4, 4), (0, 0)]);
+        let mut game_state = GameState::with_players(Player::default_order(2), config);
+        let mut renderer = TextRenderer::new();
+        let (_, mv) = Move::from_notation("B:L3@r3c3/rot0", &game_state).unwrap();
+        game_state.apply_move(&mv);
+        for event in game_state.drain_events() {
+            renderer.watch(&event);
+        }
+
+        assert_eq!(
+            renderer.render(&game_state),
+            "\
+Yellow to move
+  Blue: 20 pieces left
+> Yellow: 21 pieces left
+Last move: B:L3@r3c3/rot0
+#######
+#.....#
+#.....#
+#.....#
+#...B.#
+#...BB#
+#######
+"
+        );
+    }
+}
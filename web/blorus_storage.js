@@ -0,0 +1,29 @@
+// Lets the web build keep saved games in local storage. Load this after miniquad's gl.js.
+
+function blorus_read_string(ptr, len) {
+    return new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+}
+
+function blorus_register_plugin(importObject) {
+    importObject.env.blorus_storage_set = function (key, key_len, val, val_len) {
+        localStorage.setItem(blorus_read_string(key, key_len), blorus_read_string(val, val_len));
+    };
+
+    // Copies as much of the value as fits into `buf` and returns its full length,
+    // or -1 if there's nothing under `key`.
+    importObject.env.blorus_storage_get = function (key, key_len, buf, buf_len) {
+        const value = localStorage.getItem(blorus_read_string(key, key_len));
+        if (value === null) {
+            return -1;
+        }
+        const bytes = new TextEncoder().encode(value);
+        new Uint8Array(wasm_memory.buffer, buf, buf_len).set(bytes.subarray(0, buf_len));
+        return bytes.length;
+    };
+}
+
+miniquad_add_plugin({
+    register_plugin: blorus_register_plugin,
+    name: "blorus_storage",
+    version: 1,
+});
@@ -0,0 +1,70 @@
+// Lets the web build play online over WebSockets. Load this after miniquad's gl.js.
+
+const blorus_sockets = [];
+
+function blorus_ws_read_string(ptr, len) {
+    return new TextDecoder().decode(new Uint8Array(wasm_memory.buffer, ptr, len));
+}
+
+function blorus_ws_register_plugin(importObject) {
+    // Returns a handle for the new socket. Anything sent before it opens waits until it does.
+    importObject.env.blorus_ws_connect = function (url, url_len) {
+        const entry = { socket: null, pending: [], received: [], closed: false };
+        entry.socket = new WebSocket(blorus_ws_read_string(url, url_len));
+        entry.socket.binaryType = "arraybuffer";
+        entry.socket.onopen = function () {
+            for (const bytes of entry.pending) {
+                entry.socket.send(bytes);
+            }
+            entry.pending = [];
+        };
+        entry.socket.onmessage = function (event) {
+            entry.received.push(new Uint8Array(event.data));
+        };
+        entry.socket.onclose = function () {
+            entry.closed = true;
+        };
+        blorus_sockets.push(entry);
+        return blorus_sockets.length - 1;
+    };
+
+    importObject.env.blorus_ws_send = function (handle, data, len) {
+        const entry = blorus_sockets[handle];
+        // Copy it out now, since the memory may have moved by the time it's sent.
+        const bytes = new Uint8Array(wasm_memory.buffer, data, len).slice();
+        if (entry.socket.readyState === WebSocket.CONNECTING) {
+            entry.pending.push(bytes);
+        } else if (entry.socket.readyState === WebSocket.OPEN) {
+            entry.socket.send(bytes);
+        }
+    };
+
+    // Copies as many received bytes as fit into `buf` and returns how many that was,
+    // or -1 if the socket's closed and there's nothing left.
+    importObject.env.blorus_ws_recv = function (handle, buf, buf_len) {
+        const entry = blorus_sockets[handle];
+        if (entry.received.length === 0) {
+            return entry.closed ? -1 : 0;
+        }
+        const out = new Uint8Array(wasm_memory.buffer, buf, buf_len);
+        let copied = 0;
+        while (entry.received.length > 0 && copied < buf_len) {
+            const chunk = entry.received[0];
+            const n = Math.min(chunk.length, buf_len - copied);
+            out.set(chunk.subarray(0, n), copied);
+            copied += n;
+            if (n === chunk.length) {
+                entry.received.shift();
+            } else {
+                entry.received[0] = chunk.subarray(n);
+            }
+        }
+        return copied;
+    };
+}
+
+miniquad_add_plugin({
+    register_plugin: blorus_ws_register_plugin,
+    name: "blorus_websocket",
+    version: 1,
+});